@@ -0,0 +1,46 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::globals,
+}
+
+struct AccretionDiskSettings {
+    core_color: vec4<f32>,
+    inner_color: vec4<f32>,
+    outer_color: vec4<f32>,
+    horizon: f32,
+    speed: f32,
+}
+
+@group(2) @binding(0) var<uniform> settings: AccretionDiskSettings;
+
+const TAU: f32 = 6.28318530718;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Position on the disk, the edge of the disk has a radius of 1.0
+    let p = in.uv * 2.0 - 1.0;
+    let r = length(p);
+    let horizon = settings.horizon;
+
+    if r < horizon {
+        return settings.core_color;
+    }
+    if r > 1.0 {
+        discard;
+    }
+
+    // Matter closer to the center orbits faster, which winds the streaks into spirals
+    let angle = atan2(p.y, p.x) / TAU;
+    let swirl = angle + globals.time * settings.speed / (r * r) * 0.1;
+    let streaks = 0.6 + 0.25 * sin(swirl * TAU * 5.0 + r * 30.0) + 0.15 * sin(swirl * TAU * 11.0 - r * 50.0);
+
+    let t = (r - horizon) / (1.0 - horizon);
+    let fade_in = smoothstep(0.0, 0.08, t);
+    let fade_out = 1.0 - smoothstep(0.3, 1.0, t);
+    // A thin bright ring of light orbiting right at the event horizon
+    let photon_ring = 1.0 - smoothstep(0.0, 0.04, abs(t - 0.05));
+
+    let color = mix(settings.inner_color, settings.outer_color, t);
+    let alpha = clamp(fade_in * fade_out * streaks + photon_ring, 0.0, 1.0) * color.a;
+    return vec4<f32>(color.rgb * (1.0 + photon_ring), alpha);
+}
@@ -0,0 +1,41 @@
+// Bends the light around black holes, see `src/postprocessing/lensing.rs`
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct LensingSettings {
+    // xy: center in uv coordinates, z: radius relative to the screen height, w: strength
+    lenses: array<vec4<f32>, 8>,
+    lens_count: u32,
+    aspect_ratio: f32,
+    // How far the light is bent around a lens, relative to its radius
+    reach: f32,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: LensingSettings;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    // Distances are measured relative to the screen height, so lenses stay round
+    let scale = vec2<f32>(settings.aspect_ratio, 1.0);
+    var uv = in.uv;
+
+    for (var i: u32 = 0u; i < settings.lens_count; i = i + 1u) {
+        let lens = settings.lenses[i];
+        let offset = (in.uv - lens.xy) * scale;
+        let distance = length(offset);
+        let radius = lens.z;
+        let reach = radius * settings.reach;
+        if distance > reach || distance < 0.0001 {
+            continue;
+        }
+
+        // Light passing close to the lens is bent the most, which pulls the area around the
+        // event horizon into a ring
+        let falloff = 1.0 - smoothstep(reach * 0.5, reach, distance);
+        let deflection = lens.w * radius * radius / max(distance, radius * 0.5) * falloff;
+        uv -= offset / distance * deflection / scale;
+    }
+
+    return textureSampleLevel(screen_texture, texture_sampler, uv, 0.0);
+}
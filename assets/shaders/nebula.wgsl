@@ -0,0 +1,65 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::globals,
+}
+
+struct NebulaSettings {
+    color: vec4<f32>,
+    highlight_color: vec4<f32>,
+    seed: f32,
+    opacity: f32,
+}
+
+@group(2) @binding(0) var<uniform> settings: NebulaSettings;
+
+// Same number of bands as the lighting of the toon shader
+const QUANTIZE_STEPS: f32 = 3.0;
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+fn value_noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    return mix(
+        mix(hash(i), hash(i + vec2<f32>(1.0, 0.0)), u.x),
+        mix(hash(i + vec2<f32>(0.0, 1.0)), hash(i + vec2<f32>(1.0, 1.0)), u.x),
+        u.y
+    );
+}
+
+fn fbm(p: vec2<f32>) -> f32 {
+    var value = 0.0;
+    var amplitude = 0.5;
+    var q = p;
+    for (var i = 0; i < 4; i++) {
+        value += amplitude * value_noise(q);
+        q = q * 2.03 + vec2<f32>(17.0, 31.0);
+        amplitude *= 0.5;
+    }
+    return value;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let p = in.uv * 2.0 - 1.0;
+    let r = length(p);
+    if r > 1.0 {
+        discard;
+    }
+
+    // The cloud slowly drifts, every layer in its own direction
+    let drift = vec2<f32>(cos(settings.seed), sin(settings.seed)) * globals.time * 0.02;
+    let noise = fbm(p * 2.5 + settings.seed * 13.0 + drift);
+    let density = clamp(noise * 1.6 - r * r, 0.0, 1.0);
+
+    let band = ceil(density * QUANTIZE_STEPS) / QUANTIZE_STEPS;
+    if band <= 0.0 {
+        discard;
+    }
+
+    let color = mix(settings.color, settings.highlight_color, band);
+    return vec4<f32>(color.rgb, band * settings.opacity * color.a);
+}
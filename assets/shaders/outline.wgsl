@@ -0,0 +1,63 @@
+// Screen-space outline from the depth and normal prepass, see `src/postprocessing/outline.rs`
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct OutlineSettings {
+    color: vec4<f32>,
+    thickness: f32,
+    depth_threshold: f32,
+    normal_threshold: f32,
+    _padding: f32,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+#ifdef MULTISAMPLED
+@group(0) @binding(2) var depth_texture: texture_depth_multisampled_2d;
+@group(0) @binding(3) var normal_texture: texture_multisampled_2d<f32>;
+#else
+@group(0) @binding(2) var depth_texture: texture_depth_2d;
+@group(0) @binding(3) var normal_texture: texture_2d<f32>;
+#endif
+@group(0) @binding(4) var<uniform> settings: OutlineSettings;
+
+// The last argument is the sample index with MSAA and the mip level without, 0 either way
+fn load_depth(position: vec2<i32>) -> f32 {
+    return textureLoad(depth_texture, position, 0);
+}
+
+fn load_normal(position: vec2<i32>) -> vec3<f32> {
+    return textureLoad(normal_texture, position, 0).xyz * 2.0 - 1.0;
+}
+
+fn edge(center: vec2<i32>, offset: vec2<i32>, size: vec2<i32>) -> f32 {
+    let a = clamp(center + offset, vec2<i32>(0), size - 1);
+    let b = clamp(center - offset, vec2<i32>(0), size - 1);
+
+    // The depth is non-linear, so the difference is compared relative to the depth itself
+    let depth_a = load_depth(a);
+    let depth_b = load_depth(b);
+    let depth_edge = abs(depth_a - depth_b) / max(max(depth_a, depth_b), 0.000001);
+
+    let normal_edge = 1.0 - dot(load_normal(a), load_normal(b));
+
+    return max(
+        step(settings.depth_threshold, depth_edge),
+        step(settings.normal_threshold, normal_edge),
+    );
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(screen_texture, texture_sampler, in.uv);
+
+    let size = vec2<i32>(textureDimensions(depth_texture));
+    let center = vec2<i32>(in.uv * vec2<f32>(size));
+    let offset = max(i32(settings.thickness * 0.5), 1);
+
+    let outline = max(
+        edge(center, vec2<i32>(offset, 0), size),
+        edge(center, vec2<i32>(0, offset), size),
+    );
+
+    return vec4<f32>(mix(color.rgb, settings.color.rgb, outline * settings.color.a), color.a);
+}
@@ -0,0 +1,46 @@
+#import bevy_pbr::mesh_view_bindings::view
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+
+    @location(3) i_position_scale: vec4<f32>,
+    @location(4) i_color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+const RADIUS_SQUARED: f32 = 0.25;
+const SHARPNESS: f32 = 1000.0;
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    // Billboard the quad so that it always faces the camera
+    let right = view.world_from_view[0].xyz;
+    let up = view.world_from_view[1].xyz;
+    let offset = (right * vertex.position.x + up * vertex.position.y) * vertex.i_position_scale.w;
+
+    var out: VertexOutput;
+    out.clip_position = view.clip_from_world * vec4<f32>(vertex.i_position_scale.xyz + offset, 1.0);
+    out.uv = vertex.uv;
+    out.color = vertex.i_color;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let delta = in.uv - vec2<f32>(0.5, 0.5);
+    let difference = RADIUS_SQUARED - dot(delta, delta);
+
+    if difference < 0.0 {
+        discard;
+    }
+
+    let alpha = min(1.0, difference * SHARPNESS);
+
+    return vec4<f32>(in.color.xyz, in.color.w * alpha);
+}
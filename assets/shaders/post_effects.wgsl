@@ -0,0 +1,50 @@
+// Chromatic aberration, vignette, scanlines and film grain, see `src/postprocessing/effects.rs`
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import bevy_render::globals::Globals
+
+struct PostEffectsSettings {
+    aberration: f32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    scanline_intensity: f32,
+    scanline_count: f32,
+    grain_intensity: f32,
+    _padding: vec2<f32>,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: PostEffectsSettings;
+@group(0) @binding(3) var<uniform> globals: Globals;
+
+const PI: f32 = 3.14159265;
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(12.9898, 78.233))) * 43758.5453);
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let from_center = in.uv - vec2<f32>(0.5);
+
+    // The color channels drift apart towards the edges of the screen
+    let offset = from_center * settings.aberration;
+    var color = vec3<f32>(
+        textureSample(screen_texture, texture_sampler, in.uv + offset).r,
+        textureSample(screen_texture, texture_sampler, in.uv).g,
+        textureSample(screen_texture, texture_sampler, in.uv - offset).b,
+    );
+
+    // 1.0 in the corners
+    let distance = length(from_center) * sqrt(2.0);
+    let vignette = smoothstep(settings.vignette_radius - 0.5, settings.vignette_radius, distance);
+    color *= 1.0 - clamp(settings.vignette_intensity, 0.0, 1.0) * vignette;
+
+    let line = 0.5 + 0.5 * sin(in.uv.y * settings.scanline_count * PI);
+    color *= 1.0 - settings.scanline_intensity * line;
+
+    let noise = hash(in.uv * 1000.0 + fract(globals.time) * 100.0) - 0.5;
+    color += noise * settings.grain_intensity;
+
+    return vec4<f32>(color, 1.0);
+}
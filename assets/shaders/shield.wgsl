@@ -1,12 +1,89 @@
 #import bevy_pbr::{
-    forward_io::VertexOutput,
-    mesh_view_bindings::view, 
+    mesh_functions::{get_world_from_local, mesh_position_local_to_world, mesh_normal_local_to_world},
+    mesh_view_bindings::{view, globals},
+    view_transformations::position_world_to_clip,
 }
 
-@group(2) @binding(0) var<uniform> color: vec4<f32>;
+// Has to match `MAX_SHIELD_IMPACTS` in `shield.rs`
+const MAX_IMPACTS: u32 = 8u;
+// Seconds until a ripple has faded out
+const RIPPLE_DURATION: f32 = 1.0;
+// Units per second
+const RIPPLE_SPEED: f32 = 8.0;
+const RIPPLE_WIDTH: f32 = 1.5;
+// Number of hexagons around and along the shield
+const HEX_SCALE: vec2<f32> = vec2<f32>(40.0, 20.0);
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    // The impacts are stored in the local space of the shield, so the ripples move with it
+    @location(3) local_position: vec3<f32>,
+};
+
+struct ShieldMaterial {
+    color: vec4<f32>,
+    impacts: array<vec4<f32>, MAX_IMPACTS>,
+    strength: f32,
+}
+
+@group(2) @binding(0) var<uniform> material: ShieldMaterial;
+
+// Distance from the center of the closest cell of a hexagonal grid, 0.5 on its edges
+fn hex_distance(p: vec2<f32>) -> f32 {
+    let r = vec2<f32>(1.0, 1.7320508);
+    let h = r * 0.5;
+    let a = p - r * floor(p / r) - h;
+    let b = p - h - r * floor((p - h) / r) - h;
+    let cell = abs(select(b, a, dot(a, a) < dot(b, b)));
+    return max(dot(cell, normalize(r)), cell.x);
+}
+
+// Brightest ring of all impacts at a position in the local space of the shield
+fn impact_ripples(local_position: vec3<f32>) -> f32 {
+    var intensity = 0.0;
+    for (var i = 0u; i < MAX_IMPACTS; i++) {
+        let impact = material.impacts[i];
+        let age = globals.time - impact.w;
+        if impact.w < 0.0 || age < 0.0 || age > RIPPLE_DURATION {
+            continue;
+        }
+        let ring_distance = abs(distance(local_position, impact.xyz) - age * RIPPLE_SPEED);
+        let ring = 1.0 - clamp(ring_distance / RIPPLE_WIDTH, 0.0, 1.0);
+        intensity = max(intensity, ring * (1.0 - age / RIPPLE_DURATION));
+    }
+    return intensity;
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let world_from_local = get_world_from_local(vertex.instance_index);
+
+    var out: VertexOutput;
+    out.world_position = mesh_position_local_to_world(world_from_local, vec4<f32>(vertex.position, 1.0));
+    out.clip_position = position_world_to_clip(out.world_position.xyz);
+    out.world_normal = mesh_normal_local_to_world(vertex.normal, vertex.instance_index);
+    out.uv = vertex.uv;
+    out.local_position = vertex.position;
+    return out;
+}
 
 @fragment
 fn fragment(input: VertexOutput) -> @location(0) vec4<f32> {
     let view_direction = normalize(input.world_position.xyz - view.world_position);
-    return vec4<f32>(color.xyz, max(1.0 + 2.0 * dot(view_direction, input.world_normal), 0.05));
-}
\ No newline at end of file
+    let rim = max(1.0 + 2.0 * dot(view_direction, input.world_normal), 0.05);
+
+    let hex_edge = smoothstep(0.4, 0.5, hex_distance(input.uv * HEX_SCALE));
+    let ripple = impact_ripples(input.local_position) * mix(0.3, 1.0, hex_edge);
+
+    return vec4<f32>(material.color.xyz, (rim + ripple) * material.strength);
+}
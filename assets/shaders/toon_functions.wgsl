@@ -85,6 +85,38 @@ fn toon_outline(position: vec3<f32>, world_pos: vec3<f32>, sample_index: u32) ->
 }
 
 
+const SCORCH_COLOR: vec3<f32> = vec3<f32>(0.03, 0.02, 0.015);
+// Above 1.0, so the cracks are picked up by the bloom
+const EMBER_COLOR: vec3<f32> = vec3<f32>(3.0, 0.8, 0.1);
+
+fn scorch_hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+fn scorch_noise(p: vec2<f32>) -> f32 {
+    let cell = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    return mix(
+        mix(scorch_hash(cell), scorch_hash(cell + vec2<f32>(1.0, 0.0)), u.x),
+        mix(scorch_hash(cell + vec2<f32>(0.0, 1.0)), scorch_hash(cell + vec2<f32>(1.0, 1.0)), u.x),
+        u.y
+    );
+}
+
+// Darkens patches of the surface, which grow with the damage. The worst of them glow like embers.
+fn apply_scorch_marks(color: vec4<f32>, uv: vec2<f32>) -> vec4<f32> {
+    if settings.damage <= 0.0 {
+        return color;
+    }
+    let noise = 0.65 * scorch_noise(uv * 6.0) + 0.35 * scorch_noise(uv * 19.0);
+    let threshold = 1.0 - 0.6 * settings.damage;
+    let scorch = smoothstep(threshold, threshold + 0.08, noise);
+    let ember = smoothstep(threshold + 0.2, threshold + 0.22, noise) * settings.damage;
+    let scorched = mix(color.rgb, SCORCH_COLOR, scorch * 0.85) + EMBER_COLOR * ember;
+    return vec4<f32>(scorched, color.a);
+}
+
 fn toon_fragment(in: VertexOutput, sample_index: u32) -> vec4<f32> {
     var out_color: vec4<f32> = settings.color;
 
@@ -92,6 +124,8 @@ fn toon_fragment(in: VertexOutput, sample_index: u32) -> vec4<f32> {
         out_color *= textureSampleBias(texture, texture_sampler, in.uv, view.mip_bias);
     }
 
+    out_color = apply_scorch_marks(out_color, in.uv);
+
     if toon_outline(in.position.xyz, in.world_position.xyz, sample_index) {
         return vec4(0.0, 0.0, 0.0, 1.0);
     }
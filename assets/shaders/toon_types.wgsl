@@ -6,4 +6,6 @@ struct OutlineMaterialUniform {
     depth_normal_threshold: f32,
     use_texture: u32,
     color: vec4<f32>, 
+    // Between 0 and 1, how much of the surface is covered in scorch marks
+    damage: f32,
 }
\ No newline at end of file
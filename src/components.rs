@@ -4,7 +4,10 @@ pub mod colliders;
 pub mod despawn_after;
 pub mod gravity;
 pub mod health;
+pub mod hull_damage;
 pub mod movement;
+pub mod orbit;
+pub mod pool;
 
 pub struct ComponentsPlugin;
 
@@ -14,7 +17,9 @@ impl Plugin for ComponentsPlugin {
             despawn_after::DespawnAfterPlugin,
             gravity::GravityPlugin,
             movement::MovementPlugin,
+            orbit::OrbitPlugin,
             health::HealthPlugin,
+            hull_damage::HullDamagePlugin,
         ));
     }
 }
@@ -4,6 +4,8 @@ use bevy::prelude::*;
 
 use crate::states::game_paused;
 
+use super::pool::{InPool, ReleaseEntity};
+
 #[derive(Component, Deref, DerefMut)]
 pub struct DespawnTimer(pub Timer);
 
@@ -14,14 +16,14 @@ impl DespawnTimer {
 }
 
 fn despawn_after_system(
-    mut query: Query<(Entity, &mut DespawnTimer)>,
+    mut query: Query<(Entity, &mut DespawnTimer), Without<InPool>>,
     time: Res<Time>,
     mut commands: Commands,
 ) {
     for (entity, mut timer) in &mut query {
         timer.tick(time.delta());
         if timer.finished() {
-            commands.entity(entity).despawn_recursive();
+            commands.entity(entity).add(ReleaseEntity);
         }
     }
 }
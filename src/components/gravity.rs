@@ -3,6 +3,8 @@ use bevy_rapier3d::dynamics::Velocity;
 
 use crate::states::game_running;
 
+use super::pool::InPool;
+
 #[derive(Component)]
 pub struct GravitySource {
     pub mass: f32,
@@ -26,20 +28,22 @@ fn gravity_system(
     sources: Query<(&Transform, &GravitySource), Without<GravityAffected>>,
     mut affected: Query<
         (&mut Velocity, &mut Transform),
-        (With<GravityAffected>, Without<GravitySource>),
+        (
+            With<GravityAffected>,
+            Without<GravitySource>,
+            Without<InPool>,
+        ),
     >,
 ) {
     for (mut velocity, transform) in &mut affected {
-        let current_vel = velocity.linvel;
         velocity.linvel += sources
             .iter()
             .map(|(source_transform, source)| {
                 gravity_step(
-                    source_transform,
+                    source_transform.translation,
                     source,
                     time.delta_seconds(),
                     transform.translation,
-                    current_vel,
                 )
             })
             .sum::<Vec3>();
@@ -48,24 +52,23 @@ fn gravity_system(
 
 #[inline(always)]
 pub fn gravity_step(
-    source_transform: &Transform,
+    source_position: Vec3,
     source: &GravitySource,
     delta_time: f32,
     pos: Vec3,
-    vel: Vec3,
 ) -> Vec3 {
-    let distance = source_transform.translation.distance(pos);
+    let distance = source_position.distance(pos);
 
     if distance < 0.01 {
-        return vel;
+        return Vec3::ZERO;
     }
     if let Some(radius) = source.radius {
         if distance < radius {
-            return vel;
+            return Vec3::ZERO;
         }
     }
     let acc = source.mass / (distance * distance);
-    (source_transform.translation - pos).normalize() * acc * delta_time
+    (source_position - pos).normalize() * acc * delta_time
 }
 
 pub struct GravityPlugin;
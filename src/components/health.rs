@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::entities::spaceship::player::LastHit;
+use crate::entities::{nebula::InNebula, spaceship::player::LastHit};
 
 #[derive(Component, Default)]
 pub struct Health {
@@ -60,9 +60,13 @@ pub struct Regeneration {
     pub regen_speed: f32,
 }
 
-fn regeneration(mut query: Query<(&mut Health, &Regeneration, Option<&LastHit>)>, time: Res<Time>) {
-    for (mut health, regen, last_hit) in &mut query {
-        if health.is_dead() {
+fn regeneration(
+    mut query: Query<(&mut Health, &Regeneration, Option<&LastHit>, Has<InNebula>)>,
+    time: Res<Time>,
+) {
+    for (mut health, regen, last_hit, in_nebula) in &mut query {
+        // Nebulae drain the energy needed for repairs
+        if health.is_dead() || in_nebula {
             continue;
         }
 
@@ -0,0 +1,288 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    materials::{blink::BlinkMaterial, emissive, toon::ToonMaterial, EMISSIVE_INTENSITY},
+    particles::{
+        fire_particles::FireParticleRes,
+        simulation::{ParticleCurve, ParticleCurves, ParticleEmitter},
+    },
+    utils::scene::MaterialsApplied,
+};
+
+use super::health::Health;
+
+/// Below this fraction of its maximum health an entity counts as damaged
+const DAMAGED_THRESHOLD: f32 = 0.6;
+/// Below this fraction of its maximum health an entity counts as critically damaged
+const CRITICAL_THRESHOLD: f32 = 0.25;
+
+const WARNING_LIGHT_RADIUS: f32 = 0.3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+enum HullDamageState {
+    #[default]
+    Intact,
+    Damaged,
+    Critical,
+}
+
+impl HullDamageState {
+    fn from_health(health: &Health) -> Self {
+        let fraction = health.health / health.max_health;
+        if fraction < CRITICAL_THRESHOLD {
+            Self::Critical
+        } else if fraction < DAMAGED_THRESHOLD {
+            Self::Damaged
+        } else {
+            Self::Intact
+        }
+    }
+
+    /// Value of [`ToonMaterial::damage`]
+    fn material_damage(self) -> f32 {
+        match self {
+            Self::Intact => 0.0,
+            Self::Damaged => 0.5,
+            Self::Critical => 1.0,
+        }
+    }
+
+    /// Smoke particles per second
+    fn smoke_rate(self) -> f32 {
+        match self {
+            Self::Intact => 0.0,
+            Self::Damaged => 15.0,
+            Self::Critical => 40.0,
+        }
+    }
+}
+
+/// Shows how damaged an entity is, based on its [`Health`]. Damaged entities get scorch marks on
+/// their [`ToonMaterial`]s and trail smoke, critically damaged ones also throw sparks.
+#[derive(Component)]
+pub struct HullDamage {
+    /// Offsets of the lights that start blinking once the entity is damaged
+    pub warning_lights: &'static [Vec3],
+    /// Size of the smoke and sparks relative to a spaceship
+    pub effect_scale: f32,
+    state: HullDamageState,
+}
+
+impl Default for HullDamage {
+    fn default() -> Self {
+        Self {
+            warning_lights: &[],
+            effect_scale: 1.0,
+            state: HullDamageState::Intact,
+        }
+    }
+}
+
+impl HullDamage {
+    pub fn with_effect_scale(mut self, effect_scale: f32) -> Self {
+        self.effect_scale = effect_scale;
+        self
+    }
+
+    pub fn with_warning_lights(mut self, warning_lights: &'static [Vec3]) -> Self {
+        self.warning_lights = warning_lights;
+        self
+    }
+}
+
+/// Child entity showing the current damage state, replaced whenever the state changes
+#[derive(Component)]
+struct HullDamageEffect;
+
+#[derive(Resource)]
+struct HullDamageRes {
+    warning_light_mesh: Handle<Mesh>,
+    damaged_light_material: Handle<BlinkMaterial>,
+    critical_light_material: Handle<BlinkMaterial>,
+}
+
+/// Damaged variants of the materials, which are shared between all entities using the same model
+#[derive(Resource, Default)]
+struct DamagedMaterials {
+    variants: HashMap<(AssetId<ToonMaterial>, HullDamageState), Handle<ToonMaterial>>,
+    /// The undamaged material of every variant
+    originals: HashMap<AssetId<ToonMaterial>, Handle<ToonMaterial>>,
+}
+
+impl DamagedMaterials {
+    fn get(
+        &mut self,
+        handle: &Handle<ToonMaterial>,
+        state: HullDamageState,
+        materials: &mut Assets<ToonMaterial>,
+    ) -> Option<Handle<ToonMaterial>> {
+        let original = self.originals.get(&handle.id()).unwrap_or(handle).clone();
+        if state == HullDamageState::Intact {
+            return Some(original);
+        }
+        if let Some(variant) = self.variants.get(&(original.id(), state)) {
+            return Some(variant.clone());
+        }
+
+        let variant = materials.add(ToonMaterial {
+            damage: state.material_damage(),
+            ..materials.get(&original)?.clone()
+        });
+        self.variants
+            .insert((original.id(), state), variant.clone());
+        self.originals.insert(variant.id(), original);
+        Some(variant)
+    }
+}
+
+fn setup_hull_damage(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<BlinkMaterial>>,
+) {
+    let light_material = |period| BlinkMaterial {
+        period,
+        color_1: emissive(Color::srgb(1.0, 0.3, 0.0), EMISSIVE_INTENSITY).into(),
+        color_2: Color::srgb(0.1, 0.03, 0.0),
+    };
+
+    commands.insert_resource(HullDamageRes {
+        warning_light_mesh: meshes.add(Sphere {
+            radius: WARNING_LIGHT_RADIUS,
+        }),
+        damaged_light_material: materials.add(light_material(1.0)),
+        critical_light_material: materials.add(light_material(0.3)),
+    });
+}
+
+fn smoke_emitter(
+    fire_res: &FireParticleRes,
+    state: HullDamageState,
+    scale: f32,
+) -> ParticleEmitter {
+    ParticleEmitter::new(fire_res.materials.to_vec(), state.smoke_rate() * scale)
+        .with_velocity(Vec3::Y * 1.5, scale)
+        .with_size(0.4 * scale..0.9 * scale)
+        .with_lifetime(1.0..2.0)
+        .with_curves(ParticleCurves {
+            scale: ParticleCurve::new([(0.0, 0.5), (1.0, 2.0)]),
+            // Starts out as fire and quickly turns into dark smoke
+            color: ParticleCurve::new([
+                (0.0, LinearRgba::WHITE),
+                (0.15, LinearRgba::new(0.15, 0.15, 0.15, 0.7)),
+                (1.0, LinearRgba::new(0.1, 0.1, 0.1, 0.0)),
+            ]),
+        })
+}
+
+fn spark_emitter(fire_res: &FireParticleRes, scale: f32) -> ParticleEmitter {
+    ParticleEmitter::new(fire_res.materials.to_vec(), 25.0 * scale)
+        .with_velocity(Vec3::ZERO, 8.0 * scale)
+        .with_size(0.1..0.2)
+        .with_lifetime(0.2..0.4)
+        .with_curves(ParticleCurves {
+            color: ParticleCurve::new([
+                (0.0, emissive(LinearRgba::WHITE, EMISSIVE_INTENSITY)),
+                (1.0, LinearRgba::new(1.0, 1.0, 1.0, 0.0)),
+            ]),
+            ..default()
+        })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_hull_damage(
+    mut commands: Commands,
+    mut query: Query<
+        (
+            Entity,
+            &mut HullDamage,
+            &Health,
+            Option<&Children>,
+            Option<Ref<MaterialsApplied<ToonMaterial>>>,
+        ),
+        Or<(Changed<Health>, Added<MaterialsApplied<ToonMaterial>>)>,
+    >,
+    children_query: Query<&Children>,
+    effects: Query<(), With<HullDamageEffect>>,
+    mut material_handles: Query<&mut Handle<ToonMaterial>>,
+    mut materials: ResMut<Assets<ToonMaterial>>,
+    mut damaged_materials: ResMut<DamagedMaterials>,
+    fire_res: Res<FireParticleRes>,
+    res: Res<HullDamageRes>,
+) {
+    for (entity, mut hull_damage, health, children, materials_applied) in &mut query {
+        let state = HullDamageState::from_health(health);
+        // Entities that were damaged before their scene was loaded must not look intact
+        let materials_replaced = materials_applied.is_some_and(|applied| applied.is_added());
+        if state == hull_damage.state && !materials_replaced {
+            continue;
+        }
+
+        for descendant in children_query.iter_descendants(entity) {
+            let Ok(mut handle) = material_handles.get_mut(descendant) else {
+                continue;
+            };
+            if let Some(variant) = damaged_materials.get(&handle, state, &mut materials)
+                && variant != *handle
+            {
+                *handle = variant;
+            }
+        }
+
+        if state == hull_damage.state {
+            continue;
+        }
+        hull_damage.state = state;
+
+        for child in children.into_iter().flat_map(|children| children.iter()) {
+            if effects.contains(*child) {
+                commands.entity(*child).despawn_recursive();
+            }
+        }
+        if state == HullDamageState::Intact {
+            continue;
+        }
+
+        let scale = hull_damage.effect_scale;
+        let light_material = if state == HullDamageState::Critical {
+            &res.critical_light_material
+        } else {
+            &res.damaged_light_material
+        };
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                SpatialBundle::default(),
+                smoke_emitter(&fire_res, state, scale),
+                HullDamageEffect,
+            ));
+            if state == HullDamageState::Critical {
+                parent.spawn((
+                    SpatialBundle::default(),
+                    spark_emitter(&fire_res, scale),
+                    HullDamageEffect,
+                ));
+            }
+            for offset in hull_damage.warning_lights {
+                parent.spawn((
+                    MaterialMeshBundle {
+                        mesh: res.warning_light_mesh.clone(),
+                        material: light_material.clone(),
+                        transform: Transform::from_translation(*offset),
+                        ..default()
+                    },
+                    HullDamageEffect,
+                ));
+            }
+        });
+    }
+}
+
+pub struct HullDamagePlugin;
+
+impl Plugin for HullDamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DamagedMaterials>()
+            .add_systems(Startup, setup_hull_damage)
+            .add_systems(PostUpdate, update_hull_damage);
+    }
+}
@@ -0,0 +1,212 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier3d::dynamics::Velocity;
+
+use crate::states::{game_running, main_scene::GameTime};
+
+/// What an [`Orbit`] revolves around.
+#[derive(Clone, Copy, Debug)]
+pub enum OrbitCenter {
+    /// A fixed point in space, e.g. the barycenter of a planetary system
+    Point(Vec3),
+    /// Another body, which might be orbiting itself
+    Body(Entity),
+}
+
+/// Moves an entity along a Keplerian orbit in the XZ plane.
+///
+/// The orbit is evaluated from its elements and the [`GameTime`], so positions at any point
+/// in the future can be predicted exactly.
+#[derive(Component, Clone, Debug)]
+pub struct Orbit {
+    pub center: OrbitCenter,
+    pub semi_major_axis: f32,
+    /// 0.0 is a circle, values close to 1.0 are very elongated ellipses
+    pub eccentricity: f32,
+    /// Angle of the periapsis around the Y axis
+    pub argument_of_periapsis: f32,
+    /// Mean anomaly at a game time of zero
+    pub mean_anomaly: f32,
+    /// Mass of the orbited body, in the same units as [`super::gravity::GravitySource::mass`]
+    pub central_mass: f32,
+}
+
+impl Orbit {
+    /// Angular velocity of the mean anomaly in radians per second.
+    pub fn mean_motion(&self) -> f32 {
+        (self.central_mass / self.semi_major_axis.powi(3)).sqrt()
+    }
+
+    /// Time in seconds for one revolution.
+    pub fn period(&self) -> f32 {
+        TAU / self.mean_motion()
+    }
+
+    /// Solves Kepler's equation `M = E - e * sin(E)` for the eccentric anomaly `E`.
+    fn eccentric_anomaly(&self, time: f32) -> f32 {
+        let mean_anomaly = (self.mean_anomaly + self.mean_motion() * time).rem_euclid(TAU);
+        let e = self.eccentricity;
+
+        let mut anomaly = if e > 0.8 { PI } else { mean_anomaly };
+        for _ in 0..10 {
+            let delta = (anomaly - e * anomaly.sin() - mean_anomaly) / (1.0 - e * anomaly.cos());
+            anomaly -= delta;
+            if delta.abs() < 1e-6 {
+                break;
+            }
+        }
+        anomaly
+    }
+
+    fn to_world(&self, v: Vec2) -> Vec3 {
+        let rotated = Vec2::from_angle(self.argument_of_periapsis).rotate(v);
+        Vec3::new(rotated.x, 0.0, rotated.y)
+    }
+
+    /// Position relative to the orbited body at the given game time.
+    pub fn offset_at(&self, time: f32) -> Vec3 {
+        let anomaly = self.eccentric_anomaly(time);
+        let a = self.semi_major_axis;
+        let b = a * (1.0 - self.eccentricity * self.eccentricity).sqrt();
+        self.to_world(Vec2::new(
+            a * (anomaly.cos() - self.eccentricity),
+            b * anomaly.sin(),
+        ))
+    }
+
+    /// Velocity relative to the orbited body at the given game time.
+    pub fn velocity_at(&self, time: f32) -> Vec3 {
+        let anomaly = self.eccentric_anomaly(time);
+        let a = self.semi_major_axis;
+        let b = a * (1.0 - self.eccentricity * self.eccentricity).sqrt();
+        let anomaly_rate = self.mean_motion() / (1.0 - self.eccentricity * anomaly.cos());
+        self.to_world(Vec2::new(-a * anomaly.sin(), b * anomaly.cos()) * anomaly_rate)
+    }
+
+    /// The largest distance from the orbited body.
+    pub fn apoapsis(&self) -> f32 {
+        self.semi_major_axis * (1.0 + self.eccentricity)
+    }
+
+    /// The smallest distance from the orbited body.
+    pub fn periapsis(&self) -> f32 {
+        self.semi_major_axis * (1.0 - self.eccentricity)
+    }
+}
+
+/// Looks up where orbiting bodies are now or will be in the future, following the chain of
+/// orbited bodies (e.g. a moon orbiting a planet orbiting a barycenter).
+#[derive(SystemParam)]
+pub struct OrbitPositions<'w, 's> {
+    orbits: Query<'w, 's, &'static Orbit>,
+    transforms: Query<'w, 's, &'static GlobalTransform>,
+    game_time: Res<'w, GameTime>,
+}
+
+impl OrbitPositions<'_, '_> {
+    fn center_position(&self, center: OrbitCenter, time: f32) -> Option<Vec3> {
+        match center {
+            OrbitCenter::Point(point) => Some(point),
+            OrbitCenter::Body(body) => self.position_at(body, time),
+        }
+    }
+
+    fn position_at(&self, entity: Entity, time: f32) -> Option<Vec3> {
+        let Ok(orbit) = self.orbits.get(entity) else {
+            return self.transforms.get(entity).ok().map(|t| t.translation());
+        };
+        Some(self.center_position(orbit.center, time)? + orbit.offset_at(time))
+    }
+
+    fn velocity_at(&self, entity: Entity, time: f32) -> Vec3 {
+        let Ok(orbit) = self.orbits.get(entity) else {
+            return Vec3::ZERO;
+        };
+        let center_velocity = match orbit.center {
+            OrbitCenter::Point(_) => Vec3::ZERO,
+            OrbitCenter::Body(body) => self.velocity_at(body, time),
+        };
+        center_velocity + orbit.velocity_at(time)
+    }
+
+    /// Position of `entity` in `seconds` from now. Entities without an [`Orbit`] are assumed to
+    /// stay where they are.
+    pub fn position_in(&self, entity: Entity, seconds: f32) -> Option<Vec3> {
+        self.position_at(entity, self.game_time.elapsed_secs() + seconds)
+    }
+
+    /// Time until the position of `entity` repeats, considering every body in its orbit chain.
+    /// Returns 0.0 for entities that don't orbit.
+    pub fn period(&self, entity: Entity) -> f32 {
+        let Ok(orbit) = self.orbits.get(entity) else {
+            return 0.0;
+        };
+        let center_period = match orbit.center {
+            OrbitCenter::Point(_) => 0.0,
+            OrbitCenter::Body(body) => self.period(body),
+        };
+        orbit.period().max(center_period)
+    }
+}
+
+fn orbit_system(
+    orbit_positions: OrbitPositions,
+    mut bodies: Query<(Entity, &mut Transform, Option<&mut Velocity>), With<Orbit>>,
+) {
+    let time = orbit_positions.game_time.elapsed_secs();
+    for (entity, mut transform, velocity) in &mut bodies {
+        let Some(position) = orbit_positions.position_at(entity, time) else {
+            continue;
+        };
+        transform.translation = position;
+        // Rapier needs the velocity for contacts with the moving body
+        if let Some(mut velocity) = velocity {
+            velocity.linvel = orbit_positions.velocity_at(entity, time);
+        }
+    }
+}
+
+pub struct OrbitPlugin;
+
+impl Plugin for OrbitPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, orbit_system.run_if(game_running()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orbit(eccentricity: f32) -> Orbit {
+        Orbit {
+            center: OrbitCenter::Point(Vec3::ZERO),
+            semi_major_axis: 100.0,
+            eccentricity,
+            argument_of_periapsis: 0.0,
+            mean_anomaly: 0.0,
+            central_mass: 1000.0,
+        }
+    }
+
+    #[test]
+    fn orbit_starts_at_periapsis_and_returns_after_one_period() {
+        let orbit = orbit(0.5);
+        let start = orbit.offset_at(0.0);
+        assert!((start.length() - orbit.periapsis()).abs() < 1e-3);
+        assert!(start.distance(orbit.offset_at(orbit.period())) < 1e-2);
+        assert!((orbit.offset_at(orbit.period() / 2.0).length() - orbit.apoapsis()).abs() < 1e-2);
+    }
+
+    #[test]
+    fn circular_orbit_has_circular_velocity() {
+        let orbit = orbit(0.0);
+        let speed = (orbit.central_mass / orbit.semi_major_axis).sqrt();
+        for time in [0.0, 3.0, 10.0] {
+            let velocity = orbit.velocity_at(time);
+            assert!((velocity.length() - speed).abs() < 1e-3);
+            assert!(velocity.dot(orbit.offset_at(time)).abs() < 1e-2);
+        }
+    }
+}
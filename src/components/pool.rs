@@ -0,0 +1,148 @@
+use std::marker::PhantomData;
+
+use bevy::{ecs::system::EntityCommand, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::states::ON_GAME_STARTED;
+
+/// Marks an entity that is currently sitting unused in a pool.
+///
+/// Systems that work on pooled entities should filter these out with `Without<InPool>`.
+#[derive(Component)]
+pub struct InPool;
+
+/// Entities with this component are handed back to their pool instead of being despawned.
+#[derive(Component, Clone, Copy)]
+pub struct Pooled {
+    release: fn(&mut World, Entity),
+}
+
+impl Pooled {
+    pub fn new<T: Component>() -> Self {
+        Self {
+            release: release_to_pool::<T>,
+        }
+    }
+}
+
+/// A pool of entities identified by the marker component `T`.
+#[derive(Resource)]
+pub struct EntityPool<T: Component> {
+    free: Vec<Entity>,
+    capacity: usize,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Component> EntityPool<T> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            free: Vec::with_capacity(capacity),
+            capacity,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Reuses a free entity from the pool or spawns a new one if the pool is empty.
+    ///
+    /// `bundle` is inserted into recycled entities, so it should contain every component
+    /// that has to be reset when the entity is reused.
+    pub fn spawn(&mut self, commands: &mut Commands, bundle: impl Bundle) -> Entity {
+        while let Some(entity) = self.free.pop() {
+            // The entity might have been despawned while it was in the pool (e.g. on cleanup)
+            let Some(mut entity_commands) = commands.get_entity(entity) else {
+                continue;
+            };
+            entity_commands
+                .remove::<(InPool, ColliderDisabled)>()
+                .insert(Visibility::Inherited)
+                .insert(bundle);
+            return entity;
+        }
+
+        commands.spawn((bundle, Pooled::new::<T>())).id()
+    }
+
+    /// Spawns `count` entities that are put into the pool right away.
+    pub fn preallocate<B: Bundle>(
+        &self,
+        commands: &mut Commands,
+        count: usize,
+        bundle: impl Fn() -> B,
+    ) {
+        for _ in 0..count.min(self.capacity) {
+            commands
+                .spawn((bundle(), Pooled::new::<T>()))
+                .add(ReleaseEntity);
+        }
+    }
+}
+
+fn release_to_pool<T: Component>(world: &mut World, entity: Entity) {
+    let Some(mut pool) = world.get_resource_mut::<EntityPool<T>>() else {
+        world.entity_mut(entity).despawn_recursive();
+        return;
+    };
+
+    if pool.free.len() >= pool.capacity {
+        world.entity_mut(entity).despawn_recursive();
+        return;
+    }
+    pool.free.push(entity);
+
+    let mut entity_mut = world.entity_mut(entity);
+    if let Some(mut velocity) = entity_mut.get_mut::<Velocity>() {
+        *velocity = Velocity::zero();
+    }
+    if entity_mut.contains::<Collider>() {
+        entity_mut.insert(ColliderDisabled);
+    }
+    entity_mut.insert((InPool, Visibility::Hidden));
+}
+
+/// Returns the entity to its pool if it is [`Pooled`], otherwise despawns it recursively.
+pub struct ReleaseEntity;
+
+impl EntityCommand for ReleaseEntity {
+    fn apply(self, id: Entity, world: &mut World) {
+        let Some(entity) = world.get_entity(id) else {
+            return;
+        };
+        // Entities can be released multiple times in one frame (e.g. a bullet that hits something
+        // just as its lifetime ends), but must only end up in the pool once.
+        if entity.contains::<InPool>() {
+            return;
+        }
+
+        let pooled = entity.get::<Pooled>().copied();
+        match pooled {
+            Some(pooled) => (pooled.release)(world, id),
+            None => world.entity_mut(id).despawn_recursive(),
+        }
+    }
+}
+
+fn clear_pool<T: Component>(mut pool: ResMut<EntityPool<T>>) {
+    pool.free.clear();
+}
+
+pub struct PoolPlugin<T: Component> {
+    capacity: usize,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Component> PoolPlugin<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Component> Plugin for PoolPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EntityPool::<T>::new(self.capacity))
+            // Pooled entities are despawned on cleanup, so the pool has to start over
+            .add_systems(ON_GAME_STARTED, clear_pool::<T>);
+    }
+}
@@ -1,15 +1,21 @@
 use bevy::{app::Plugin, prelude::Component};
 
 pub mod asteroid;
+pub mod asteroid_belt;
+pub mod black_hole;
 pub mod bullet;
 pub mod camera;
 pub mod cruiser;
+pub mod defense;
 pub mod explosion;
+pub mod nebula;
 pub mod planet;
 pub mod powerup;
+pub mod sector;
 pub mod space_station;
 pub mod spaceship;
 pub mod turret;
+pub mod wormhole;
 
 #[derive(Component)]
 pub struct Enemy;
@@ -23,12 +29,18 @@ impl Plugin for EntitiesPlugin {
             spaceship::SpaceshipPlugin,
             bullet::BulletPlugin,
             asteroid::AsteroidPlugin,
+            asteroid_belt::AsteroidBeltPlugin,
             planet::PlanetPlugin,
+            black_hole::BlackHolePlugin,
+            nebula::NebulaPlugin,
             explosion::ExplosionPlugin,
             cruiser::CruiserPLugin,
             space_station::SpaceStationPlugin,
             powerup::PowerupPlugin,
+            sector::SectorPlugin,
             turret::TurretPlugin,
-        ));
+            wormhole::WormholePlugin,
+        ))
+        .add_plugins(defense::DefensePlugin);
     }
 }
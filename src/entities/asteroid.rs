@@ -1,6 +1,6 @@
-use std::{f32::consts::FRAC_PI_2, time::Duration};
+use std::{f32::consts::TAU, sync::Arc};
 
-use bevy::{app::FixedMain, prelude::*, render::RenderSet};
+use bevy::prelude::*;
 use bevy_asset_loader::{
     asset_collection::AssetCollection,
     loading_state::{
@@ -14,23 +14,76 @@ use rand::Rng;
 use space_game_common::EnemyType;
 
 use crate::{
-    components::{colliders::VelocityColliderBundle, despawn_after::DespawnTimer},
-    entities::bullet::BulletType,
-    particles::ParticleMaterial,
+    components::{colliders::VelocityColliderBundle, health::Health},
+    particles::{
+        simulation::{Particle, ParticleCurves, Particles},
+        ParticleMaterial,
+    },
     states::{game_running, AppState, DespawnOnCleanup},
     ui::game_hud::ScoreGameEvent,
-    utils::{
-        collisions::BULLET_COLLISION_GROUP, materials::default_outline,
-        misc::CollidingEntitiesExtension, sets::Set,
-    },
+    utils::{collisions::BULLET_COLLISION_GROUP, materials::default_outline, sets::Set},
     ToonMaterial,
 };
 
-use super::{bullet::Bullet, explosion::ExplosionEvent, spaceship::player::Player};
+use super::{
+    bullet::{BulletTarget, BulletType, LastBulletHit},
+    explosion::ExplosionEvent,
+    powerup::{PowerUp, SpawnPowerup},
+    spaceship::{SpaceshipCollisions, SPACESHIP_RESTITUTION},
+};
 
 #[derive(Component)]
 pub struct Asteroid;
 
+/// Mass of an asteroid with a scale of 1
+const ASTEROID_MASS: f32 = 2.0;
+/// Radius of the collider of an asteroid with a scale of 1
+const ASTEROID_RADIUS: f32 = 1.2;
+
+/// Size tier of an asteroid. Destroyed asteroids split into a few asteroids of the next smaller
+/// tier, the smallest ones break into debris.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AsteroidSize {
+    Large,
+    Medium,
+    Small,
+}
+
+impl AsteroidSize {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        match rng.gen_range(0.0..1.0) {
+            x if x < 0.3 => Self::Large,
+            x if x < 0.7 => Self::Medium,
+            _ => Self::Small,
+        }
+    }
+
+    pub fn random_scale(self, rng: &mut impl Rng) -> Vec3 {
+        let scale = match self {
+            Self::Large => 1.5,
+            Self::Medium => 1.0,
+            Self::Small => 0.6,
+        };
+        Vec3::splat(scale * rng.gen_range(0.9..1.1))
+    }
+
+    pub fn health(self) -> f32 {
+        match self {
+            Self::Large => 40.0,
+            Self::Medium => 20.0,
+            Self::Small => 10.0,
+        }
+    }
+
+    fn fragment_size(self) -> Option<Self> {
+        match self {
+            Self::Large => Some(Self::Medium),
+            Self::Medium => Some(Self::Small),
+            Self::Small => None,
+        }
+    }
+}
+
 impl Asteroid {
     const COLLISION_GROUPS: CollisionGroups =
         CollisionGroups::new(BULLET_COLLISION_GROUP, Group::ALL);
@@ -39,155 +92,167 @@ impl Asteroid {
 #[derive(Component)]
 pub struct AsteroidField;
 
-fn spawn_asteroid_field(
+pub struct AsteroidFieldSpawnConfig {
+    pub pos: Vec3,
+    pub radius: f32,
+    pub count: usize,
+}
+
+pub fn spawn_asteroid_field(
+    In(config): In<AsteroidFieldSpawnConfig>,
     mut commands: Commands,
-    player_query: Query<(&Transform, &Velocity), With<Player>>,
-    asteroid_fields: Query<&Transform, With<AsteroidField>>,
     res: Res<AsteroidRes>,
     assets: Res<AsteroidAssets>,
 ) {
-    for (player_transform, player_velocity) in &player_query {
-        let spawn_asteroid_field = asteroid_fields.iter().all(|field_transform| {
-            let distance = field_transform
-                .translation
-                .distance(player_transform.translation);
-            distance > 200.0
-        });
-        if spawn_asteroid_field {
-            let mut rng = rand::thread_rng();
-
-            let player_direction = player_velocity.linvel.normalize();
-            let cross_direction = player_direction.cross(Vec3::Y) * rng.gen_range(-1.0..1.0);
-            let position = player_transform.translation
-                + (player_direction + cross_direction) * rng.gen_range(70.0..120.0);
-            commands
-                .spawn((
-                    AsteroidField,
-                    DespawnOnCleanup,
-                    Transform::from_translation(position),
-                    GlobalTransform::default(),
-                    InheritedVisibility::VISIBLE,
-                ))
-                .with_children(|c| {
-                    let num_asteroids = rng.gen_range(10..50);
-                    for _ in 0..num_asteroids {
-                        let translation =
-                            Vec3::new(rng.gen_range(-20.0..20.0), 0.0, rng.gen_range(-20.0..20.0));
-
-                        let rotation =
-                            Quat::from_rotation_y(rng.gen_range(0.0..std::f32::consts::PI * 2.0));
-                        let scale = Vec3::splat(rng.gen_range(0.7..1.4));
-                        let linvel = Vec3::new(
-                            rand::random::<f32>() - 0.5,
-                            0.0,
-                            rand::random::<f32>() - 0.5,
-                        );
-                        let angvel = Vec3::Y * (rng.gen_range(-0.5..0.5));
-
-                        let mesh = if rng.gen::<bool>() {
-                            assets.asteroid_1.clone()
-                        } else {
-                            assets.asteroid_2.clone()
-                        };
-
-                        c.spawn(AsteroidBundle {
-                            mesh_bundle: MaterialMeshBundle {
-                                mesh: mesh.clone(),
-                                material: res.material.clone(),
-                                transform: Transform {
-                                    translation,
-                                    rotation,
-                                    scale,
-                                },
-                                ..default()
-                            },
-                            asteroid: Asteroid,
-                            velocity_collider_bundle: VelocityColliderBundle {
-                                velocity: Velocity { linvel, angvel },
-                                collider: Collider::ball(1.2),
-                                ..default()
-                            },
-                            outline_bundle: OutlineBundle {
-                                outline: default_outline(),
-                                ..default()
-                            },
-                            collision_groups: Asteroid::COLLISION_GROUPS,
-                        });
-                    }
-                });
-        }
-    }
-}
+    let AsteroidFieldSpawnConfig { pos, radius, count } = config;
+    let mut rng = rand::thread_rng();
 
-fn despawn_asteroid_field(
-    mut commands: Commands,
-    player_query: Query<&Transform, With<Player>>,
-    asteroid_fields: Query<(Entity, &Transform), With<AsteroidField>>,
-) {
-    for player_transform in &player_query {
-        for (entity, field_transform) in &asteroid_fields {
-            let distance = field_transform
-                .translation
-                .distance(player_transform.translation);
-            if distance > 200.0 {
-                debug!("Despawning asteroid field");
-                commands.entity(entity).despawn_recursive();
+    commands
+        .spawn((
+            AsteroidField,
+            DespawnOnCleanup,
+            Transform::from_translation(pos),
+            GlobalTransform::default(),
+            InheritedVisibility::VISIBLE,
+        ))
+        .with_children(|c| {
+            for _ in 0..count {
+                let offset =
+                    Vec2::from_angle(rng.gen_range(0.0..TAU)) * radius * rng.gen::<f32>().sqrt();
+                let translation = Vec3::new(offset.x, 0.0, offset.y);
+
+                let rotation = Quat::from_rotation_y(rng.gen_range(0.0..TAU));
+                let size = AsteroidSize::random(&mut rng);
+                let scale = size.random_scale(&mut rng);
+                let linvel = Vec3::new(
+                    rand::random::<f32>() - 0.5,
+                    0.0,
+                    rand::random::<f32>() - 0.5,
+                );
+                let angvel = Vec3::Y * (rng.gen_range(-0.5..0.5));
+
+                c.spawn(AsteroidBundle::new(
+                    &res,
+                    assets.random_mesh(&mut rng),
+                    size,
+                    Transform {
+                        translation,
+                        rotation,
+                        scale,
+                    },
+                    Velocity { linvel, angvel },
+                ));
             }
-        }
-    }
+        });
 }
 
-fn asteroid_collisions(
+fn asteroid_destruction(
     mut commands: Commands,
-    query: Query<(Entity, &CollidingEntities, &GlobalTransform), With<Asteroid>>,
+    query: Query<
+        (
+            Entity,
+            &Health,
+            &AsteroidSize,
+            &LastBulletHit,
+            &Transform,
+            &GlobalTransform,
+            &Velocity,
+            &Handle<Mesh>,
+            Option<&Parent>,
+        ),
+        (With<Asteroid>, Changed<Health>),
+    >,
     mut explosions: EventWriter<ExplosionEvent>,
-    bullet_query: Query<&Bullet>,
     res: Res<AsteroidRes>,
+    mut particles: ResMut<Particles>,
     mut score_events: EventWriter<ScoreGameEvent>,
 ) {
     const NUM_DESTRUCTION_PARTICLES: usize = 20;
+    const PARTICLE_SIZE: f32 = 0.2;
+    /// Speed the fragments fly apart with
+    const FRAGMENT_SPEED: std::ops::Range<f32> = 2.0..4.0;
+
+    let curves = Arc::new(ParticleCurves::default());
 
-    for (entity, colliding, global_transform) in &query {
-        if colliding.is_empty() {
+    for (
+        entity,
+        health,
+        size,
+        last_bullet_hit,
+        transform,
+        global_transform,
+        velocity,
+        mesh,
+        parent,
+    ) in &query
+    {
+        if !health.is_dead() {
             continue;
         }
         let mut rng = rand::thread_rng();
         commands.entity(entity).despawn_recursive();
 
-        let transform = global_transform.compute_transform();
-
-        for bullet in colliding.filter_fulfills_query(&bullet_query) {
-            if bullet.bullet_type == BulletType::Player {
-                score_events.send(ScoreGameEvent {
-                    world_pos: transform.translation,
-                    enemy: EnemyType::Asteroid,
-                });
-                break;
-            }
-        }
+        let position = global_transform.translation();
 
         explosions.send(ExplosionEvent {
-            position: transform.translation,
+            position,
             ..default()
         });
-        for _ in 0..NUM_DESTRUCTION_PARTICLES {
-            commands.spawn((
-                MaterialMeshBundle {
-                    mesh: res.particle_mesh.clone(),
-                    material: res.particle_material.clone(),
-                    transform: Transform {
-                        translation: transform.translation
-                            + Vec3::new(
-                                rng.gen_range(-1.0..1.0),
-                                rng.gen_range(-1.0..1.0),
-                                rng.gen_range(-1.0..1.0),
-                            ),
-                        rotation: Quat::from_rotation_x(-FRAC_PI_2),
-                        scale: Vec3::splat(rng.gen_range(2.0..5.0)),
+
+        let destroyed_by_player = last_bullet_hit.0 == Some(BulletType::Player);
+        if destroyed_by_player {
+            commands.add(SpawnPowerup {
+                powerup: PowerUp::Ore,
+                pos: position,
+            });
+            score_events.send(ScoreGameEvent {
+                world_pos: position,
+                enemy: EnemyType::Asteroid,
+            });
+        }
+
+        if let Some(fragment_size) = size.fragment_size() {
+            let fragment_count = rng.gen_range(2..=3);
+            let angle_offset = rng.gen_range(0.0..TAU);
+            for i in 0..fragment_count {
+                let angle = angle_offset + i as f32 / fragment_count as f32 * TAU;
+                let direction = Vec2::from_angle(angle);
+                let direction = Vec3::new(direction.x, 0.0, direction.y);
+                let scale = fragment_size.random_scale(&mut rng);
+
+                // Fragments are attached to the same field or belt chunk as the original
+                let mut fragment = commands.spawn(AsteroidBundle::new(
+                    &res,
+                    mesh.clone(),
+                    fragment_size,
+                    Transform {
+                        translation: transform.translation + direction * ASTEROID_RADIUS * scale.x,
+                        rotation: Quat::from_rotation_y(rng.gen_range(0.0..TAU)),
+                        scale,
                     },
-                    ..default()
-                },
-                Velocity::linear(
+                    Velocity {
+                        linvel: velocity.linvel + direction * rng.gen_range(FRAGMENT_SPEED),
+                        angvel: Vec3::Y * rng.gen_range(-1.0..1.0),
+                    },
+                ));
+                if let Some(parent) = parent {
+                    fragment.set_parent(parent.get());
+                }
+            }
+            continue;
+        }
+
+        // The smallest asteroids break into debris
+        for _ in 0..NUM_DESTRUCTION_PARTICLES {
+            particles.spawn(
+                &res.particle_material,
+                Particle::new(
+                    position
+                        + Vec3::new(
+                            rng.gen_range(-1.0..1.0),
+                            rng.gen_range(-1.0..1.0),
+                            rng.gen_range(-1.0..1.0),
+                        ),
                     Vec3::new(
                         rng.gen_range(-1.0..1.0),
                         rng.gen_range(-1.0..1.0),
@@ -195,43 +260,111 @@ fn asteroid_collisions(
                     )
                     .normalize()
                         * rng.gen_range(1.0..4.0),
+                    PARTICLE_SIZE * rng.gen_range(2.0..5.0),
+                    rng.gen_range(0.5..1.5),
+                    curves.clone(),
                 ),
-                RigidBody::KinematicVelocityBased,
-                DespawnOnCleanup,
-                DespawnTimer::new(Duration::from_millis(rng.gen_range(500..1500))),
-            ));
+            );
         }
     }
 }
 
 #[derive(Bundle)]
-struct AsteroidBundle {
+pub struct AsteroidBundle {
     mesh_bundle: MaterialMeshBundle<ToonMaterial>,
     asteroid: Asteroid,
     velocity_collider_bundle: VelocityColliderBundle,
+    mass_properties: ColliderMassProperties,
+    restitution: Restitution,
+    locked_axes: LockedAxes,
     outline_bundle: OutlineBundle,
     collision_groups: CollisionGroups,
+    size: AsteroidSize,
+    health: Health,
+    bullet_target: BulletTarget,
+    last_bullet_hit: LastBulletHit,
+    spaceship_collisions: SpaceshipCollisions,
+}
+
+impl AsteroidBundle {
+    pub fn new(
+        res: &AsteroidRes,
+        mesh: Handle<Mesh>,
+        size: AsteroidSize,
+        transform: Transform,
+        velocity: Velocity,
+    ) -> Self {
+        Self {
+            mesh_bundle: MaterialMeshBundle {
+                mesh,
+                material: res.material.clone(),
+                transform,
+                ..default()
+            },
+            asteroid: Asteroid,
+            velocity_collider_bundle: VelocityColliderBundle {
+                velocity,
+                collider: Collider::ball(ASTEROID_RADIUS),
+                rigid_body: RigidBody::Dynamic,
+                ..default()
+            },
+            mass_properties: ColliderMassProperties::Mass(
+                ASTEROID_MASS * transform.scale.x.powi(3),
+            ),
+            restitution: SPACESHIP_RESTITUTION,
+            locked_axes: LockedAxes::TRANSLATION_LOCKED_Y,
+            outline_bundle: OutlineBundle {
+                outline: default_outline(),
+                ..default()
+            },
+            collision_groups: Asteroid::COLLISION_GROUPS,
+            size,
+            health: Health::new(size.health()),
+            bullet_target: BulletTarget {
+                target_type: BulletType::Both,
+                bullet_damage: Some(10.0),
+            },
+            last_bullet_hit: LastBulletHit::default(),
+            spaceship_collisions: SpaceshipCollisions {
+                bound_radius: ASTEROID_RADIUS * transform.scale.x,
+            },
+        }
+    }
+
+    /// Restores the health of an asteroid that was damaged before.
+    pub fn with_health(mut self, health: f32) -> Self {
+        self.health.health = health;
+        self
+    }
 }
 
 #[derive(AssetCollection, Resource)]
-struct AsteroidAssets {
+pub struct AsteroidAssets {
     #[asset(path = "asteroid1.obj")]
     asteroid_1: Handle<Mesh>,
     #[asset(path = "asteroid2.obj")]
     asteroid_2: Handle<Mesh>,
 }
 
+impl AsteroidAssets {
+    pub fn random_mesh(&self, rng: &mut impl Rng) -> Handle<Mesh> {
+        if rng.gen::<bool>() {
+            self.asteroid_1.clone()
+        } else {
+            self.asteroid_2.clone()
+        }
+    }
+}
+
 #[derive(Resource)]
-struct AsteroidRes {
+pub struct AsteroidRes {
     material: Handle<ToonMaterial>,
-    particle_mesh: Handle<Mesh>,
     particle_material: Handle<ParticleMaterial>,
 }
 
 fn asteroid_setup(
     mut standard_materials: ResMut<Assets<ToonMaterial>>,
     mut particle_materials: ResMut<Assets<ParticleMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
     mut commands: Commands,
 ) {
     let material = standard_materials.add(ToonMaterial {
@@ -245,11 +378,8 @@ fn asteroid_setup(
         color: Srgba::hex("665F64").unwrap().into(),
     });
 
-    let particle_mesh = meshes.add(Rectangle::new(0.2, 0.2));
-
     commands.insert_resource(AsteroidRes {
         material,
-        particle_mesh,
         particle_material,
     });
 }
@@ -265,21 +395,9 @@ impl Plugin for AsteroidPlugin {
         .add_systems(Startup, asteroid_setup)
         .add_systems(
             Update,
-            (
-                asteroid_collisions
-                    .in_set(Set::ExplosionEvents)
-                    .in_set(Set::ScoreEvents),
-                despawn_asteroid_field,
-            )
-                .run_if(game_running()),
-        );
-
-        // TODO: For some reason this sometimes crashes on wasm (https://github.com/IchbinLuka/space_game.rs/issues/9)
-        #[cfg(not(target_arch = "wasm32"))]
-        app.add_systems(
-            FixedMain,
-            spawn_asteroid_field
-                .after(RenderSet::PrepareResources)
+            asteroid_destruction
+                .in_set(Set::ExplosionEvents)
+                .in_set(Set::ScoreEvents)
                 .run_if(game_running()),
         );
     }
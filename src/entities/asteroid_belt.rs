@@ -0,0 +1,234 @@
+use std::{
+    f32::consts::{PI, TAU},
+    ops::Range,
+};
+
+use bevy::prelude::*;
+use bevy_rapier3d::dynamics::Velocity;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    components::health::Health,
+    states::{game_running, DespawnOnCleanup},
+    utils::sets::Set,
+};
+
+use super::{
+    asteroid::{Asteroid, AsteroidAssets, AsteroidBundle, AsteroidRes, AsteroidSize},
+    camera::MainCamera,
+};
+
+/// Length of a chunk along the center line of a belt
+const CHUNK_LENGTH: f32 = 40.0;
+/// Chunks closer to the camera than this are loaded
+const LOAD_DISTANCE: f32 = 150.0;
+/// Chunks further away from the camera than this are unloaded. This is larger than
+/// [`LOAD_DISTANCE`], so chunks at the edge don't get reloaded every frame.
+const UNLOAD_DISTANCE: f32 = 200.0;
+
+/// An asteroid of a chunk that is currently not loaded
+struct AsteroidState {
+    transform: Transform,
+    velocity: Velocity,
+    mesh: Handle<Mesh>,
+    size: AsteroidSize,
+    health: f32,
+}
+
+#[derive(Default)]
+struct BeltChunk {
+    /// Parent of the asteroids while the chunk is loaded
+    entity: Option<Entity>,
+    /// The asteroids that were left when the chunk was unloaded. `None` if the chunk has never
+    /// been loaded.
+    asteroids: Option<Vec<AsteroidState>>,
+}
+
+/// A ring of asteroids in world space. Only the chunks close to the camera are spawned, the
+/// state of the others is kept here, so destroyed asteroids stay destroyed.
+#[derive(Component)]
+pub struct AsteroidBelt {
+    seed: u64,
+    radius: Range<f32>,
+    /// Asteroids per square unit
+    density: f32,
+    chunks: Vec<BeltChunk>,
+}
+
+/// Marks the entity the asteroids of a loaded chunk are attached to.
+#[derive(Component)]
+struct BeltChunkMarker;
+
+pub struct AsteroidBeltSpawnConfig {
+    pub center: Vec3,
+    pub radius: Range<f32>,
+    pub density: f32,
+    pub seed: u64,
+}
+
+impl AsteroidBelt {
+    fn chunk_angle(&self, index: usize) -> Range<f32> {
+        let step = TAU / self.chunks.len() as f32;
+        (index as f32 * step)..((index + 1) as f32 * step)
+    }
+
+    /// Center of a chunk relative to the center of the belt
+    fn chunk_center(&self, index: usize) -> Vec3 {
+        let angle = self.chunk_angle(index);
+        let direction = Vec2::from_angle((angle.start + angle.end) / 2.0);
+        let distance = (self.radius.start + self.radius.end) / 2.0;
+        Vec3::new(direction.x, 0.0, direction.y) * distance
+    }
+
+    /// Generates the asteroids of a chunk that has never been loaded. The same chunk of the
+    /// same belt always gets the same asteroids.
+    fn generate_chunk(&self, index: usize, assets: &AsteroidAssets) -> Vec<AsteroidState> {
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(index as u64));
+        let inner = self.radius.start.powi(2);
+        let outer = self.radius.end.powi(2);
+        let area = (outer - inner) * PI / self.chunks.len() as f32;
+        let count = (area * self.density).round() as usize;
+
+        (0..count)
+            .map(|_| {
+                let direction = Vec2::from_angle(rng.gen_range(self.chunk_angle(index)));
+                // Uniformly distributed over the area of the chunk
+                let distance = rng.gen_range(inner..outer).sqrt();
+                let size = AsteroidSize::random(&mut rng);
+                AsteroidState {
+                    transform: Transform {
+                        translation: Vec3::new(direction.x, 0.0, direction.y) * distance,
+                        rotation: Quat::from_rotation_y(rng.gen_range(0.0..TAU)),
+                        scale: size.random_scale(&mut rng),
+                    },
+                    velocity: Velocity {
+                        linvel: Vec3::new(rng.gen_range(-0.5..0.5), 0.0, rng.gen_range(-0.5..0.5)),
+                        angvel: Vec3::Y * rng.gen_range(-0.5..0.5),
+                    },
+                    mesh: assets.random_mesh(&mut rng),
+                    size,
+                    health: size.health(),
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn spawn_asteroid_belt(In(config): In<AsteroidBeltSpawnConfig>, mut commands: Commands) {
+    let AsteroidBeltSpawnConfig {
+        center,
+        radius,
+        density,
+        seed,
+    } = config;
+    let circumference = TAU * (radius.start + radius.end) / 2.0;
+    let chunk_count = ((circumference / CHUNK_LENGTH).ceil() as usize).max(1);
+
+    commands.spawn((
+        DespawnOnCleanup,
+        SpatialBundle::from_transform(Transform::from_translation(center)),
+        AsteroidBelt {
+            seed,
+            radius,
+            density,
+            chunks: (0..chunk_count).map(|_| BeltChunk::default()).collect(),
+        },
+    ));
+}
+
+/// Loads the chunks that came close to the camera and unloads the ones that are too far away.
+fn stream_asteroid_belts(
+    mut commands: Commands,
+    mut belts: Query<(Entity, &GlobalTransform, &mut AsteroidBelt)>,
+    chunks: Query<&Children, With<BeltChunkMarker>>,
+    asteroids: Query<
+        (&Transform, &Velocity, &Handle<Mesh>, &AsteroidSize, &Health),
+        With<Asteroid>,
+    >,
+    cameras: Query<&GlobalTransform, With<MainCamera>>,
+    res: Res<AsteroidRes>,
+    assets: Res<AsteroidAssets>,
+) {
+    let Ok(camera) = cameras.get_single() else {
+        return;
+    };
+    let camera_pos = camera.translation().xz();
+
+    for (belt_entity, belt_transform, mut belt) in &mut belts {
+        let center = belt_transform.translation();
+        for index in 0..belt.chunks.len() {
+            let distance = (center + belt.chunk_center(index))
+                .xz()
+                .distance(camera_pos);
+
+            let loaded = belt.chunks[index].entity;
+            match loaded {
+                None if distance < LOAD_DISTANCE => {
+                    let states = match belt.chunks[index].asteroids.take() {
+                        Some(states) => states,
+                        None => belt.generate_chunk(index, &assets),
+                    };
+                    let chunk = commands
+                        .spawn((BeltChunkMarker, SpatialBundle::default()))
+                        .with_children(|c| {
+                            for state in states {
+                                c.spawn(
+                                    AsteroidBundle::new(
+                                        &res,
+                                        state.mesh,
+                                        state.size,
+                                        state.transform,
+                                        state.velocity,
+                                    )
+                                    .with_health(state.health),
+                                );
+                            }
+                        })
+                        .set_parent(belt_entity)
+                        .id();
+                    belt.chunks[index].entity = Some(chunk);
+                }
+                Some(chunk) if distance > UNLOAD_DISTANCE => {
+                    let states = chunks
+                        .get(chunk)
+                        .map(|children| {
+                            asteroids
+                                .iter_many(children.iter())
+                                // Asteroids that were killed after they were checked for
+                                // destruction this frame would otherwise come back
+                                .filter(|(.., health)| !health.is_dead())
+                                .map(|(transform, velocity, mesh, size, health)| AsteroidState {
+                                    transform: *transform,
+                                    velocity: *velocity,
+                                    mesh: mesh.clone(),
+                                    size: *size,
+                                    health: health.health,
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    commands.entity(chunk).despawn_recursive();
+                    belt.chunks[index] = BeltChunk {
+                        entity: None,
+                        asteroids: Some(states),
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+pub struct AsteroidBeltPlugin;
+
+impl Plugin for AsteroidBeltPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            // Asteroids destroyed this frame must not be stored
+            stream_asteroid_belts
+                .after(Set::ExplosionEvents)
+                .run_if(game_running()),
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{
+        gravity::GravitySource,
+        health::Health,
+        pool::{InPool, ReleaseEntity},
+    },
+    materials::accretion_disk::{AccretionDiskBundle, AccretionDiskMaterial},
+    postprocessing::lensing::GravitationalLens,
+    states::{game_running, DespawnOnCleanup},
+    ui::minimap::{MinimapAssets, MinimapSize, ShowOnMinimap, MINIMAP_RANGE, MINIMAP_SIZE},
+    utils::sets::Set,
+};
+
+use super::{
+    asteroid::Asteroid,
+    bullet::Bullet,
+    explosion::ExplosionEvent,
+    spaceship::{Spaceship, SpaceshipCollisions},
+};
+
+pub const EVENT_HORIZON_RADIUS: f32 = 3.0;
+pub const ACCRETION_DISK_RADIUS: f32 = 14.0;
+/// Close enough to the event horizon, not even a ship at full thrust can escape
+const BLACK_HOLE_MASS: f32 = 20000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlackHoleKind {
+    /// Pulls everything in and destroys it at the event horizon
+    Black,
+    /// Pushes everything away
+    White,
+}
+
+#[derive(Component, Debug)]
+pub struct BlackHole {
+    pub kind: BlackHoleKind,
+}
+
+#[derive(Resource)]
+pub struct BlackHoleRes {
+    mesh: Handle<Mesh>,
+    black_hole_material: Handle<AccretionDiskMaterial>,
+    white_hole_material: Handle<AccretionDiskMaterial>,
+}
+
+pub struct BlackHoleSpawnConfig {
+    pub kind: BlackHoleKind,
+    pub pos: Vec3,
+}
+
+fn black_hole_setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<AccretionDiskMaterial>>,
+) {
+    commands.insert_resource(BlackHoleRes {
+        mesh: meshes.add(
+            Plane3d::default()
+                .mesh()
+                .size(ACCRETION_DISK_RADIUS * 2.0, ACCRETION_DISK_RADIUS * 2.0),
+        ),
+        black_hole_material: materials.add(AccretionDiskMaterial {
+            core_color: Color::BLACK,
+            inner_color: Srgba::hex("fff1c4").unwrap().into(),
+            outer_color: Srgba::hex("ff5a1f").unwrap().into(),
+            horizon: EVENT_HORIZON_RADIUS / ACCRETION_DISK_RADIUS,
+            speed: 1.0,
+        }),
+        white_hole_material: materials.add(AccretionDiskMaterial {
+            core_color: Color::WHITE,
+            inner_color: Srgba::hex("e8f7ff").unwrap().into(),
+            outer_color: Srgba::hex("4fa8ff").unwrap().into(),
+            horizon: EVENT_HORIZON_RADIUS / ACCRETION_DISK_RADIUS,
+            speed: -1.0,
+        }),
+    });
+}
+
+pub fn spawn_black_hole(
+    In(config): In<BlackHoleSpawnConfig>,
+    mut commands: Commands,
+    res: Res<BlackHoleRes>,
+    minimap_assets: Res<MinimapAssets>,
+) {
+    let BlackHoleSpawnConfig { kind, pos } = config;
+
+    let (material, mass, lens_strength, minimap_color) = match kind {
+        BlackHoleKind::Black => (
+            res.black_hole_material.clone(),
+            BLACK_HOLE_MASS,
+            1.0,
+            Color::from(Srgba::hex("9b4dff").unwrap()),
+        ),
+        BlackHoleKind::White => (
+            res.white_hole_material.clone(),
+            -BLACK_HOLE_MASS,
+            -0.5,
+            Color::WHITE,
+        ),
+    };
+
+    commands.spawn((
+        DespawnOnCleanup,
+        AccretionDiskBundle {
+            material_mesh: MaterialMeshBundle {
+                mesh: res.mesh.clone(),
+                material,
+                transform: Transform::from_translation(pos),
+                ..default()
+            },
+            ..default()
+        },
+        BlackHole { kind },
+        GravitySource {
+            mass,
+            radius: Some(EVENT_HORIZON_RADIUS),
+        },
+        GravitationalLens {
+            radius: EVENT_HORIZON_RADIUS,
+            strength: lens_strength,
+        },
+        SpaceshipCollisions {
+            bound_radius: ACCRETION_DISK_RADIUS,
+        },
+        ShowOnMinimap {
+            sprite: minimap_assets.planet_indicator.clone(),
+            size: MinimapSize::Custom(Vec2::splat(
+                ACCRETION_DISK_RADIUS / MINIMAP_RANGE * MINIMAP_SIZE,
+            )),
+            color: minimap_color,
+        },
+    ));
+}
+
+fn event_horizon(
+    mut commands: Commands,
+    black_holes: Query<(&Transform, &BlackHole)>,
+    bullets: Query<(Entity, &Transform), (With<Bullet>, Without<InPool>)>,
+    // Asteroids are children of their field or belt chunk
+    asteroids: Query<(Entity, &GlobalTransform), With<Asteroid>>,
+    mut spaceships: Query<(&Transform, &mut Health), With<Spaceship>>,
+    mut explosions: EventWriter<ExplosionEvent>,
+) {
+    for (black_hole_transform, black_hole) in &black_holes {
+        if black_hole.kind != BlackHoleKind::Black {
+            continue;
+        }
+        let center = black_hole_transform.translation;
+        let inside = |position: Vec3| position.distance(center) < EVENT_HORIZON_RADIUS;
+
+        for (entity, transform) in &bullets {
+            if inside(transform.translation) {
+                commands.entity(entity).add(ReleaseEntity);
+            }
+        }
+
+        for (entity, transform) in &asteroids {
+            let position = transform.translation();
+            if inside(position) {
+                commands.entity(entity).despawn_recursive();
+                explosions.send(ExplosionEvent {
+                    position,
+                    ..default()
+                });
+            }
+        }
+
+        // Spaceships explode through their regular death handling
+        for (transform, mut health) in &mut spaceships {
+            if inside(transform.translation) && !health.is_dead() {
+                health.kill();
+            }
+        }
+    }
+}
+
+pub struct BlackHolePlugin;
+
+impl Plugin for BlackHolePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, black_hole_setup).add_systems(
+            Update,
+            event_horizon
+                .in_set(Set::ExplosionEvents)
+                .run_if(game_running()),
+        );
+    }
+}
@@ -5,7 +5,12 @@ use bevy_mod_outline::{OutlineBundle, OutlineVolume};
 use bevy_rapier3d::prelude::*;
 
 use crate::{
-    components::{gravity::GravityAffected, health::Health},
+    components::{
+        gravity::GravityAffected,
+        health::{Health, Shield},
+        pool::{EntityPool, InPool, PoolPlugin, ReleaseEntity},
+    },
+    materials::{emissive, shield::ShieldImpactEvent, EMISSIVE_INTENSITY},
     states::{game_running, DespawnOnCleanup, ON_GAME_STARTED},
     utils::{collisions::BULLET_COLLISION_GROUP, sets::Set},
 };
@@ -17,6 +22,8 @@ pub struct Bullet {
     pub spawn_time: Duration,
     pub relative_speed: Vec3,
     pub bullet_type: BulletType,
+    /// Position of the bullet when collisions were last checked
+    pub last_position: Vec3,
 }
 
 #[derive(Component, Default)]
@@ -25,6 +32,16 @@ pub struct BulletTarget {
     pub bullet_damage: Option<f32>,
 }
 
+impl BulletTarget {
+    pub fn is_hit_by(&self, bullet_type: BulletType) -> bool {
+        self.target_type == BulletType::Both || self.target_type == bullet_type
+    }
+}
+
+/// Type of the bullet that last hit a [`BulletTarget`], for targets that can be hit by both.
+#[derive(Component, Default)]
+pub struct LastBulletHit(pub Option<BulletType>);
+
 #[derive(Clone, Copy, PartialEq, Eq, Default)]
 pub enum BulletType {
     Player,
@@ -48,34 +65,96 @@ const BULLET_CORNER_2: Vec3 = Vec3::new(-0.04, -0.04, 0.0);
 
 const BULLET_SPEED: f32 = 40.0;
 
+const BULLET_POOL_SIZE: usize = 256;
+
 fn bullet_setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    pool: Res<EntityPool<Bullet>>,
 ) {
     let bullet_mesh = meshes.add(Cuboid::from_corners(BULLET_CORNER_1, BULLET_CORNER_2));
     let bullet_material = materials.add(StandardMaterial {
-        base_color: Color::WHITE,
-        emissive: Color::WHITE.into(),
+        // Unlit materials ignore `emissive`, so the base color has to be bright enough to bloom
+        base_color: emissive(Color::WHITE, EMISSIVE_INTENSITY).into(),
         unlit: true,
         diffuse_transmission: 1.0,
         ..default()
     });
 
-    commands.insert_resource(BulletResource {
+    let bullet_res = BulletResource {
         bullet_mesh,
         bullet_material,
+    };
+
+    pool.preallocate(&mut commands, BULLET_POOL_SIZE / 2, || {
+        bullet_bundle(
+            &bullet_res,
+            Transform::default(),
+            Bullet {
+                spawn_time: Duration::ZERO,
+                relative_speed: Vec3::ZERO,
+                bullet_type: BulletType::Both,
+                last_position: Vec3::ZERO,
+            },
+            Vec3::ZERO,
+        )
     });
+
+    commands.insert_resource(bullet_res);
+}
+
+fn bullet_bundle(
+    bullet_res: &BulletResource,
+    transform: Transform,
+    bullet: Bullet,
+    linvel: Vec3,
+) -> impl Bundle {
+    let bullet_size = BULLET_CORNER_1 - BULLET_CORNER_2;
+
+    (
+        PbrBundle {
+            mesh: bullet_res.bullet_mesh.clone(),
+            material: bullet_res.bullet_material.clone(),
+            transform,
+            ..default()
+        },
+        OutlineBundle {
+            outline: OutlineVolume {
+                colour: if bullet.bullet_type == BulletType::Player {
+                    Srgba::hex("59ccf9").unwrap().into()
+                } else {
+                    Srgba::RED.into()
+                },
+                width: 2.0,
+                visible: true,
+            },
+            ..default()
+        },
+        bullet,
+        Collider::cuboid(bullet_size.x, bullet_size.y, bullet_size.z),
+        BULLET_GROUP,
+        ActiveEvents::COLLISION_EVENTS,
+        ActiveHooks::FILTER_INTERSECTION_PAIR,
+        RigidBody::KinematicVelocityBased,
+        Sensor,
+        GravityAffected,
+        Velocity {
+            linvel,
+            ..default()
+        },
+        DespawnOnCleanup,
+        CollidingEntities::default(),
+    )
 }
 
 fn bullet_spawn(
     mut commands: Commands,
     mut events: EventReader<BulletSpawnEvent>,
     bullet_res: Res<BulletResource>,
+    mut pool: ResMut<EntityPool<Bullet>>,
     time: Res<Time>,
 ) {
-    let bullet_size = BULLET_CORNER_1 - BULLET_CORNER_2;
-
     for event in events.read() {
         // let pos = transform.translation + transform.rotation.mul_vec3(side.into());
         let mut bullet_transform = Transform::from_translation(event.position.translation);
@@ -85,79 +164,100 @@ fn bullet_spawn(
 
         bullet_transform.rotate(rotation);
 
-        commands.spawn((
-            PbrBundle {
-                mesh: bullet_res.bullet_mesh.clone(),
-                material: bullet_res.bullet_material.clone(),
-                transform: bullet_transform,
-                ..default()
-            },
-            Bullet {
-                spawn_time: time.elapsed(),
-                relative_speed: event.entity_velocity.linvel,
-                bullet_type: event.bullet_type,
-            },
-            OutlineBundle {
-                outline: OutlineVolume {
-                    colour: if event.bullet_type == BulletType::Player {
-                        Srgba::hex("59ccf9").unwrap().into()
-                    } else {
-                        Srgba::RED.into()
-                    },
-                    width: 2.0,
-                    visible: true,
+        pool.spawn(
+            &mut commands,
+            bullet_bundle(
+                &bullet_res,
+                bullet_transform,
+                Bullet {
+                    spawn_time: time.elapsed(),
+                    relative_speed: event.entity_velocity.linvel,
+                    bullet_type: event.bullet_type,
+                    last_position: bullet_transform.translation,
                 },
-                ..default()
-            },
-            Collider::cuboid(bullet_size.x, bullet_size.y, bullet_size.z),
-            BULLET_GROUP,
-            ActiveEvents::COLLISION_EVENTS,
-            ActiveHooks::FILTER_INTERSECTION_PAIR,
-            RigidBody::KinematicVelocityBased,
-            Sensor,
-            GravityAffected,
-            Velocity {
-                linvel: event.direction.normalize() * BULLET_SPEED + event.entity_velocity.linvel,
-                ..default()
-            },
-            DespawnOnCleanup,
-            CollidingEntities::default(),
-        ));
+                event.direction.normalize() * BULLET_SPEED + event.entity_velocity.linvel,
+            ),
+        );
         // TODO: play sound
     }
 }
 
-fn bullet_despawn(time: Res<Time>, mut commands: Commands, query: Query<(Entity, &Bullet)>) {
+fn bullet_despawn(
+    time: Res<Time>,
+    mut commands: Commands,
+    query: Query<(Entity, &Bullet), Without<InPool>>,
+) {
     const BULLET_LIFETIME: Duration = Duration::from_secs(5);
     for (entity, bullet) in &query {
         if time.elapsed() - bullet.spawn_time > BULLET_LIFETIME {
-            commands.entity(entity).despawn_recursive();
+            commands.entity(entity).add(ReleaseEntity);
         }
     }
 }
 
 fn bullet_collision(
-    query: Query<(Entity, &Bullet, &CollidingEntities, &Transform)>,
-    mut bullet_target_query: Query<(&BulletTarget, Option<&mut Health>, Option<&mut LastHit>)>,
+    mut query: Query<(Entity, &mut Bullet, &CollidingEntities, &Transform), Without<InPool>>,
+    mut bullet_target_query: Query<(
+        &BulletTarget,
+        Option<&mut Health>,
+        Option<&mut LastHit>,
+        Option<&mut LastBulletHit>,
+        Has<Shield>,
+    )>,
+    rapier_context: Res<RapierContext>,
     mut commands: Commands,
     mut explosions: EventWriter<ExplosionEvent>,
+    mut shield_impacts: EventWriter<ShieldImpactEvent>,
     time: Res<Time>,
 ) {
-    for (entity, bullet, colliding_entities, transform) in &query {
-        if colliding_entities.is_empty() {
+    for (entity, mut bullet, colliding_entities, transform) in &mut query {
+        let mut hits = colliding_entities.iter().collect::<Vec<_>>();
+        let mut hit_position = transform.translation;
+
+        // Fast bullets can pass through small colliders between two physics steps without ever
+        // intersecting them, so the path travelled since the last check is ray cast as well.
+        let travelled = transform.translation - bullet.last_position;
+        let distance = travelled.length();
+        if distance > 0.0 {
+            let bullet_type = bullet.bullet_type;
+            let is_target = |target: Entity| {
+                bullet_target_query
+                    .get(target)
+                    .is_ok_and(|(bullet_target, ..)| bullet_target.is_hit_by(bullet_type))
+            };
+            let direction = travelled / distance;
+
+            if let Some((target, toi)) = rapier_context.cast_ray(
+                bullet.last_position,
+                direction,
+                distance,
+                true,
+                QueryFilter::new()
+                    .groups(BULLET_GROUP)
+                    .predicate(&is_target),
+            ) {
+                if !hits.contains(&target) {
+                    hits.push(target);
+                }
+                hit_position = bullet.last_position + direction * toi;
+            }
+        }
+        bullet.last_position = transform.translation;
+
+        if hits.is_empty() {
             continue;
         }
 
         let mut despawn: bool = false;
 
-        for entity in colliding_entities.iter() {
-            let Ok((bullet_target, health, last_hit)) = bullet_target_query.get_mut(entity) else {
+        for entity in hits {
+            let Ok((bullet_target, health, last_hit, last_bullet_hit, is_shield)) =
+                bullet_target_query.get_mut(entity)
+            else {
                 continue;
             };
 
-            if bullet_target.target_type != BulletType::Both
-                && bullet_target.target_type != bullet.bullet_type
-            {
+            if !bullet_target.is_hit_by(bullet.bullet_type) {
                 continue;
             }
 
@@ -171,22 +271,35 @@ fn bullet_collision(
                 last_hit.0 = Some(time.elapsed_seconds());
             }
 
+            if let Some(mut last_bullet_hit) = last_bullet_hit {
+                last_bullet_hit.0 = Some(bullet.bullet_type);
+            }
+
+            if is_shield {
+                shield_impacts.send(ShieldImpactEvent {
+                    shield: entity,
+                    position: hit_position,
+                });
+            }
+
             despawn = true;
         }
 
         if despawn {
             explosions.send(ExplosionEvent {
-                position: transform.translation,
+                position: hit_position,
                 ..default()
             });
 
             debug!("Bullet collided with something");
-            commands.entity(entity).despawn_recursive();
+            commands.entity(entity).add(ReleaseEntity);
         }
     }
 }
 
-fn bullet_rotation_correction(mut query: Query<(&mut Transform, &Velocity, &Bullet)>) {
+fn bullet_rotation_correction(
+    mut query: Query<(&mut Transform, &Velocity, &Bullet), Without<InPool>>,
+) {
     for (mut transform, vel, bullet) in &mut query {
         let rotation = Quat::from_rotation_arc(
             *transform.forward(),
@@ -206,7 +319,8 @@ pub struct BulletPlugin;
 
 impl Plugin for BulletPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(ON_GAME_STARTED, bullet_setup)
+        app.add_plugins(PoolPlugin::<Bullet>::new(BULLET_POOL_SIZE))
+            .add_systems(ON_GAME_STARTED, bullet_setup)
             .add_systems(
                 Update,
                 (
@@ -220,3 +334,79 @@ impl Plugin for BulletPlugin {
             .add_event::<BulletSpawnEvent>();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::{render::mesh::MeshPlugin, scene::ScenePlugin};
+
+    use super::*;
+
+    #[test]
+    fn fast_bullets_do_not_tunnel_through_small_colliders() {
+        const TIMESTEP: f32 = 1.0 / 10.0;
+
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            MeshPlugin,
+            ScenePlugin,
+            RapierPhysicsPlugin::<NoUserData>::default(),
+        ))
+        .add_event::<ExplosionEvent>()
+        .add_systems(Update, bullet_collision);
+        app.finish();
+
+        let mut rapier_config = app.world_mut().resource_mut::<RapierConfiguration>();
+        rapier_config.gravity = Vec3::ZERO;
+        rapier_config.timestep_mode = TimestepMode::Fixed {
+            dt: TIMESTEP,
+            substeps: 1,
+        };
+
+        // Same size as a bot. The bullet moves further than the diameter of the target in one
+        // step and is never inside of it at the end of a step.
+        let target = app
+            .world_mut()
+            .spawn((
+                TransformBundle::from_transform(Transform::from_xyz(0.0, 0.0, -10.0)),
+                RigidBody::Fixed,
+                Collider::ball(1.2),
+                BulletTarget {
+                    target_type: BulletType::Player,
+                    bullet_damage: Some(10.0),
+                },
+                Health::new(100.0),
+            ))
+            .id();
+        assert!(BULLET_SPEED * TIMESTEP > 2.4);
+
+        let bullet_res = BulletResource {
+            bullet_mesh: Handle::default(),
+            bullet_material: Handle::default(),
+        };
+        let bullet = app
+            .world_mut()
+            .spawn(bullet_bundle(
+                &bullet_res,
+                Transform::default(),
+                Bullet {
+                    spawn_time: Duration::ZERO,
+                    relative_speed: Vec3::ZERO,
+                    bullet_type: BulletType::Player,
+                    last_position: Vec3::ZERO,
+                },
+                Vec3::NEG_Z * BULLET_SPEED,
+            ))
+            .id();
+
+        for _ in 0..10 {
+            app.update();
+        }
+
+        let health = app.world().get::<Health>(target).unwrap();
+        assert_eq!(health.health, 90.0);
+        assert!(app.world().get_entity(bullet).is_none());
+    }
+}
@@ -1,77 +1,76 @@
-#[cfg(not(target_family = "wasm"))]
-use bevy::core_pipeline::prepass::{DepthPrepass, NormalPrepass};
-
 use bevy::{
-    core_pipeline::Skybox,
-    prelude::*,
-    render::{
-        render_resource::{TextureViewDescriptor, TextureViewDimension},
-        view::RenderLayers,
+    core_pipeline::{
+        bloom::{BloomPrefilterSettings, BloomSettings},
+        prepass::{DepthPrepass, NormalPrepass},
+        Skybox,
     },
+    prelude::*,
+    render::view::RenderLayers,
 };
-use bevy_asset_loader::asset_collection::AssetCollection;
 
 use crate::{
-    states::{game_running, AppState, DespawnOnCleanup, ON_GAME_STARTED},
-    utils::{asset_loading::AppExtension, sets::Set},
+    model::settings::{BloomSetting, Settings},
+    states::{DespawnOnCleanup, ON_GAME_STARTED},
+};
+
+use self::{
+    cinematic::CinematicPlugin,
+    director::{CameraDirector, CameraDirectorPlugin},
+    dynamic_resolution::DynamicResolutionPlugin,
+    skybox::SkyboxPlugin,
 };
 
-use super::spaceship::player::Player;
+pub mod cinematic;
+pub mod director;
+pub mod dynamic_resolution;
+pub mod skybox;
 
 #[derive(Component)]
 pub struct MainCamera;
 
 pub const RENDER_LAYER_2D: usize = 1;
 
-fn camera_follow_system(
-    mut camera_query: Query<&mut Transform, (With<MainCamera>, Without<Player>)>,
-    player_query: Query<&Transform, With<Player>>,
-) {
-    for mut camera_transform in &mut camera_query {
-        let player_tranform = player_query.iter().next();
-        if let Some(transform) = player_tranform {
-            camera_transform.translation = Vec3 {
-                x: transform.translation.x,
-                z: transform.translation.z,
-                ..camera_transform.translation
-            };
-        } else {
-            camera_transform.translation = Vec3 {
-                x: 0.0,
-                z: 0.0,
-                ..camera_transform.translation
-            };
-        }
-    }
+/// Whether the cameras render to a floating point texture, so emissive colors above 1.0 are kept
+/// for the bloom. WebGL falls back to a regular texture, where only a thresholded bloom is used.
+pub(crate) const HDR: bool = cfg!(not(target_family = "wasm"));
+
+/// Converts a position in the viewport of `camera` to a logical position in `window`. They differ
+/// when the camera renders to a scaled image, see [`dynamic_resolution`].
+pub fn viewport_to_window(camera: &Camera, window: &Window, position: Vec2) -> Vec2 {
+    camera
+        .logical_viewport_size()
+        .map_or(position, |size| position * window.size() / size)
 }
 
-fn setup_skybox_texture(mut images: ResMut<Assets<Image>>, camera_assets: Res<CameraAssets>) {
-    let image = images.get_mut(&camera_assets.skybox).unwrap();
-    // NOTE: PNGs do not have any metadata that could indicate they contain a cubemap texture,
-    // so they appear as one texture. The following code reconfigures the texture as necessary.
-    if image.texture_descriptor.array_layer_count() == 1 {
-        image.reinterpret_stacked_2d_as_array(image.height() / image.width());
-        image.texture_view_descriptor = Some(TextureViewDescriptor {
-            dimension: Some(TextureViewDimension::Cube),
-            ..default()
-        });
-    }
+/// The inverse of [`viewport_to_window`]
+pub fn window_to_viewport(camera: &Camera, window: &Window, position: Vec2) -> Vec2 {
+    camera
+        .logical_viewport_size()
+        .map_or(position, |size| position * size / window.size())
 }
 
 fn camera_setup(mut commands: Commands, camera_assets: Res<CameraAssets>) {
     let mut camera_transform = Transform::from_xyz(0.0, 75.0, 0.0);
     camera_transform.rotate(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2));
 
-    spawn_camera(&mut commands, camera_transform, &camera_assets);
+    let camera = spawn_camera(&mut commands, camera_transform, &camera_assets);
+    commands.entity(camera).insert(CameraDirector::default());
 }
 
-pub fn spawn_camera(commands: &mut Commands, transform: Transform, camera_assets: &CameraAssets) {
+/// Spawns the 2d camera for the ui and the main camera, whose entity is returned
+pub fn spawn_camera(
+    commands: &mut Commands,
+    transform: Transform,
+    camera_assets: &CameraAssets,
+) -> Entity {
     commands.spawn((
         DespawnOnCleanup,
         Camera2dBundle {
             camera: Camera {
                 order: 1,
                 clear_color: ClearColorConfig::None,
+                // Must match the 3d camera, which this camera draws over
+                hdr: HDR,
                 ..default()
             },
             ..default()
@@ -79,90 +78,116 @@ pub fn spawn_camera(commands: &mut Commands, transform: Transform, camera_assets
         RenderLayers::layer(RENDER_LAYER_2D),
     ));
 
-    commands.spawn((
-        Camera3dBundle {
-            transform,
-            projection: Projection::Perspective(PerspectiveProjection {
-                far: 10000.0,
+    commands
+        .spawn((
+            Camera3dBundle {
+                camera: Camera {
+                    hdr: HDR,
+                    ..default()
+                },
+                transform,
+                projection: Projection::Perspective(PerspectiveProjection {
+                    far: 10000.0,
+                    ..default()
+                }),
                 ..default()
-            }),
-            ..default()
-        },
-        Skybox {
-            image: camera_assets.skybox.clone(),
-            brightness: 1000.,
-        },
-        MainCamera,
-        DespawnOnCleanup,
-        #[cfg(not(target_family = "wasm"))]
-        (
-            // On WebGL, normal and depth prepasses are currently broken.
-            // See https://github.com/bevyengine/bevy/issues/9710
-            DepthPrepass,
-            NormalPrepass,
-        ),
-    ));
+            },
+            Skybox {
+                image: camera_assets.skybox.clone(),
+                brightness: 1000.,
+            },
+            MainCamera,
+            DespawnOnCleanup,
+        ))
+        .id()
 }
 
-fn control_camera(
-    input: Res<ButtonInput<KeyCode>>,
-    mut camera: Query<&mut Transform, With<MainCamera>>,
-    time: Res<Time>,
-) {
-    let Ok(mut camera_transform) = camera.get_single_mut() else {
-        return;
+fn bloom_settings(setting: BloomSetting) -> Option<BloomSettings> {
+    let intensity = match setting {
+        BloomSetting::Off => return None,
+        BloomSetting::Low => 0.1,
+        BloomSetting::High => 0.25,
     };
+    let prefilter_settings = if HDR {
+        BloomSettings::NATURAL.prefilter_settings
+    } else {
+        // Without HDR nothing is brighter than 1.0, so only bloom the brightest parts
+        BloomPrefilterSettings {
+            threshold: 0.8,
+            threshold_softness: 0.2,
+        }
+    };
+    Some(BloomSettings {
+        intensity,
+        prefilter_settings,
+        ..BloomSettings::NATURAL
+    })
+}
 
-    if input.pressed(KeyCode::ArrowUp) {
-        camera_transform.rotate_local_x(time.delta_seconds() * 2.0);
+fn apply_bloom_setting(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    cameras: Query<Entity, With<MainCamera>>,
+    new_cameras: Query<(), Added<MainCamera>>,
+) {
+    if !settings.is_changed() && new_cameras.is_empty() {
+        return;
     }
-    if input.pressed(KeyCode::ArrowDown) {
-        camera_transform.rotate_local_x(-time.delta_seconds() * 2.0);
+
+    for camera in &cameras {
+        match bloom_settings(settings.bloom) {
+            Some(bloom) => {
+                commands.entity(camera).insert(bloom);
+            }
+            None => {
+                commands.entity(camera).remove::<BloomSettings>();
+            }
+        }
     }
-    if input.pressed(KeyCode::ArrowLeft) {
-        camera_transform.rotate_local_y(time.delta_seconds() * 2.0);
+}
+
+fn apply_prepass_setting(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    cameras: Query<Entity, With<MainCamera>>,
+    new_cameras: Query<(), Added<MainCamera>>,
+) {
+    if !settings.is_changed() && new_cameras.is_empty() {
+        return;
     }
-    if input.pressed(KeyCode::ArrowRight) {
-        camera_transform.rotate_local_y(-time.delta_seconds() * 2.0);
+
+    for camera in &cameras {
+        if settings.graphics.prepasses() {
+            commands
+                .entity(camera)
+                .insert((DepthPrepass, NormalPrepass));
+        } else {
+            commands
+                .entity(camera)
+                .remove::<(DepthPrepass, NormalPrepass)>();
+        }
     }
 }
 
-#[derive(AssetCollection, Resource)]
+/// Generated by the [`SkyboxPlugin`]
+#[derive(Resource)]
 pub struct CameraAssets {
-    #[asset(path = "skybox.png")]
     skybox: Handle<Image>,
+    /// Seed and face size of the sky in [`Self::skybox`], or the one that is being generated
+    skybox_key: Option<(u64, u32)>,
 }
 
 pub struct CameraComponentPlugin;
 
 impl Plugin for CameraComponentPlugin {
     fn build(&self, app: &mut App) {
-        app.add_collection_to_loading_states::<CameraAssets>(&[
-            AppState::MainSceneLoading,
-            AppState::StartScreenLoading,
-            AppState::TestSceneLoading,
-        ])
-        .add_systems(
-            OnEnter(AppState::StartScreen),
-            setup_skybox_texture.in_set(Set::CameraSkyboxInit),
-        )
-        .add_systems(
-            OnEnter(AppState::MainScene),
-            setup_skybox_texture.in_set(Set::CameraSkyboxInit),
-        )
-        .add_systems(
-            OnEnter(AppState::TestScene),
-            setup_skybox_texture.in_set(Set::CameraSkyboxInit),
-        )
-        .add_systems(ON_GAME_STARTED, camera_setup)
-        .add_systems(
-            Update,
-            (
-                camera_follow_system
-                    .in_set(Set::CameraMovement)
-                    .run_if(game_running()),
-                control_camera,
-            ),
-        );
+        app.add_systems(ON_GAME_STARTED, camera_setup)
+            .add_systems(Update, (apply_bloom_setting, apply_prepass_setting))
+            .add_plugins((
+                CameraDirectorPlugin,
+                CinematicPlugin,
+                DynamicResolutionPlugin,
+                SkyboxPlugin,
+            ));
     }
 }
@@ -0,0 +1,324 @@
+use std::f32::consts::TAU;
+
+use bevy::{ecs::world::Command, prelude::*};
+
+use crate::{
+    entities::{space_station::SpaceStation, spaceship::player::Player},
+    states::{game_over, game_running},
+    ui::game_over::GameOverEvent,
+};
+
+use super::{director::CameraDirector, MainCamera};
+
+const ORBIT_RADIUS: f32 = 45.0;
+const ORBIT_HEIGHT: f32 = 25.0;
+/// Seconds for one full orbit around the destroyed station
+const ORBIT_PERIOD: f32 = 40.0;
+const ORBIT_KEYFRAMES: usize = 8;
+/// The cut to an arriving cruiser never takes longer than this
+const MAX_CRUISER_SHOT: f32 = 5.0;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Easing {
+    Linear,
+    #[default]
+    InOut,
+    Out,
+}
+
+impl Easing {
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::InOut => t * t * (3.0 - 2.0 * t),
+            Self::Out => 1.0 - (1.0 - t) * (1.0 - t),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CameraKeyframe {
+    /// Seconds since the start of the sequence
+    pub time: f32,
+    pub position: Vec3,
+    pub look_at: Vec3,
+    pub up: Vec3,
+    /// Easing of the way from the previous keyframe to this one
+    pub easing: Easing,
+}
+
+impl CameraKeyframe {
+    pub fn new(time: f32, position: Vec3, look_at: Vec3) -> Self {
+        Self {
+            time,
+            position,
+            look_at,
+            up: Vec3::Y,
+            easing: default(),
+        }
+    }
+
+    pub fn with_up(mut self, up: Vec3) -> Self {
+        self.up = up;
+        self
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+/// Takes control of the main camera and moves it along a smooth path through the keyframes. The
+/// [`CameraDirector`] takes over again once the sequence is finished or skipped.
+#[derive(Component)]
+pub struct CameraSequence {
+    keyframes: Vec<CameraKeyframe>,
+    /// The keyframes are relative to this entity, as long as it exists
+    anchor: Option<Entity>,
+    origin: Vec3,
+    /// The last keyframe of a looping sequence should match the first one
+    looping: bool,
+    elapsed: f32,
+}
+
+impl CameraSequence {
+    pub fn new(keyframes: Vec<CameraKeyframe>) -> Self {
+        Self {
+            keyframes,
+            anchor: None,
+            origin: Vec3::ZERO,
+            looping: false,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn with_anchor(mut self, anchor: Entity) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    pub fn looping(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+
+    fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    fn is_finished(&self) -> bool {
+        !self.looping && self.elapsed >= self.duration()
+    }
+
+    /// Index of the keyframe `offset` steps away from `index`
+    fn neighbour(&self, index: usize, offset: isize) -> usize {
+        let last = self.keyframes.len() - 1;
+        let index = index as isize + offset;
+        if self.looping && last > 0 {
+            // The last keyframe is the same as the first one, so it is skipped when wrapping
+            index.rem_euclid(last as isize) as usize
+        } else {
+            index.clamp(0, last as isize) as usize
+        }
+    }
+
+    /// Camera position, look-at target and up direction at the current time. The first two are
+    /// relative to the origin.
+    fn sample(&self) -> Option<(Vec3, Vec3, Vec3)> {
+        let first = self.keyframes.first()?;
+        let duration = self.duration();
+        let time = if self.looping && duration > 0.0 {
+            self.elapsed % duration
+        } else {
+            self.elapsed.min(duration)
+        };
+
+        let Some(index) = self
+            .keyframes
+            .windows(2)
+            .position(|pair| time < pair[1].time)
+        else {
+            let last = self.keyframes.last().unwrap_or(first);
+            return Some((last.position, last.look_at, last.up));
+        };
+
+        let from = &self.keyframes[index];
+        let to = &self.keyframes[index + 1];
+        let t = to
+            .easing
+            .apply((time - from.time) / (to.time - from.time).max(f32::EPSILON));
+
+        let before = &self.keyframes[self.neighbour(index, -1)];
+        let after = &self.keyframes[self.neighbour(index + 1, 1)];
+        let position = catmull_rom(
+            before.position,
+            from.position,
+            to.position,
+            after.position,
+            t,
+        );
+        Some((
+            position,
+            from.look_at.lerp(to.look_at, t),
+            from.up.lerp(to.up, t).normalize_or(Vec3::Y),
+        ))
+    }
+}
+
+/// Curve through `p1` and `p2`, whose tangents are given by the points around them
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+/// Plays a sequence on the main camera, replacing the one that is currently playing
+pub struct PlayCameraSequence(pub CameraSequence);
+
+impl Command for PlayCameraSequence {
+    fn apply(self, world: &mut World) {
+        let Ok(camera) = world
+            .query_filtered::<Entity, With<MainCamera>>()
+            .get_single(world)
+        else {
+            return;
+        };
+        world.entity_mut(camera).insert(self.0);
+    }
+}
+
+/// Flies in to the station closest to the player and ends at the starting view of the
+/// [`CameraDirector`]
+fn intro_sequence(station: Vec3, director: &CameraDirector) -> CameraSequence {
+    let end = director.focus + Vec3::Y * director.height;
+    CameraSequence::new(vec![
+        CameraKeyframe::new(0.0, station + Vec3::new(150.0, 100.0, 120.0), station),
+        CameraKeyframe::new(3.0, station + Vec3::new(35.0, 15.0, 25.0), station)
+            .with_easing(Easing::Out),
+        CameraKeyframe::new(5.0, station + Vec3::new(-10.0, 25.0, 30.0), station)
+            .with_easing(Easing::Linear),
+        // The director looks straight down, with the negative z axis pointing up on the screen
+        CameraKeyframe::new(7.0, end, director.focus).with_up(Vec3::NEG_Z),
+    ])
+}
+
+/// Follows a cruiser while it warps in, `duration` is the length of its animation
+pub fn cruiser_arrival_sequence(cruiser: Entity, duration: f32) -> CameraSequence {
+    let duration = duration.min(MAX_CRUISER_SHOT);
+    CameraSequence::new(vec![
+        CameraKeyframe::new(0.0, Vec3::new(50.0, 30.0, 50.0), Vec3::ZERO),
+        CameraKeyframe::new(duration, Vec3::new(25.0, 20.0, 40.0), Vec3::ZERO),
+    ])
+    .with_anchor(cruiser)
+}
+
+/// Slowly orbits the position of the station that was destroyed last
+fn game_over_sequence(position: Vec3) -> CameraSequence {
+    let keyframes = (0..=ORBIT_KEYFRAMES)
+        .map(|i| {
+            let fraction = i as f32 / ORBIT_KEYFRAMES as f32;
+            let (sin, cos) = (fraction * TAU).sin_cos();
+            CameraKeyframe::new(
+                fraction * ORBIT_PERIOD,
+                position + Vec3::new(cos * ORBIT_RADIUS, ORBIT_HEIGHT, sin * ORBIT_RADIUS),
+                position,
+            )
+            .with_easing(Easing::Linear)
+        })
+        .collect();
+    CameraSequence::new(keyframes).looping()
+}
+
+fn start_intro(
+    mut commands: Commands,
+    cameras: Query<(Entity, &CameraDirector), Added<CameraDirector>>,
+    stations: Query<&Transform, With<SpaceStation>>,
+    player: Query<&Transform, With<Player>>,
+) {
+    let player = player
+        .get_single()
+        .map_or(Vec3::ZERO, |transform| transform.translation);
+    let Some(station) = stations
+        .iter()
+        .map(|transform| transform.translation)
+        .min_by(|a, b| a.distance(player).total_cmp(&b.distance(player)))
+    else {
+        return;
+    };
+
+    for (camera, director) in &cameras {
+        commands
+            .entity(camera)
+            .insert(intro_sequence(station, director));
+    }
+}
+
+fn start_game_over_orbit(mut commands: Commands, mut events: EventReader<GameOverEvent>) {
+    if let Some(event) = events.read().last() {
+        commands.add(PlayCameraSequence(game_over_sequence(event.position)));
+    }
+}
+
+fn skip_camera_sequences(
+    mut commands: Commands,
+    cameras: Query<Entity, With<CameraSequence>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+) {
+    let skipped = keyboard_input.get_just_pressed().next().is_some()
+        || mouse_input.get_just_pressed().next().is_some()
+        || touches.any_just_pressed();
+    if !skipped {
+        return;
+    }
+    for camera in &cameras {
+        commands.entity(camera).remove::<CameraSequence>();
+    }
+}
+
+fn play_camera_sequences(
+    mut commands: Commands,
+    mut cameras: Query<(Entity, &mut Transform, &mut CameraSequence), With<MainCamera>>,
+    anchors: Query<&GlobalTransform>,
+    time: Res<Time>,
+) {
+    for (camera, mut transform, mut sequence) in &mut cameras {
+        if let Some(anchor) = sequence.anchor
+            && let Ok(anchor) = anchors.get(anchor)
+        {
+            sequence.origin = anchor.translation();
+        }
+        sequence.elapsed += time.delta_seconds();
+
+        if let Some((position, look_at, up)) = sequence.sample() {
+            *transform = Transform::from_translation(sequence.origin + position)
+                .looking_at(sequence.origin + look_at, up);
+        }
+        if sequence.is_finished() {
+            commands.entity(camera).remove::<CameraSequence>();
+        }
+    }
+}
+
+pub struct CinematicPlugin;
+
+impl Plugin for CinematicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                start_intro.run_if(game_running()),
+                start_game_over_orbit,
+                skip_camera_sequences.run_if(game_running()),
+                // The game over orbit keeps playing behind the game over screen
+                play_camera_sequences.run_if(game_running().or_else(game_over())),
+            )
+                .chain(),
+        );
+    }
+}
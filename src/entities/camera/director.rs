@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::{
+    entities::{
+        explosion::ExplosionEvent,
+        space_station::SpaceStation,
+        spaceship::{player::Player, IsPlayer},
+        Enemy,
+    },
+    model::settings::Settings,
+    states::game_running,
+    utils::sets::Set,
+};
+
+use super::{cinematic::CameraSequence, MainCamera};
+
+/// Height above the focus while the player is standing still
+const BASE_HEIGHT: f32 = 75.0;
+const MAX_HEIGHT: f32 = 140.0;
+/// Additional height per unit of speed
+const SPEED_ZOOM: f32 = 1.0;
+/// How many seconds of movement the camera leads ahead of the player
+const LOOK_AHEAD_TIME: f32 = 0.5;
+const MAX_LOOK_AHEAD: f32 = 15.0;
+/// Enemies and stations closer than this to the player are kept in view
+const FRAMING_RANGE: f32 = 70.0;
+/// How far the focus is moved from the player towards the framed entities
+const FRAMING_WEIGHT: f32 = 0.3;
+/// Space kept between framed entities and the edge of the screen
+const FRAMING_MARGIN: f32 = 10.0;
+/// Higher values follow the target more tightly
+const FOLLOW_DAMPING: f32 = 5.0;
+const ZOOM_DAMPING: f32 = 2.0;
+
+/// Trauma lost per second
+const TRAUMA_DECAY: f32 = 1.5;
+/// Trauma caused by an explosion right at the focus, per unit of explosion radius
+const TRAUMA_PER_RADIUS: f32 = 0.05;
+/// Explosions further away from the focus do not shake the camera
+const SHAKE_RANGE: f32 = 100.0;
+const MAX_SHAKE_OFFSET: f32 = 2.0;
+const MAX_SHAKE_ROLL: f32 = 0.05;
+const SHAKE_FREQUENCY: f32 = 25.0;
+
+/// Moves the main camera during the game. It follows the player from above with some damping,
+/// zooms out with speed, leads in the direction of movement and keeps nearby threats and stations
+/// in view. Explosions add trauma, which shakes the camera.
+#[derive(Component)]
+pub struct CameraDirector {
+    /// Point on the plane of the game the camera looks at, without shake
+    pub focus: Vec3,
+    pub height: f32,
+    /// Between 0 and 1, the shake grows with its square
+    pub trauma: f32,
+}
+
+impl Default for CameraDirector {
+    fn default() -> Self {
+        Self {
+            focus: Vec3::ZERO,
+            height: BASE_HEIGHT,
+            trauma: 0.0,
+        }
+    }
+}
+
+impl CameraDirector {
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+}
+
+/// Smooth noise between -1 and 1, a different `seed` gives an unrelated curve
+fn shake_noise(time: f32, seed: f32) -> f32 {
+    ((time + seed * 13.7).sin() + (time * 2.3 + seed * 7.1).sin() * 0.5) / 1.5
+}
+
+fn explosion_trauma(
+    mut events: EventReader<ExplosionEvent>,
+    mut directors: Query<&mut CameraDirector>,
+    settings: Res<Settings>,
+) {
+    if !settings.screen_shake.0 {
+        events.clear();
+        return;
+    }
+
+    for event in events.read() {
+        for mut director in &mut directors {
+            let falloff = 1.0 - event.position.distance(director.focus) / SHAKE_RANGE;
+            if falloff > 0.0 {
+                director.add_trauma(event.radius * TRAUMA_PER_RADIUS * falloff);
+            }
+        }
+    }
+}
+
+fn direct_camera(
+    mut cameras: Query<
+        (&mut Transform, &mut CameraDirector, &Projection),
+        (With<MainCamera>, Without<Player>, Without<CameraSequence>),
+    >,
+    player: Query<(&Transform, &Velocity), IsPlayer>,
+    points_of_interest: Query<&GlobalTransform, Or<(With<Enemy>, With<SpaceStation>)>>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+
+    for (mut transform, mut director, projection) in &mut cameras {
+        let (target_focus, target_height) = match player.get_single() {
+            Ok((player_transform, velocity)) => {
+                let position = player_transform.translation;
+                let look_ahead = (velocity.linvel * LOOK_AHEAD_TIME)
+                    .with_y(0.0)
+                    .clamp_length_max(MAX_LOOK_AHEAD);
+
+                let nearby = points_of_interest
+                    .iter()
+                    .map(|transform| transform.translation().with_y(position.y))
+                    .filter(|point| point.distance(position) < FRAMING_RANGE)
+                    .collect::<Vec<_>>();
+
+                let mut focus = position + look_ahead;
+                if !nearby.is_empty() {
+                    let center = nearby.iter().sum::<Vec3>() / nearby.len() as f32;
+                    focus += (center - position) * FRAMING_WEIGHT;
+                }
+
+                // The vertical field of view is the narrower one on landscape screens
+                let half_fov = match projection {
+                    Projection::Perspective(perspective) => perspective.fov / 2.0,
+                    Projection::Orthographic(_) => std::f32::consts::FRAC_PI_4,
+                };
+                let extent = nearby
+                    .iter()
+                    .chain([&position])
+                    .map(|point| point.distance(focus) + FRAMING_MARGIN)
+                    .fold(0.0, f32::max);
+                let height = (BASE_HEIGHT + velocity.linvel.length() * SPEED_ZOOM)
+                    .max(extent / half_fov.tan())
+                    .min(MAX_HEIGHT);
+
+                (focus, height)
+            }
+            Err(_) => (Vec3::ZERO, BASE_HEIGHT),
+        };
+
+        let director = director.as_mut();
+        director.focus = director
+            .focus
+            .lerp(target_focus, 1.0 - (-FOLLOW_DAMPING * delta).exp());
+        director.height +=
+            (target_height - director.height) * (1.0 - (-ZOOM_DAMPING * delta).exp());
+        director.trauma = (director.trauma - TRAUMA_DECAY * delta).max(0.0);
+
+        let shake = if settings.screen_shake.0 {
+            director.trauma * director.trauma
+        } else {
+            0.0
+        };
+        let noise_time = time.elapsed_seconds_wrapped() * SHAKE_FREQUENCY;
+        let offset = Vec3::new(
+            shake_noise(noise_time, 0.0),
+            0.0,
+            shake_noise(noise_time, 1.0),
+        ) * MAX_SHAKE_OFFSET
+            * shake;
+        let roll = shake_noise(noise_time, 2.0) * MAX_SHAKE_ROLL * shake;
+
+        transform.translation = director.focus + Vec3::Y * director.height + offset;
+        transform.rotation =
+            Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2) * Quat::from_rotation_z(roll);
+    }
+}
+
+pub struct CameraDirectorPlugin;
+
+impl Plugin for CameraDirectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (explosion_trauma, direct_camera)
+                .chain()
+                .in_set(Set::CameraMovement)
+                .run_if(game_running()),
+        );
+    }
+}
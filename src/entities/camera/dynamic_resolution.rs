@@ -0,0 +1,191 @@
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+        texture::BevyDefault,
+        view::RenderLayers,
+    },
+    window::PrimaryWindow,
+};
+
+use crate::model::settings::Settings;
+
+use super::{MainCamera, RENDER_LAYER_2D};
+
+/// Longest smoothed frame time that is accepted before the resolution is lowered
+const FRAME_TIME_BUDGET: f32 = 1.0 / 55.0;
+/// The resolution is only lowered once the budget is exceeded by this factor, so it does not
+/// flicker between two steps
+const FRAME_TIME_TOLERANCE: f32 = 1.1;
+const MIN_RENDER_SCALE: f32 = 0.5;
+const RENDER_SCALE_STEP: f32 = 0.05;
+/// Seconds between two adjustments, so the frame time can settle in between
+const ADJUST_INTERVAL: f32 = 0.5;
+/// Weight of the latest frame in the smoothed frame time
+const FRAME_TIME_SMOOTHING: f32 = 0.1;
+/// Behind all other sprites, the 2d camera sees everything down to a z of -0.1
+const DISPLAY_Z: f32 = -0.05;
+
+/// Exists while dynamic resolution is enabled. The main camera then renders to `image`, which
+/// has `scale` times the resolution of the window.
+#[derive(Resource)]
+struct DynamicResolution {
+    scale: f32,
+    image: Handle<Image>,
+    frame_time: f32,
+    timer: Timer,
+}
+
+/// Stretches the image of the main camera over the window
+#[derive(Component)]
+struct DynamicResolutionDisplay;
+
+fn render_target_image() -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::bevy_default(),
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage =
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+    image
+}
+
+fn apply_dynamic_resolution_setting(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    dynamic_resolution: Option<Res<DynamicResolution>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    match (settings.dynamic_resolution.0, dynamic_resolution) {
+        (true, None) => {
+            commands.insert_resource(DynamicResolution {
+                scale: 1.0,
+                image: images.add(render_target_image()),
+                frame_time: FRAME_TIME_BUDGET,
+                timer: Timer::from_seconds(ADJUST_INTERVAL, TimerMode::Repeating),
+            });
+        }
+        (false, Some(dynamic_resolution)) => {
+            images.remove(&dynamic_resolution.image);
+            commands.remove_resource::<DynamicResolution>();
+        }
+        _ => {}
+    }
+}
+
+fn adjust_render_scale(mut dynamic_resolution: ResMut<DynamicResolution>, time: Res<Time<Real>>) {
+    let dynamic_resolution = dynamic_resolution.as_mut();
+    dynamic_resolution.frame_time +=
+        (time.delta_seconds() - dynamic_resolution.frame_time) * FRAME_TIME_SMOOTHING;
+
+    if !dynamic_resolution.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    if dynamic_resolution.frame_time > FRAME_TIME_BUDGET * FRAME_TIME_TOLERANCE {
+        dynamic_resolution.scale -= RENDER_SCALE_STEP;
+    } else if dynamic_resolution.frame_time < FRAME_TIME_BUDGET {
+        dynamic_resolution.scale += RENDER_SCALE_STEP;
+    }
+    dynamic_resolution.scale = dynamic_resolution.scale.clamp(MIN_RENDER_SCALE, 1.0);
+}
+
+fn update_render_target(
+    mut commands: Commands,
+    dynamic_resolution: Option<Res<DynamicResolution>>,
+    mut images: ResMut<Assets<Image>>,
+    mut cameras: Query<&mut Camera, With<MainCamera>>,
+    mut displays: Query<(Entity, &mut Sprite), With<DynamicResolutionDisplay>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let Some(dynamic_resolution) = dynamic_resolution else {
+        for mut camera in &mut cameras {
+            if matches!(camera.target, RenderTarget::Image(_)) {
+                camera.target = RenderTarget::default();
+            }
+        }
+        for (display, _) in &displays {
+            commands.entity(display).despawn();
+        }
+        return;
+    };
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    let size = (window.physical_size().as_vec2() * dynamic_resolution.scale)
+        .as_uvec2()
+        .max(UVec2::ONE);
+    // Mutable access alone recreates the texture on the GPU
+    let resized = images
+        .get(&dynamic_resolution.image)
+        .is_some_and(|image| image.size() != size);
+    if resized && let Some(image) = images.get_mut(&dynamic_resolution.image) {
+        image.resize(Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        });
+    }
+
+    for mut camera in &mut cameras {
+        let renders_to_image = matches!(
+            &camera.target,
+            RenderTarget::Image(image) if *image == dynamic_resolution.image
+        );
+        if !renders_to_image {
+            camera.target = RenderTarget::Image(dynamic_resolution.image.clone());
+        }
+    }
+
+    if let Ok((_, mut sprite)) = displays.get_single_mut() {
+        if sprite.custom_size != Some(window.size()) {
+            sprite.custom_size = Some(window.size());
+        }
+    } else {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(window.size()),
+                    ..default()
+                },
+                texture: dynamic_resolution.image.clone(),
+                transform: Transform::from_xyz(0.0, 0.0, DISPLAY_Z),
+                ..default()
+            },
+            RenderLayers::layer(RENDER_LAYER_2D),
+            DynamicResolutionDisplay,
+        ));
+    }
+}
+
+/// Renders the main camera at a lower resolution while the frame time exceeds its budget. The
+/// image is stretched over the window by the 2d camera.
+pub struct DynamicResolutionPlugin;
+
+impl Plugin for DynamicResolutionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                apply_dynamic_resolution_setting,
+                adjust_render_scale.run_if(resource_exists::<DynamicResolution>),
+                update_render_target,
+            )
+                .chain(),
+        );
+    }
+}
@@ -0,0 +1,534 @@
+use std::{f32::consts::TAU, sync::Arc};
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{
+            Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+        },
+    },
+    tasks::AsyncComputeTaskPool,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    entities::sector::Sector,
+    model::settings::Settings,
+    states::AppState,
+    utils::{sets::Set, tasks::TaskComponent},
+};
+
+use super::CameraAssets;
+
+/// Seed of the sky behind the menus, which looks the same every time
+const MENU_SKYBOX_SEED: u64 = 0;
+/// Width of a cube face in pixels, before the graphics preset divides it
+const FACE_SIZE: u32 = 1024;
+/// The nebulae only change slowly, so they are computed every this many pixels and interpolated
+const NEBULA_STEP: usize = 8;
+
+const BACKGROUND_COLORS: [Srgba; 4] = [
+    Srgba::rgb(0.098, 0.098, 0.439),
+    Srgba::rgb(0.1, 0.06, 0.25),
+    Srgba::rgb(0.04, 0.1, 0.23),
+    Srgba::rgb(0.13, 0.06, 0.19),
+];
+const NEBULA_COLORS: [Srgba; 6] = [
+    Srgba::rgb(0.85, 0.2, 0.6),
+    Srgba::rgb(0.1, 0.7, 0.75),
+    Srgba::rgb(0.95, 0.45, 0.1),
+    Srgba::rgb(0.5, 0.2, 0.9),
+    Srgba::rgb(0.15, 0.35, 0.95),
+    Srgba::rgb(0.9, 0.15, 0.15),
+];
+const STAR_COLORS: [Srgba; 3] = [
+    Srgba::WHITE,
+    Srgba::rgb(0.75, 0.85, 1.0),
+    Srgba::rgb(1.0, 0.9, 0.7),
+];
+
+/// Everything about a sky that is picked from its seed
+struct SkyStyle {
+    background: Vec3,
+    nebula_colors: [Vec3; 2],
+    nebula_strength: f32,
+    noise_seed: u32,
+    star_count: usize,
+    galaxy_count: usize,
+}
+
+impl SkyStyle {
+    fn from_rng(rng: &mut impl Rng) -> Self {
+        let mut pick = |colors: &[Srgba]| to_vec3(colors[rng.gen_range(0..colors.len())]);
+        let background = pick(&BACKGROUND_COLORS);
+        let nebula_colors = [pick(&NEBULA_COLORS), pick(&NEBULA_COLORS)];
+        Self {
+            background: background * rng.gen_range(0.5..1.0),
+            nebula_colors,
+            nebula_strength: rng.gen_range(0.1..0.35),
+            noise_seed: rng.gen(),
+            star_count: rng.gen_range(800..2500),
+            galaxy_count: rng.gen_range(2..7),
+        }
+    }
+}
+
+fn to_vec3(color: Srgba) -> Vec3 {
+    let color = LinearRgba::from(color);
+    Vec3::new(color.red, color.green, color.blue)
+}
+
+/// Direction of a point on a face of the cube, `u` and `v` go from -1 to 1. The faces are in the
+/// order the GPU expects them: +x, -x, +y, -y, +z, -z.
+fn face_direction(face: usize, u: f32, v: f32) -> Vec3 {
+    match face {
+        0 => Vec3::new(1.0, -v, -u),
+        1 => Vec3::new(-1.0, -v, u),
+        2 => Vec3::new(u, 1.0, v),
+        3 => Vec3::new(u, -1.0, -v),
+        4 => Vec3::new(u, -v, 1.0),
+        _ => Vec3::new(-u, -v, -1.0),
+    }
+    .normalize()
+}
+
+/// The inverse of [`face_direction`]
+fn direction_to_face(direction: Vec3) -> (usize, f32, f32) {
+    let abs = direction.abs();
+    if abs.x >= abs.y && abs.x >= abs.z {
+        if direction.x > 0.0 {
+            (0, -direction.z / abs.x, -direction.y / abs.x)
+        } else {
+            (1, direction.z / abs.x, -direction.y / abs.x)
+        }
+    } else if abs.y >= abs.z {
+        if direction.y > 0.0 {
+            (2, direction.x / abs.y, direction.z / abs.y)
+        } else {
+            (3, direction.x / abs.y, -direction.z / abs.y)
+        }
+    } else if direction.z > 0.0 {
+        (4, direction.x / abs.z, -direction.y / abs.z)
+    } else {
+        (5, -direction.x / abs.z, -direction.y / abs.z)
+    }
+}
+
+fn hash(cell: IVec3, seed: u32) -> f32 {
+    let mut h = (cell.x as u32).wrapping_mul(0x8da6_b343)
+        ^ (cell.y as u32).wrapping_mul(0xd816_3841)
+        ^ (cell.z as u32).wrapping_mul(0xcb1a_b31f)
+        ^ seed.wrapping_mul(0x9e37_79b9);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    h = h.wrapping_mul(0x297a_2d39);
+    h ^= h >> 15;
+    h as f32 / u32::MAX as f32
+}
+
+fn value_noise(position: Vec3, seed: u32) -> f32 {
+    let cell = position.floor();
+    let f = position - cell;
+    let f = f * f * (3.0 - 2.0 * f);
+    let cell = cell.as_ivec3();
+
+    let corner = |x, y, z| hash(cell + IVec3::new(x, y, z), seed);
+    let x00 = corner(0, 0, 0).lerp(corner(1, 0, 0), f.x);
+    let x10 = corner(0, 1, 0).lerp(corner(1, 1, 0), f.x);
+    let x01 = corner(0, 0, 1).lerp(corner(1, 0, 1), f.x);
+    let x11 = corner(0, 1, 1).lerp(corner(1, 1, 1), f.x);
+    x00.lerp(x10, f.y).lerp(x01.lerp(x11, f.y), f.z)
+}
+
+/// Sum of several octaves of noise, between 0 and 1
+fn fbm(position: Vec3, seed: u32) -> f32 {
+    const OCTAVES: u32 = 5;
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    let mut frequency = 1.0;
+    for octave in 0..OCTAVES {
+        sum += value_noise(position * frequency, seed.wrapping_add(octave)) * amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum / (1.0 - 0.5_f32.powi(OCTAVES as i32))
+}
+
+fn nebula(direction: Vec3, style: &SkyStyle) -> Vec3 {
+    let density = fbm(direction * 2.0, style.noise_seed);
+    let density = ((density - 0.45) / 0.35).clamp(0.0, 1.0);
+    let hue = fbm(direction * 1.5 + 17.0, style.noise_seed.wrapping_add(100));
+    let color = style.nebula_colors[0].lerp(style.nebula_colors[1], hue);
+    color * density * density * style.nebula_strength
+}
+
+/// A distant galaxy, drawn as an elliptical glow with a bright core
+struct Galaxy {
+    direction: Vec3,
+    color: Vec3,
+    /// Relative to the size of a face
+    radius: f32,
+    flattening: f32,
+    rotation: Mat2,
+}
+
+struct Star {
+    direction: Vec3,
+    color: Vec3,
+    /// Relative to the size of a face
+    radius: f32,
+}
+
+/// Linear colors of a single face, before they are converted to the texture format
+struct Face {
+    index: usize,
+    size: usize,
+    pixels: Vec<Vec3>,
+}
+
+impl Face {
+    /// Adds `color` around `direction`, if it is on this face. `brightness` gets the offset from
+    /// the center in pixels and returns how much of the color is added there. Anything beyond
+    /// `radius` pixels is skipped.
+    fn splat(
+        &mut self,
+        direction: Vec3,
+        radius: f32,
+        color: Vec3,
+        brightness: impl Fn(Vec2) -> f32,
+    ) {
+        let (face, u, v) = direction_to_face(direction);
+        if face != self.index {
+            return;
+        }
+        let center = (Vec2::new(u, v) + 1.0) / 2.0 * self.size as f32;
+        let min = (center - radius).floor().max(Vec2::ZERO).as_uvec2();
+        let max = (center + radius)
+            .ceil()
+            .min(Vec2::splat(self.size as f32 - 1.0))
+            .as_uvec2();
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let offset = Vec2::new(x as f32 + 0.5, y as f32 + 0.5) - center;
+                self.pixels[y as usize * self.size + x as usize] += color * brightness(offset);
+            }
+        }
+    }
+
+    /// The nebulae are sampled on a coarse grid, which covers the edges of the face
+    fn add_nebulae(&mut self, style: &SkyStyle) {
+        let size = self.size;
+        let grid_size = size.div_ceil(NEBULA_STEP) + 1;
+        let grid = (0..grid_size * grid_size)
+            .map(|i| {
+                let cell = Vec2::new((i % grid_size) as f32, (i / grid_size) as f32);
+                let uv = (cell * NEBULA_STEP as f32 / size as f32) * 2.0 - 1.0;
+                nebula(face_direction(self.index, uv.x, uv.y), style)
+            })
+            .collect::<Vec<_>>();
+
+        for y in 0..size {
+            for x in 0..size {
+                let cell = Vec2::new(x as f32, y as f32) / NEBULA_STEP as f32;
+                let (x0, y0) = (cell.x as usize, cell.y as usize);
+                let (x1, y1) = ((x0 + 1).min(grid_size - 1), (y0 + 1).min(grid_size - 1));
+                let f = cell.fract();
+                let top = grid[y0 * grid_size + x0].lerp(grid[y0 * grid_size + x1], f.x);
+                let bottom = grid[y1 * grid_size + x0].lerp(grid[y1 * grid_size + x1], f.x);
+                self.pixels[y * size + x] += top.lerp(bottom, f.y);
+            }
+        }
+    }
+
+    fn add_galaxy(&mut self, galaxy: &Galaxy) {
+        let radius = galaxy.radius * self.size as f32;
+        self.splat(galaxy.direction, radius, galaxy.color, |offset| {
+            let offset = galaxy.rotation * offset / radius;
+            let distance = Vec2::new(offset.x, offset.y / galaxy.flattening).length_squared();
+            0.5 * (-distance * 4.0).exp() + 0.8 * (-distance * 60.0).exp()
+        });
+    }
+
+    fn add_star(&mut self, star: &Star) {
+        // Stars on small faces would disappear otherwise
+        let radius = (star.radius * self.size as f32).max(0.6);
+        self.splat(star.direction, radius + 1.0, star.color, |offset| {
+            (radius + 0.5 - offset.length()).clamp(0.0, 1.0)
+        });
+    }
+
+    fn write_srgb(&self, data: &mut Vec<u8>) {
+        data.extend(self.pixels.iter().flat_map(|pixel| {
+            let pixel = pixel.min(Vec3::ONE);
+            let color = Srgba::from(LinearRgba::rgb(pixel.x, pixel.y, pixel.z));
+            [color.red, color.green, color.blue, 1.0].map(|c| (c * 255.0).round() as u8)
+        }));
+    }
+}
+
+fn random_direction(rng: &mut impl Rng) -> Vec3 {
+    let z: f32 = rng.gen_range(-1.0..1.0);
+    let angle = rng.gen_range(0.0..TAU);
+    let radius = (1.0 - z * z).sqrt();
+    Vec3::new(radius * angle.cos(), radius * angle.sin(), z)
+}
+
+/// Everything that is placed on the sky of one seed, shared by the faces
+struct SkyLayout {
+    style: SkyStyle,
+    galaxies: Vec<Galaxy>,
+    stars: Vec<Star>,
+}
+
+impl SkyLayout {
+    fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let style = SkyStyle::from_rng(&mut rng);
+
+        let galaxies = (0..style.galaxy_count)
+            .map(|_| {
+                // Galaxies are kept away from the edges of the faces, so they are not cut off
+                let direction = loop {
+                    let direction = random_direction(&mut rng);
+                    let (_, u, v) = direction_to_face(direction);
+                    if u.abs() < 0.8 && v.abs() < 0.8 {
+                        break direction;
+                    }
+                };
+                Galaxy {
+                    direction,
+                    color: to_vec3(NEBULA_COLORS[rng.gen_range(0..NEBULA_COLORS.len())])
+                        .lerp(Vec3::ONE, 0.6),
+                    radius: rng.gen_range(0.015..0.04),
+                    flattening: rng.gen_range(0.2..0.6),
+                    rotation: Mat2::from_angle(rng.gen_range(0.0..TAU)),
+                }
+            })
+            .collect::<Vec<_>>();
+        let stars = (0..style.star_count)
+            .map(|_| Star {
+                direction: random_direction(&mut rng),
+                color: to_vec3(STAR_COLORS[rng.gen_range(0..STAR_COLORS.len())])
+                    * rng.gen_range(0.7..1.0),
+                radius: rng.gen_range(0.001..0.0023),
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            style,
+            galaxies,
+            stars,
+        }
+    }
+
+    /// The sRGB pixels of the cube face `index`. Only one face at a time is kept in floating
+    /// point, the texture itself is 8 bits per channel.
+    fn face(&self, index: usize, face_size: u32) -> Vec<u8> {
+        let size = face_size.max(1) as usize;
+        let mut face = Face {
+            index,
+            size,
+            pixels: vec![self.style.background; size * size],
+        };
+        face.add_nebulae(&self.style);
+        for galaxy in &self.galaxies {
+            face.add_galaxy(galaxy);
+        }
+        for star in &self.stars {
+            face.add_star(star);
+        }
+        let mut data = Vec::with_capacity(size * size * 4);
+        face.write_srgb(&mut data);
+        data
+    }
+}
+
+fn cube_image(face_size: u32, data: Vec<u8>) -> Image {
+    let mut image = Image::new(
+        Extent3d {
+            width: face_size.max(1),
+            height: face_size.max(1),
+            depth_or_array_layers: 6,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..default()
+    });
+    image
+}
+
+/// Generates the cubemap of a starry sky with nebulae and distant galaxies, each `seed` gives a
+/// different sky.
+pub fn generate_skybox(seed: u64, face_size: u32) -> Image {
+    let layout = SkyLayout::new(seed);
+    let data = (0..6)
+        .flat_map(|index| layout.face(index, face_size))
+        .collect();
+    cube_image(face_size, data)
+}
+
+fn skybox_face_size(settings: &Settings) -> u32 {
+    FACE_SIZE / settings.graphics.skybox_downscale()
+}
+
+/// Black until the first sky is generated
+fn setup_skybox(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 6,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..default()
+    });
+    commands.insert_resource(CameraAssets {
+        skybox: images.add(image),
+        skybox_key: None,
+    });
+}
+
+/// Generates the sky for `seed` in the background, unless it is already there or on its way
+fn update_skybox(
+    seed: u64,
+    commands: &mut Commands,
+    camera_assets: &mut CameraAssets,
+    settings: &Settings,
+) {
+    let key = (seed, skybox_face_size(settings));
+    if camera_assets.skybox_key == Some(key) {
+        return;
+    }
+    camera_assets.skybox_key = Some(key);
+
+    commands.spawn(skybox_face_task(
+        key,
+        Arc::new(SkyLayout::new(seed)),
+        Vec::new(),
+    ));
+}
+
+/// Generates the next face after the ones in `data` on the compute pool. Each face is its own
+/// task that is only spawned once the previous one is done, so on wasm, where the tasks run on
+/// the main thread, a frame is drawn between the faces.
+fn skybox_face_task(key: (u64, u32), layout: Arc<SkyLayout>, mut data: Vec<u8>) -> TaskComponent {
+    let (_, face_size) = key;
+    let index = data.len() / (face_size.max(1) as usize).pow(2) / 4;
+    let face_layout = layout.clone();
+    TaskComponent::new_in(
+        AsyncComputeTaskPool::get(),
+        async move { face_layout.face(index, face_size) },
+        move |face, world| {
+            let Some(camera_assets) = world.get_resource::<CameraAssets>() else {
+                return;
+            };
+            // A different sky was requested in the meantime
+            if camera_assets.skybox_key != Some(key) {
+                return;
+            }
+            data.extend(face);
+            if index + 1 < 6 {
+                world.spawn(skybox_face_task(key, layout, data));
+                return;
+            }
+            let handle = camera_assets.skybox.clone();
+            world
+                .resource_mut::<Assets<Image>>()
+                .insert(&handle, cube_image(face_size, data));
+        },
+    )
+}
+
+fn menu_skybox(
+    mut commands: Commands,
+    mut camera_assets: ResMut<CameraAssets>,
+    settings: Res<Settings>,
+) {
+    update_skybox(
+        MENU_SKYBOX_SEED,
+        &mut commands,
+        &mut camera_assets,
+        &settings,
+    );
+}
+
+fn sector_skybox(
+    mut commands: Commands,
+    sector: Res<Sector>,
+    mut camera_assets: ResMut<CameraAssets>,
+    settings: Res<Settings>,
+) {
+    update_skybox(sector.seed, &mut commands, &mut camera_assets, &settings);
+}
+
+/// Generates the current sky again when the graphics preset changes its resolution
+fn resize_skybox(
+    mut commands: Commands,
+    mut camera_assets: ResMut<CameraAssets>,
+    settings: Res<Settings>,
+) {
+    if let Some((seed, _)) = camera_assets.skybox_key {
+        update_skybox(seed, &mut commands, &mut camera_assets, &settings);
+    }
+}
+
+/// Generates the sky behind the menus, and a different one for every sector
+pub struct SkyboxPlugin;
+
+impl Plugin for SkyboxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_skybox)
+            .add_systems(
+                OnEnter(AppState::StartScreen),
+                menu_skybox.in_set(Set::CameraSkyboxInit),
+            )
+            .add_systems(
+                OnEnter(AppState::TestScene),
+                menu_skybox.in_set(Set::CameraSkyboxInit),
+            )
+            .add_systems(
+                Update,
+                (
+                    sector_skybox.run_if(resource_exists_and_changed::<Sector>),
+                    resize_skybox.run_if(resource_changed::<Settings>),
+                ),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_generates_same_skybox() {
+        let a = generate_skybox(42, 16);
+        let b = generate_skybox(42, 16);
+        assert_eq!(a.data, b.data);
+        assert_eq!(a.texture_descriptor.array_layer_count(), 6);
+        assert_ne!(a.data, generate_skybox(43, 16).data);
+    }
+
+    #[test]
+    fn face_directions_round_trip() {
+        for face in 0..6 {
+            let direction = face_direction(face, 0.3, -0.5);
+            let (result, u, v) = direction_to_face(direction);
+            assert_eq!(result, face);
+            assert!((u - 0.3).abs() < 1e-5 && (v + 0.5).abs() < 1e-5);
+        }
+    }
+}
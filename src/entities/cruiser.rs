@@ -16,13 +16,17 @@ use rand::Rng;
 use space_game_common::EnemyType;
 
 use crate::components::health::HasShield;
+use crate::components::hull_damage::HullDamage;
+use crate::components::orbit::{Orbit, OrbitPositions};
 use crate::components::{
     colliders::VelocityColliderBundle, despawn_after::DespawnTimer, health::Health,
 };
+use crate::entities::camera::cinematic::{cruiser_arrival_sequence, PlayCameraSequence};
 use crate::entities::spaceship::bot::SpawnSquad;
 use crate::materials::exhaust::{ExhaustMaterial, ExhaustRes};
 use crate::materials::shield::{ShieldBundle, ShieldMaterial};
 use crate::materials::toon::{replace_with_toon_materials, ToonMaterial};
+use crate::materials::{emissive, EMISSIVE_INTENSITY};
 use crate::states::main_scene::GameTime;
 use crate::states::{game_running, AppState, DespawnOnCleanup, ON_GAME_STARTED};
 use crate::ui::game_hud::{ScoreGameEvent, SpawnEnemyIndicator};
@@ -35,8 +39,10 @@ use crate::utils::misc::{AsCommand, CollidingEntitiesExtension, Comparef32};
 use crate::utils::scene::{AnimationRoot, ReplaceMaterialPlugin};
 use crate::utils::sets::Set;
 
+use super::black_hole::{BlackHole, ACCRETION_DISK_RADIUS};
 use super::bullet::{Bullet, BulletTarget, BulletType};
 use super::explosion::ExplosionEvent;
+use super::nebula::InNebula;
 use super::planet::Planet;
 use super::space_station::SpaceStation;
 use super::spaceship::bot::EnemyTarget;
@@ -61,7 +67,7 @@ pub struct Cruiser {
 pub struct CruiserShield;
 
 #[derive(Component)]
-struct ShieldDisabled;
+pub(super) struct ShieldDisabled;
 
 #[derive(Component)]
 struct CruiserTrail;
@@ -80,9 +86,9 @@ struct CruiserRes {
 }
 
 #[derive(Component, Deref, DerefMut)]
-struct ShieldRegenerate(pub Timer);
+pub(super) struct ShieldRegenerate(pub Timer);
 
-struct DeactivateShield;
+pub(super) struct DeactivateShield;
 
 impl EntityCommand for DeactivateShield {
     fn apply(self, id: Entity, world: &mut World) {
@@ -100,7 +106,7 @@ impl EntityCommand for DeactivateShield {
     }
 }
 
-struct ActivateShield;
+pub(super) struct ActivateShield;
 
 impl EntityCommand for ActivateShield {
     fn apply(self, id: Entity, world: &mut World) {
@@ -153,8 +159,8 @@ fn cruiser_spawn_setup(
     if res.is_none() {
         commands.insert_resource(CruiserRes {
             exhaust_material: exhaust_materials.add(ExhaustMaterial {
-                inner_color: Srgba::hex("c0eff9").unwrap().into(),
-                outer_color: Srgba::hex("3ad8fc").unwrap().into(),
+                inner_color: emissive(Srgba::hex("c0eff9").unwrap(), EMISSIVE_INTENSITY).into(),
+                outer_color: emissive(Srgba::hex("3ad8fc").unwrap(), EMISSIVE_INTENSITY).into(),
                 ..default()
             }),
         });
@@ -167,14 +173,46 @@ pub struct SpawnCruiserEvent;
 struct NoGoZone {
     center: Vec3,
     radius: f32,
+    /// Orbiting bodies are checked against their future positions instead of `center`
+    orbiting_body: Option<Entity>,
+}
+
+/// Time between the samples taken when checking a path against an orbiting no-go zone
+const ORBIT_CHECK_STEP: f32 = 0.5;
+
+impl NoGoZone {
+    fn blocks_path(&self, start: Vec3, dest: Vec3, orbit_positions: &OrbitPositions) -> bool {
+        let radius = self.radius + 10.0;
+        let Some(body) = self.orbiting_body else {
+            let intersection = sphere_intersection(self.center, radius, start, dest - start);
+            let Some(intersection) = intersection else {
+                return false;
+            };
+            return intersection < 1.0;
+        };
+
+        // The cruiser stays at its destination, so the whole orbit has to be clear afterwards
+        let travel_time = start.distance(dest) / CRUISER_SPEED;
+        let check_time = travel_time + orbit_positions.period(body);
+        let steps = (check_time / ORBIT_CHECK_STEP).ceil() as usize;
+        (0..=steps).any(|i| {
+            let time = i as f32 * ORBIT_CHECK_STEP;
+            let cruiser_pos = start.lerp(dest, (time / travel_time).min(1.0));
+            orbit_positions
+                .position_in(body, time)
+                .is_some_and(|center| center.distance(cruiser_pos) < radius)
+        })
+    }
 }
 
 fn spawn_cruiser_events(
     mut spawn_events: EventReader<SpawnCruiserEvent>,
     mut commands: Commands,
     space_stations: Query<(&Transform, &SpaceshipCollisions), With<SpaceStation>>,
-    planets: Query<(&Transform, &Planet)>,
+    planets: Query<(Entity, &Transform, &Planet, Has<Orbit>)>,
     cruisers: Query<&Transform, With<Cruiser>>,
+    black_holes: Query<&Transform, With<BlackHole>>,
+    orbit_positions: OrbitPositions,
 ) {
     let mut rng = rand::thread_rng();
 
@@ -195,13 +233,15 @@ fn spawn_cruiser_events(
         no_go_zones.push(NoGoZone {
             center: transform.translation,
             radius: space_ship_collisions.bound_radius,
+            orbiting_body: None,
         });
     }
 
-    for (transform, planet) in &planets {
+    for (entity, transform, planet, orbiting) in &planets {
         no_go_zones.push(NoGoZone {
             center: transform.translation,
             radius: planet.radius,
+            orbiting_body: orbiting.then_some(entity),
         });
     }
 
@@ -209,6 +249,15 @@ fn spawn_cruiser_events(
         no_go_zones.push(NoGoZone {
             center: transform.translation,
             radius: CRUISER_HITBOX_SIZE.z,
+            orbiting_body: None,
+        });
+    }
+
+    for transform in &black_holes {
+        no_go_zones.push(NoGoZone {
+            center: transform.translation,
+            radius: ACCRETION_DISK_RADIUS,
+            orbiting_body: None,
         });
     }
 
@@ -236,14 +285,10 @@ fn spawn_cruiser_events(
             let start = dest + delta_normalized * START_DISTANCE;
 
             // Check if path intersects with one of the no-go zones
-            if no_go_zones.iter().any(|z| {
-                let intersection =
-                    sphere_intersection(z.center, z.radius + 10.0, start, dest - start);
-                let Some(intersection) = intersection else {
-                    return false;
-                };
-                intersection < 1.0
-            }) {
+            if no_go_zones
+                .iter()
+                .any(|z| z.blocks_path(start, dest, &orbit_positions))
+            {
                 info!("Path intersects with no-go zone, retrying");
                 continue;
             }
@@ -298,16 +343,15 @@ fn spawn_cruiser(
             ..default()
         },
         Health::new(100.0),
-        SpaceshipCollisions {
-            collision_damage: 5.0,
-            ..default()
-        },
+        HullDamage::default().with_effect_scale(3.0),
+        SpaceshipCollisions::default(),
         Enemy,
         DespawnOnCleanup,
         COLLISION_GROUPS,
         ShowOnMinimap {
             sprite: minimap_res.cruiser_indicator.clone(),
             size: 0.1.into(),
+            ..default()
         },
     ));
 }
@@ -338,7 +382,8 @@ fn finish_cruiser(
                     ..default()
                 },
                 rigid_body: RigidBody::Fixed,
-                active_collision_types: ActiveCollisionTypes::KINEMATIC_STATIC,
+                active_collision_types: ActiveCollisionTypes::KINEMATIC_STATIC
+                    | ActiveCollisionTypes::DYNAMIC_STATIC,
                 bullet_target: BulletTarget {
                     target_type: BulletType::Player,
                     bullet_damage: Some(10.0),
@@ -348,10 +393,7 @@ fn finish_cruiser(
                 ..default()
             },
             CruiserShield,
-            SpaceshipCollisions {
-                collision_damage: 10.0,
-                ..default()
-            },
+            SpaceshipCollisions::default(),
             COLLISION_GROUPS,
         ))
         .id();
@@ -370,13 +412,22 @@ fn finish_cruiser(
 }
 
 fn cruiser_animation_start(
-    query: Query<&AnimationRoot, (With<Cruiser>, Added<AnimationRoot>)>,
+    query: Query<(Entity, &AnimationRoot), (With<Cruiser>, Added<AnimationRoot>)>,
     mut animation_players: Query<&mut AnimationPlayer>,
     assets: Res<CruiserAssets>,
     mut animation_graphs: ResMut<Assets<AnimationGraph>>,
+    animation_clips: Res<Assets<AnimationClip>>,
     mut commands: Commands,
 ) {
-    for root in &query {
+    for (cruiser, root) in &query {
+        // Cut to the cruiser while it warps in
+        if let Some(clip) = animation_clips.get(&assets.cruiser_animation) {
+            commands.add(PlayCameraSequence(cruiser_arrival_sequence(
+                cruiser,
+                clip.duration(),
+            )));
+        }
+
         for entity in &root.player_entites {
             let Ok(mut player) = animation_players.get_mut(*entity) else {
                 continue;
@@ -494,6 +545,7 @@ fn cruiser_scene_setup(
                         base_orientation: *global_transform.compute_transform().forward(),
                         bullet_type: BulletType::Bot,
                         rotation_bounds: TURRET_ROTATION_BOUNDS,
+                        ammo: None,
                     },
                     CruiserTurret,
                 ));
@@ -514,11 +566,16 @@ fn cruiser_shield_death(
 }
 
 fn cruiser_shield_regenerate(
-    mut query: Query<(Entity, &mut Health, &mut ShieldRegenerate), With<CruiserShield>>,
+    mut query: Query<(Entity, &mut Health, &mut ShieldRegenerate, &Parent), With<CruiserShield>>,
+    in_nebula: Query<(), With<InNebula>>,
     time: Res<Time>,
     mut commands: Commands,
 ) {
-    for (entity, mut health, mut timer) in &mut query {
+    for (entity, mut health, mut timer, parent) in &mut query {
+        // The shield stays down while the cruiser is inside a nebula
+        if in_nebula.contains(parent.get()) {
+            continue;
+        }
         timer.tick(time.delta());
 
         if timer.just_finished() {
@@ -0,0 +1,825 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    color::palettes::css,
+    pbr::{NotShadowCaster, NotShadowReceiver},
+    prelude::*,
+    window::PrimaryWindow,
+};
+use bevy_mod_outline::OutlineBundle;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    components::health::{HasShield, Health},
+    materials::{
+        shield::{ShieldBundle, ShieldMaterial},
+        toon::{replace_with_toon_materials, ToonMaterial},
+    },
+    postprocessing::outline::OutlineHighlight,
+    states::{game_running, DespawnOnCleanup},
+    ui::health_bar_3d::SpawnHealthBar,
+    utils::{
+        materials::default_outline,
+        misc::{AsCommand, Comparef32},
+        scene::ReplaceMaterialPlugin,
+        sets::Set,
+    },
+};
+
+use super::{
+    bullet::{BulletTarget, BulletType},
+    camera::{window_to_viewport, MainCamera},
+    cruiser::{ActivateShield, DeactivateShield, ShieldDisabled, ShieldRegenerate},
+    explosion::ExplosionEvent,
+    powerup::PowerUpAssets,
+    space_station::SpaceStation,
+    spaceship::{bot::EnemyTarget, player::PlayerInventory, SpaceshipBundle, SpaceshipCollisions},
+    turret::Turret,
+    Enemy,
+};
+
+/// Game speed while the build mode is open
+const BUILD_MODE_TIME_SCALE: f32 = 0.1;
+/// Distance between two cells of the placement grid
+const GRID_SPACING: f32 = 6.0;
+/// Cells closer to a space station than this overlap with the station
+const GRID_INNER_RADIUS: f32 = 12.0;
+const GRID_OUTER_RADIUS: f32 = 30.0;
+
+const MAX_DEFENSE_LEVEL: u32 = 3;
+
+const MISSILE_RANGE: f32 = 100.0;
+const MISSILE_SPEED: f32 = 30.0;
+/// How fast a missile turns towards its target in radians per second
+const MISSILE_TURN_SPEED: f32 = 3.0;
+const MISSILE_HIT_RADIUS: f32 = 2.5;
+const MISSILE_LIFETIME: f32 = 6.0;
+
+const SHIELD_RADIUS: f32 = 8.0;
+const REPAIR_RANGE: f32 = 30.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DefenseType {
+    GunTurret,
+    MissileTurret,
+    ShieldProjector,
+    RepairDrone,
+}
+
+impl DefenseType {
+    pub const ALL: [Self; 4] = [
+        Self::GunTurret,
+        Self::MissileTurret,
+        Self::ShieldProjector,
+        Self::RepairDrone,
+    ];
+
+    /// Ore it takes to place a defense of this type
+    pub fn cost(self) -> u32 {
+        match self {
+            Self::GunTurret => 10,
+            Self::MissileTurret => 20,
+            Self::ShieldProjector => 15,
+            Self::RepairDrone => 15,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Self::GunTurret => t!("gun_turret"),
+            Self::MissileTurret => t!("missile_turret"),
+            Self::ShieldProjector => t!("shield_projector"),
+            Self::RepairDrone => t!("repair_drone"),
+        }
+        .to_string()
+    }
+
+    fn health(self) -> f32 {
+        match self {
+            Self::GunTurret => 60.0,
+            Self::MissileTurret => 80.0,
+            Self::ShieldProjector => 60.0,
+            Self::RepairDrone => 50.0,
+        }
+    }
+}
+
+/// A structure the player placed around a space station in build mode.
+#[derive(Component)]
+pub struct Defense {
+    pub kind: DefenseType,
+    pub level: u32,
+}
+
+impl Defense {
+    /// Ore it takes to upgrade to the next level. `None` if the level is already maxed out.
+    pub fn upgrade_cost(&self) -> Option<u32> {
+        (self.level < MAX_DEFENSE_LEVEL).then_some(self.kind.cost() * self.level)
+    }
+
+    fn max_health(&self) -> f32 {
+        self.kind.health() * (1.0 + 0.5 * (self.level - 1) as f32)
+    }
+
+    fn fire_interval(&self) -> f32 {
+        match self.kind {
+            DefenseType::MissileTurret => 3.0 - 0.5 * (self.level - 1) as f32,
+            _ => 1.0 / self.level as f32,
+        }
+    }
+
+    fn missile_damage(&self) -> f32 {
+        30.0 * self.level as f32
+    }
+
+    fn shield_health(&self) -> f32 {
+        100.0 * self.level as f32
+    }
+
+    fn repair_speed(&self) -> f32 {
+        8.0 * self.level as f32
+    }
+}
+
+#[derive(Component)]
+struct MissileLauncher {
+    timer: Timer,
+}
+
+#[derive(Component)]
+struct Missile {
+    target: Entity,
+    damage: f32,
+    lifetime: Timer,
+}
+
+#[derive(Component)]
+struct ProjectorShield;
+
+#[derive(Component)]
+struct RepairDrone;
+
+/// Open while the player places defenses. Time runs slower while this resource exists.
+#[derive(Resource)]
+pub struct BuildMode {
+    pub selected: DefenseType,
+    cursor: Option<BuildCursor>,
+}
+
+/// The grid cell under the mouse cursor
+struct BuildCursor {
+    position: Vec3,
+    action: BuildAction,
+}
+
+enum BuildAction {
+    Place,
+    Upgrade(Entity),
+    Invalid,
+}
+
+/// Preview of the selected defense at the grid cell under the cursor
+#[derive(Component)]
+struct BuildGhost;
+
+#[derive(Resource)]
+struct DefenseRes {
+    base_mesh: Handle<Mesh>,
+    launcher_mesh: Handle<Mesh>,
+    projector_mesh: Handle<Mesh>,
+    drone_mesh: Handle<Mesh>,
+    missile_mesh: Handle<Mesh>,
+    shield_mesh: Handle<Mesh>,
+    material: Handle<ToonMaterial>,
+    missile_material: Handle<ToonMaterial>,
+    ghost_mesh: Handle<Mesh>,
+    ghost_valid: Handle<StandardMaterial>,
+    ghost_invalid: Handle<StandardMaterial>,
+    ghost_upgrade: Handle<StandardMaterial>,
+}
+
+struct DefenseSpawnConfig {
+    kind: DefenseType,
+    position: Vec3,
+}
+
+fn defense_setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut toon_materials: ResMut<Assets<ToonMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let ghost_material = |color: Srgba| StandardMaterial {
+        base_color: color.with_alpha(0.4).into(),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    };
+
+    commands.insert_resource(DefenseRes {
+        base_mesh: meshes.add(Cylinder::new(1.5, 1.0)),
+        launcher_mesh: meshes.add(Cuboid::new(1.2, 1.2, 2.4)),
+        projector_mesh: meshes.add(Sphere { radius: 1.0 }.mesh().ico(1).unwrap()),
+        drone_mesh: meshes.add(Sphere { radius: 0.5 }.mesh().ico(0).unwrap()),
+        missile_mesh: meshes.add(Capsule3d::new(0.2, 0.8)),
+        shield_mesh: meshes.add(Sphere {
+            radius: SHIELD_RADIUS,
+        }),
+        material: toon_materials.add(ToonMaterial {
+            color: Srgba::hex("8A96A8").unwrap().into(),
+            ..default()
+        }),
+        missile_material: toon_materials.add(ToonMaterial {
+            color: Srgba::hex("EF4D34").unwrap().into(),
+            ..default()
+        }),
+        ghost_mesh: meshes.add(Cylinder::new(GRID_SPACING / 2.0 - 0.5, 0.2)),
+        ghost_valid: materials.add(ghost_material(css::LIME)),
+        ghost_invalid: materials.add(ghost_material(css::RED)),
+        ghost_upgrade: materials.add(ghost_material(css::YELLOW)),
+    });
+}
+
+fn spawn_defense(
+    In(config): In<DefenseSpawnConfig>,
+    mut commands: Commands,
+    res: Res<DefenseRes>,
+    powerup_assets: Res<PowerUpAssets>,
+    mut shield_materials: ResMut<Assets<ShieldMaterial>>,
+) {
+    let DefenseSpawnConfig { kind, position } = config;
+    let defense = Defense { kind, level: 1 };
+    let transform = Transform::from_translation(position);
+
+    let mut entity = commands.spawn((
+        DespawnOnCleanup,
+        Health::new(defense.max_health()),
+        BulletTarget {
+            target_type: BulletType::Bot,
+            bullet_damage: Some(10.0),
+        },
+        EnemyTarget::default(),
+        RigidBody::Fixed,
+        Collider::cylinder(1.0, 1.5),
+        ActiveCollisionTypes::DYNAMIC_STATIC | ActiveCollisionTypes::KINEMATIC_STATIC,
+        SpaceshipCollisions { bound_radius: 2.0 },
+        OutlineBundle {
+            outline: default_outline(),
+            ..default()
+        },
+    ));
+
+    if kind == DefenseType::GunTurret {
+        entity.insert((
+            Turret {
+                bullet_timer: Timer::from_seconds(defense.fire_interval(), TimerMode::Repeating),
+                bullet_type: BulletType::Player,
+                base_orientation: Vec3::Z,
+                rotation_bounds: (f32::NEG_INFINITY, f32::INFINITY),
+                ammo: None,
+            },
+            SceneBundle {
+                scene: powerup_assets.turret.clone(),
+                transform,
+                ..default()
+            },
+        ));
+    } else {
+        entity.insert(MaterialMeshBundle {
+            mesh: res.base_mesh.clone(),
+            material: res.material.clone(),
+            transform,
+            ..default()
+        });
+    }
+
+    let mut shield_entity = None;
+    entity.with_children(|c| match kind {
+        DefenseType::GunTurret => {}
+        DefenseType::MissileTurret => {
+            c.spawn(MaterialMeshBundle {
+                mesh: res.launcher_mesh.clone(),
+                material: res.material.clone(),
+                transform: Transform::from_xyz(0.0, 1.0, 0.0),
+                ..default()
+            });
+        }
+        DefenseType::ShieldProjector => {
+            c.spawn(MaterialMeshBundle {
+                mesh: res.projector_mesh.clone(),
+                material: res.material.clone(),
+                transform: Transform::from_xyz(0.0, 1.0, 0.0),
+                ..default()
+            });
+            let shield = c
+                .spawn((
+                    ShieldBundle {
+                        material_mesh: MaterialMeshBundle {
+                            mesh: res.shield_mesh.clone(),
+                            material: shield_materials.add(ShieldMaterial::default()),
+                            ..default()
+                        },
+                        collider: Collider::ball(SHIELD_RADIUS),
+                        rigid_body: RigidBody::Fixed,
+                        active_collision_types: ActiveCollisionTypes::KINEMATIC_STATIC,
+                        bullet_target: BulletTarget {
+                            target_type: BulletType::Bot,
+                            bullet_damage: Some(10.0),
+                        },
+                        health: Health::new(defense.shield_health()),
+                        ..default()
+                    },
+                    ProjectorShield,
+                    SpaceshipBundle::COLLISION_GROUPS,
+                    // Ships inside the shield must not be pushed out of it
+                    SolverGroups::new(Group::NONE, Group::NONE),
+                ))
+                .id();
+            shield_entity = Some(shield);
+        }
+        DefenseType::RepairDrone => {
+            c.spawn((
+                RepairDrone,
+                MaterialMeshBundle {
+                    mesh: res.drone_mesh.clone(),
+                    material: res.material.clone(),
+                    ..default()
+                },
+            ));
+        }
+    });
+
+    if kind == DefenseType::MissileTurret {
+        entity.insert(MissileLauncher {
+            timer: Timer::from_seconds(defense.fire_interval(), TimerMode::Repeating),
+        });
+    }
+    let entity = entity.insert(defense).id();
+
+    commands.add(SpawnHealthBar {
+        entity,
+        shield_entity,
+        scale: 0.25,
+        offset: Vec2::new(0., 10.),
+    });
+    if let Some(shield) = shield_entity {
+        commands.entity(shield).add(ActivateShield);
+    }
+}
+
+fn upgrade_defense(
+    defense: &mut Defense,
+    health: &mut Health,
+    turret: Option<Mut<Turret>>,
+    launcher: Option<Mut<MissileLauncher>>,
+) {
+    defense.level += 1;
+    health.max_health = defense.max_health();
+    health.heal(health.max_health);
+
+    let interval = std::time::Duration::from_secs_f32(defense.fire_interval());
+    if let Some(mut turret) = turret {
+        turret.bullet_timer.set_duration(interval);
+    }
+    if let Some(mut launcher) = launcher {
+        launcher.timer.set_duration(interval);
+    }
+}
+
+fn defense_death(
+    defenses: Query<(Entity, &Health, &GlobalTransform), (With<Defense>, Changed<Health>)>,
+    mut commands: Commands,
+    mut explosion_events: EventWriter<ExplosionEvent>,
+) {
+    for (entity, health, transform) in &defenses {
+        if health.is_dead() {
+            explosion_events.send(ExplosionEvent {
+                position: transform.translation(),
+                radius: 5.0,
+                ..default()
+            });
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn missile_launcher_update(
+    mut commands: Commands,
+    mut launchers: Query<(&Defense, &GlobalTransform, &mut MissileLauncher)>,
+    enemies: Query<(Entity, &GlobalTransform), With<Enemy>>,
+    res: Res<DefenseRes>,
+    time: Res<Time>,
+) {
+    for (defense, transform, mut launcher) in &mut launchers {
+        launcher.timer.tick(time.delta());
+        if !launcher.timer.just_finished() {
+            continue;
+        }
+
+        let position = transform.translation();
+        let Some((target, target_transform)) = enemies
+            .iter()
+            .filter(|(_, t)| t.translation().distance(position) < MISSILE_RANGE)
+            .min_by_key(|(_, t)| Comparef32(t.translation().distance(position)))
+        else {
+            continue;
+        };
+
+        let start = position + Vec3::Y * 2.0;
+        commands.spawn((
+            DespawnOnCleanup,
+            Missile {
+                target,
+                damage: defense.missile_damage(),
+                lifetime: Timer::from_seconds(MISSILE_LIFETIME, TimerMode::Once),
+            },
+            MaterialMeshBundle {
+                mesh: res.missile_mesh.clone(),
+                material: res.missile_material.clone(),
+                transform: Transform::from_translation(start)
+                    .looking_at(target_transform.translation(), Vec3::Y),
+                ..default()
+            },
+        ));
+    }
+}
+
+/// Steers missiles towards their target and lets them explode on impact.
+fn missile_update(
+    mut commands: Commands,
+    mut missiles: Query<(Entity, &mut Transform, &mut Missile)>,
+    mut targets: Query<(&GlobalTransform, &mut Health, Has<HasShield>), With<Enemy>>,
+    mut explosion_events: EventWriter<ExplosionEvent>,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut missile) in &mut missiles {
+        missile.lifetime.tick(time.delta());
+
+        let target = targets.get_mut(missile.target).ok();
+        let hit = target.as_ref().is_some_and(|(target_transform, ..)| {
+            target_transform
+                .translation()
+                .distance(transform.translation)
+                < MISSILE_HIT_RADIUS
+        });
+
+        if !hit && !missile.lifetime.finished() {
+            if let Some((target_transform, ..)) = &target {
+                let goal = transform.looking_at(target_transform.translation(), Vec3::Y);
+                transform.rotation = transform.rotation.slerp(
+                    goal.rotation,
+                    (MISSILE_TURN_SPEED * time.delta_seconds()).min(1.0),
+                );
+            }
+            let forward = transform.forward();
+            transform.translation += forward * MISSILE_SPEED * time.delta_seconds();
+            continue;
+        }
+
+        if let Some((_, mut health, has_shield)) = target.filter(|_| hit) {
+            if !has_shield {
+                health.take_damage(missile.damage);
+            }
+        }
+        explosion_events.send(ExplosionEvent {
+            position: transform.translation,
+            radius: 4.0,
+            ..default()
+        });
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn projector_shield_death(
+    shields: Query<(Entity, &Health), (With<ProjectorShield>, Without<ShieldDisabled>)>,
+    mut commands: Commands,
+) {
+    for (entity, health) in &shields {
+        if health.is_dead() {
+            commands.entity(entity).add(DeactivateShield);
+        }
+    }
+}
+
+fn projector_shield_regenerate(
+    mut shields: Query<
+        (Entity, &mut Health, &mut ShieldRegenerate, &Parent),
+        With<ProjectorShield>,
+    >,
+    defenses: Query<&Defense>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut health, mut timer, parent) in &mut shields {
+        timer.tick(time.delta());
+
+        if timer.just_finished() {
+            commands.entity(entity).add(ActivateShield);
+        }
+        if timer.finished() {
+            // The shield grows with the level of the projector
+            if let Ok(defense) = defenses.get(parent.get()) {
+                health.max_health = defense.shield_health();
+            }
+            health.heal(10.0 * time.delta_seconds());
+        }
+    }
+}
+
+/// Lets repair drones circle their dock and heal the most damaged structure in range.
+fn repair_drone_update(
+    mut drones: Query<&mut Transform, With<RepairDrone>>,
+    docks: Query<(&Defense, &GlobalTransform, &Children)>,
+    mut structures: Query<(&GlobalTransform, &mut Health), Or<(With<SpaceStation>, With<Defense>)>>,
+    time: Res<Time>,
+) {
+    let angle = time.elapsed_seconds() * 1.5 % TAU;
+    for (defense, dock_transform, children) in &docks {
+        let mut drone = drones.iter_many_mut(children.iter());
+        while let Some(mut transform) = drone.fetch_next() {
+            let offset = Vec2::from_angle(angle) * 2.5;
+            transform.translation = Vec3::new(offset.x, 2.0, offset.y);
+        }
+
+        let position = dock_transform.translation();
+        let target = structures
+            .iter_mut()
+            .filter(|(transform, health)| {
+                transform.translation().distance(position) < REPAIR_RANGE
+                    && !health.is_dead()
+                    && health.health < health.max_health
+            })
+            .min_by_key(|(_, health)| Comparef32(health.health / health.max_health));
+
+        if let Some((_, mut health)) = target {
+            health.heal(defense.repair_speed() * time.delta_seconds());
+        }
+    }
+}
+
+/// All cells of the placement grid around a space station
+fn grid_cells(center: Vec3) -> impl Iterator<Item = Vec3> {
+    let steps = (GRID_OUTER_RADIUS / GRID_SPACING) as i32;
+    (-steps..=steps)
+        .flat_map(move |x| (-steps..=steps).map(move |z| (x, z)))
+        .map(move |(x, z)| Vec3::new(x as f32, 0.0, z as f32) * GRID_SPACING)
+        .filter(|offset| (GRID_INNER_RADIUS..=GRID_OUTER_RADIUS).contains(&offset.length()))
+        .map(move |offset| center.with_y(0.0) + offset)
+}
+
+fn build_mode_toggle(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    build_mode: Option<Res<BuildMode>>,
+    ghosts: Query<Entity, With<BuildGhost>>,
+    stations: Query<(), With<SpaceStation>>,
+    mut time: ResMut<Time<Virtual>>,
+    res: Res<DefenseRes>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyB) {
+        return;
+    }
+
+    if build_mode.is_some() {
+        commands.remove_resource::<BuildMode>();
+        for ghost in &ghosts {
+            commands.entity(ghost).despawn_recursive();
+        }
+        time.set_relative_speed(1.0);
+        return;
+    }
+
+    if stations.is_empty() {
+        return;
+    }
+    commands.insert_resource(BuildMode {
+        selected: DefenseType::GunTurret,
+        cursor: None,
+    });
+    commands.spawn((
+        BuildGhost,
+        DespawnOnCleanup,
+        NotShadowCaster,
+        NotShadowReceiver,
+        MaterialMeshBundle {
+            mesh: res.ghost_mesh.clone(),
+            material: res.ghost_valid.clone(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    ));
+    time.set_relative_speed(BUILD_MODE_TIME_SCALE);
+}
+
+/// Leaves build mode as soon as the game stops running, e.g. when it is paused or over, so the
+/// slowed down time does not carry over.
+fn build_mode_exit(
+    mut commands: Commands,
+    ghosts: Query<Entity, With<BuildGhost>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    commands.remove_resource::<BuildMode>();
+    for ghost in &ghosts {
+        commands.entity(ghost).despawn_recursive();
+    }
+    time.set_relative_speed(1.0);
+}
+
+/// Finds the grid cell under the mouse cursor and moves the ghost there.
+#[allow(clippy::too_many_arguments)]
+fn build_mode_cursor(
+    mut build_mode: ResMut<BuildMode>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    stations: Query<&GlobalTransform, With<SpaceStation>>,
+    defenses: Query<(Entity, &GlobalTransform, &Defense)>,
+    mut ghosts: Query<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &mut Handle<StandardMaterial>,
+        ),
+        With<BuildGhost>,
+    >,
+    inventory: Res<PlayerInventory>,
+    res: Res<DefenseRes>,
+    mut gizmos: Gizmos,
+) {
+    for station in &stations {
+        for cell in grid_cells(station.translation()) {
+            gizmos.circle(cell, Dir3::Y, 0.5, Color::srgba(1.0, 1.0, 1.0, 0.3));
+        }
+    }
+
+    let point = windows
+        .get_single()
+        .ok()
+        .zip(cameras.get_single().ok())
+        .and_then(|(window, (camera, camera_transform))| {
+            let cursor = window_to_viewport(camera, window, window.cursor_position()?);
+            let ray = camera.viewport_to_world(camera_transform, cursor)?;
+            let distance = ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y))?;
+            Some(ray.get_point(distance))
+        });
+
+    let cell = point.and_then(|point| {
+        stations
+            .iter()
+            .flat_map(|station| grid_cells(station.translation()))
+            .find(|cell| cell.distance(point) < GRID_SPACING / 2.0)
+    });
+
+    let selected = build_mode.selected;
+    build_mode.cursor = cell.map(|position| {
+        let existing = defenses.iter().find(|(_, transform, _)| {
+            transform.translation().with_y(0.0).distance(position) < GRID_SPACING / 2.0
+        });
+        let action = match existing {
+            Some((entity, _, defense)) => match defense.upgrade_cost() {
+                Some(cost) if inventory.ore >= cost => BuildAction::Upgrade(entity),
+                _ => BuildAction::Invalid,
+            },
+            None if inventory.ore >= selected.cost() => BuildAction::Place,
+            None => BuildAction::Invalid,
+        };
+        BuildCursor { position, action }
+    });
+
+    for (mut transform, mut visibility, mut material) in &mut ghosts {
+        let Some(cursor) = &build_mode.cursor else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        transform.translation = cursor.position;
+        *material = match cursor.action {
+            BuildAction::Place => res.ghost_valid.clone(),
+            BuildAction::Upgrade(_) => res.ghost_upgrade.clone(),
+            BuildAction::Invalid => res.ghost_invalid.clone(),
+        };
+    }
+}
+
+/// Outlines the defense that would be upgraded by a click.
+fn highlight_hovered_defense(
+    mut commands: Commands,
+    build_mode: Option<Res<BuildMode>>,
+    highlighted: Query<Entity, (With<Defense>, With<OutlineHighlight>)>,
+) {
+    let hovered = build_mode
+        .as_ref()
+        .and_then(|build_mode| build_mode.cursor.as_ref())
+        .and_then(|cursor| match cursor.action {
+            BuildAction::Upgrade(entity) => Some(entity),
+            _ => None,
+        });
+
+    for entity in &highlighted {
+        if Some(entity) != hovered {
+            commands.entity(entity).remove::<OutlineHighlight>();
+        }
+    }
+    if let Some(entity) = hovered.filter(|entity| !highlighted.contains(*entity)) {
+        // The defense may have been destroyed this frame
+        commands.entity(entity).try_insert(OutlineHighlight {
+            color: css::YELLOW.into(),
+        });
+    }
+}
+
+fn build_mode_input(
+    mut commands: Commands,
+    mut build_mode: ResMut<BuildMode>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut defenses: Query<(
+        &mut Defense,
+        &mut Health,
+        Option<&mut Turret>,
+        Option<&mut MissileLauncher>,
+    )>,
+    mut inventory: ResMut<PlayerInventory>,
+) {
+    const KEYS: [KeyCode; 4] = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+    ];
+    for (key, kind) in KEYS.into_iter().zip(DefenseType::ALL) {
+        if keyboard_input.just_pressed(key) {
+            build_mode.selected = kind;
+        }
+    }
+
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(cursor) = &build_mode.cursor else {
+        return;
+    };
+
+    match cursor.action {
+        BuildAction::Place => {
+            let kind = build_mode.selected;
+            // The cursor was checked against the kind that was selected before this frame
+            let Some(ore) = inventory.ore.checked_sub(kind.cost()) else {
+                return;
+            };
+            inventory.ore = ore;
+            commands.add(spawn_defense.to_command(DefenseSpawnConfig {
+                kind,
+                position: cursor.position,
+            }));
+        }
+        BuildAction::Upgrade(entity) => {
+            let Ok((mut defense, mut health, turret, launcher)) = defenses.get_mut(entity) else {
+                return;
+            };
+            let Some(ore) = defense
+                .upgrade_cost()
+                .and_then(|cost| inventory.ore.checked_sub(cost))
+            else {
+                return;
+            };
+            inventory.ore = ore;
+            upgrade_defense(&mut defense, &mut health, turret, launcher);
+        }
+        BuildAction::Invalid => {}
+    }
+}
+
+pub struct DefensePlugin;
+
+impl Plugin for DefensePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ReplaceMaterialPlugin::<Defense, _>::new(
+            replace_with_toon_materials(ToonMaterial {
+                disable_outline: true,
+                ..default()
+            }),
+        ))
+        .add_systems(Startup, defense_setup)
+        .add_systems(
+            Update,
+            build_mode_exit.run_if(resource_exists::<BuildMode>.and_then(not(game_running()))),
+        )
+        .add_systems(
+            Update,
+            (
+                (defense_death, missile_update).in_set(Set::ExplosionEvents),
+                missile_launcher_update,
+                projector_shield_death,
+                projector_shield_regenerate,
+                repair_drone_update,
+                build_mode_toggle,
+                (build_mode_cursor, build_mode_input)
+                    .chain()
+                    .after(build_mode_toggle)
+                    .run_if(resource_exists::<BuildMode>),
+                highlight_hovered_defense.after(build_mode_cursor),
+            )
+                .run_if(game_running()),
+        );
+    }
+}
@@ -1,17 +1,17 @@
+use std::sync::Arc;
+
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 use rand::{seq::SliceRandom, Rng};
 
 use crate::{
-    particles::fire_particles::FireParticleRes, states::DespawnOnCleanup, utils::sets::Set,
+    particles::{
+        fire_particles::FireParticleRes,
+        simulation::{Particle, ParticleCurve, ParticleCurves, Particles},
+    },
+    utils::sets::Set,
 };
 
-#[derive(Component)]
-pub struct ExplosionParticle {
-    spawn_time: f32,
-    size: f32,
-}
-
 #[derive(Event)]
 pub struct ExplosionEvent {
     pub position: Vec3,
@@ -31,12 +31,22 @@ impl Default for ExplosionEvent {
 
 fn spawn_explosion(
     mut events: EventReader<ExplosionEvent>,
-    mut commands: Commands,
-    time: Res<Time>,
+    mut particles: ResMut<Particles>,
     fire_res: Res<FireParticleRes>,
     velocity_query: Query<(&Velocity, &Transform)>,
 ) {
     const PARTICLE_COUNT: usize = 10;
+    const PARTICLE_SIZE: f32 = 0.4;
+    const START_PHASE_LENGTH: f32 = 0.2;
+    const END_PHASE_LENGTH: f32 = 0.5;
+    const LIFETIME: f32 = START_PHASE_LENGTH + END_PHASE_LENGTH;
+
+    // Particles quickly grow to their full size and then slowly shrink
+    let curves = Arc::new(ParticleCurves {
+        scale: ParticleCurve::new([(0.0, 0.0), (START_PHASE_LENGTH / LIFETIME, 1.0), (1.0, 0.0)]),
+        ..default()
+    });
+
     for event in events.read() {
         let mut rng = rand::thread_rng();
 
@@ -51,26 +61,11 @@ fn spawn_explosion(
         for _ in 0..(PARTICLE_COUNT * event.radius as usize) {
             let scale = rng.gen_range(0.3..1.0);
 
-            commands.spawn((
-                DespawnOnCleanup,
-                ExplosionParticle {
-                    spawn_time: time.elapsed_seconds(),
-                    size: scale * event.radius,
-                },
-                MaterialMeshBundle {
-                    mesh: fire_res.mesh.clone(),
-                    material: fire_res.materials.choose(&mut rng).unwrap().clone(),
-                    transform: Transform {
-                        translation: event.position + parent_pos,
-                        rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2),
-                        ..default()
-                    },
-                    inherited_visibility: InheritedVisibility::VISIBLE,
-                    ..default()
-                },
-                RigidBody::KinematicVelocityBased,
-                Velocity {
-                    linvel: parent_velocity
+            particles.spawn(
+                fire_res.materials.choose(&mut rng).unwrap(),
+                Particle::new(
+                    event.position + parent_pos,
+                    parent_velocity
                         + Vec3::new(
                             rng.gen_range(-1.0..1.0),
                             rng.gen_range(-1.0..1.0),
@@ -79,29 +74,11 @@ fn spawn_explosion(
                         .normalize()
                             * rng.gen_range(0.3..1.3)
                             * event.radius,
-                    ..default()
-                },
-            ));
-        }
-    }
-}
-
-fn explosion_particle_update(
-    time: Res<Time>,
-    mut particles: Query<(&mut Transform, &ExplosionParticle, Entity)>,
-    mut commands: Commands,
-) {
-    const START_PHASE_LENGTH: f32 = 0.2;
-    const END_PHASE_LENGTH: f32 = 0.5;
-    for (mut transform, particle, entity) in &mut particles {
-        let lifetime = time.elapsed_seconds() - particle.spawn_time;
-        if lifetime < START_PHASE_LENGTH {
-            transform.scale = Vec3::splat(lifetime / START_PHASE_LENGTH) * particle.size;
-        } else if lifetime < START_PHASE_LENGTH + END_PHASE_LENGTH {
-            transform.scale = Vec3::splat(1.0 - (lifetime - START_PHASE_LENGTH) / END_PHASE_LENGTH)
-                * particle.size;
-        } else {
-            commands.entity(entity).despawn();
+                    PARTICLE_SIZE * scale * event.radius,
+                    LIFETIME,
+                    curves.clone(),
+                ),
+            );
         }
     }
 }
@@ -111,7 +88,6 @@ pub struct ExplosionPlugin;
 impl Plugin for ExplosionPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Update, spawn_explosion.after(Set::ExplosionEvents))
-            .add_systems(Update, explosion_particle_update)
             .add_event::<ExplosionEvent>();
     }
 }
@@ -0,0 +1,157 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier3d::dynamics::Velocity;
+use rand::Rng;
+
+use crate::{
+    materials::nebula::{NebulaLayerBundle, NebulaMaterial},
+    states::{game_running, DespawnOnCleanup},
+    ui::minimap::{MinimapAssets, MinimapSize, ShowOnMinimap, MINIMAP_RANGE, MINIMAP_SIZE},
+};
+
+use super::{camera::MainCamera, cruiser::Cruiser, spaceship::Spaceship};
+
+/// Number of billboards a nebula is made of
+const NEBULA_LAYERS: usize = 4;
+/// Fraction of their velocity ships lose per second inside a nebula
+const NEBULA_DRAG: f32 = 0.8;
+
+/// A cloud of gas that blocks sensors, suspends regeneration and slows ships down.
+#[derive(Component, Debug)]
+pub struct Nebula {
+    pub radius: f32,
+}
+
+/// Added to ships and cruisers while they are inside a [`Nebula`].
+#[derive(Component)]
+pub struct InNebula;
+
+#[derive(Component)]
+struct NebulaLayer;
+
+#[derive(Resource)]
+pub struct NebulaRes {
+    mesh: Handle<Mesh>,
+}
+
+pub struct NebulaSpawnConfig {
+    pub pos: Vec3,
+    pub radius: f32,
+}
+
+fn nebula_setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    commands.insert_resource(NebulaRes {
+        mesh: meshes.add(Rectangle::new(2.0, 2.0)),
+    });
+}
+
+pub fn spawn_nebula(
+    In(config): In<NebulaSpawnConfig>,
+    mut commands: Commands,
+    res: Res<NebulaRes>,
+    mut materials: ResMut<Assets<NebulaMaterial>>,
+    minimap_assets: Res<MinimapAssets>,
+) {
+    let NebulaSpawnConfig { pos, radius } = config;
+    let mut rng = rand::thread_rng();
+
+    let hue = rng.gen_range(0.0..360.0);
+    let color = Color::hsl(hue, 0.6, 0.35);
+    let highlight_color = Color::hsl((hue + 40.0) % 360.0, 0.7, 0.7);
+
+    commands
+        .spawn((
+            DespawnOnCleanup,
+            SpatialBundle::from_transform(Transform::from_translation(pos)),
+            Nebula { radius },
+            ShowOnMinimap {
+                sprite: minimap_assets.planet_indicator.clone(),
+                size: MinimapSize::Custom(Vec2::splat(radius / MINIMAP_RANGE * MINIMAP_SIZE)),
+                color: color.with_alpha(0.5),
+            },
+        ))
+        .with_children(|parent| {
+            for i in 0..NEBULA_LAYERS {
+                let offset = Vec2::from_angle(rng.gen_range(0.0..TAU)) * radius * 0.2;
+                // The layers are above the ships, so they can hide in the cloud
+                let height = 2.0 + i as f32;
+                parent.spawn((
+                    NebulaLayer,
+                    NebulaLayerBundle {
+                        material_mesh: MaterialMeshBundle {
+                            mesh: res.mesh.clone(),
+                            material: materials.add(NebulaMaterial {
+                                color,
+                                highlight_color,
+                                seed: rng.gen_range(0.0..TAU),
+                                opacity: 0.35,
+                            }),
+                            transform: Transform::from_xyz(offset.x, height, offset.y)
+                                .with_scale(Vec3::splat(radius * rng.gen_range(0.9..1.2))),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ));
+            }
+        });
+}
+
+/// Turns the nebula layers towards the camera.
+fn nebula_billboard(
+    cameras: Query<&GlobalTransform, With<MainCamera>>,
+    mut layers: Query<&mut Transform, With<NebulaLayer>>,
+) {
+    let Ok(camera) = cameras.get_single() else {
+        return;
+    };
+    let rotation = camera.compute_transform().rotation;
+    for mut transform in &mut layers {
+        transform.rotation = rotation;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn nebula_occupancy(
+    mut commands: Commands,
+    nebulae: Query<(&Transform, &Nebula)>,
+    bodies: Query<
+        (Entity, &Transform, Has<InNebula>),
+        (Or<(With<Spaceship>, With<Cruiser>)>, Without<Nebula>),
+    >,
+) {
+    for (entity, transform, was_inside) in &bodies {
+        let inside = nebulae.iter().any(|(nebula_transform, nebula)| {
+            nebula_transform.translation.distance(transform.translation) < nebula.radius
+        });
+        if inside && !was_inside {
+            commands.entity(entity).insert(InNebula);
+        } else if !inside && was_inside {
+            commands.entity(entity).remove::<InNebula>();
+        }
+    }
+}
+
+fn nebula_drag(
+    mut ships: Query<&mut Velocity, (With<Spaceship>, With<InNebula>)>,
+    time: Res<Time>,
+) {
+    let factor = (-NEBULA_DRAG * time.delta_seconds()).exp();
+    for mut velocity in &mut ships {
+        velocity.linvel *= factor;
+    }
+}
+
+pub struct NebulaPlugin;
+
+impl Plugin for NebulaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, nebula_setup)
+            .add_systems(Update, nebula_billboard)
+            .add_systems(
+                Update,
+                (nebula_occupancy, nebula_drag).run_if(game_running()),
+            );
+    }
+}
@@ -8,14 +8,14 @@ use crate::materials::toon::PlanetMaterial;
 use crate::states::{AppState, DespawnOnCleanup};
 use crate::ui::minimap::{MinimapAssets, MinimapSize, ShowOnMinimap, MINIMAP_RANGE, MINIMAP_SIZE};
 use crate::utils::asset_loading::AppExtension;
-use crate::utils::misc::AsCommand;
 use crate::{
-    components::gravity::GravitySource,
-    states::ON_GAME_STARTED,
+    components::{
+        gravity::GravitySource,
+        orbit::{Orbit, OrbitCenter},
+    },
     utils::{collisions::PLANET_COLLISION_GROUP, materials::default_outline},
 };
 
-use super::space_station::{setup_space_station, SpaceStation};
 use super::{
     bullet::{BulletTarget, BulletType},
     spaceship::SpaceshipCollisions,
@@ -29,11 +29,11 @@ impl Plugin for PlanetPlugin {
             AppState::MainSceneLoading,
             AppState::StartScreenLoading,
         ])
+        .add_systems(Startup, planet_setup)
         .add_systems(
-            ON_GAME_STARTED,
-            planet_setup_main_scene.after(setup_space_station),
-        )
-        .add_systems(Startup, planet_setup);
+            PostUpdate,
+            planet_material_update.after(TransformSystem::TransformPropagate),
+        );
     }
 }
 
@@ -53,13 +53,27 @@ pub struct PlanetRes {
     mesh: Handle<Mesh>,
 }
 
-const PLANET_COUNT: usize = 8;
 const COLLISION_GROUPS: CollisionGroups = CollisionGroups::new(PLANET_COLLISION_GROUP, Group::ALL);
 
 pub struct PlanetSpawnConfig {
     pub color: Color,
     pub size: f32,
     pub pos: Vec3,
+    pub orbit: Option<Orbit>,
+    /// The orbit center of every moon is replaced with the planet
+    pub moons: Vec<PlanetSpawnConfig>,
+}
+
+pub fn planet_mass(size: f32) -> f32 {
+    size * 500.0
+}
+
+pub fn random_planet_color(rng: &mut impl Rng) -> Color {
+    Color::hsl(
+        rng.gen_range(0.0..360.0),
+        rng.gen_range(0.5..1.0),
+        rng.gen_range(0.5..0.8),
+    )
 }
 
 fn planet_setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
@@ -68,55 +82,6 @@ fn planet_setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
     });
 }
 
-pub fn planet_setup_main_scene(
-    mut commands: Commands,
-    space_stations: Query<&Transform, With<SpaceStation>>,
-) {
-    let mut rng = rand::thread_rng();
-
-    let mut planets: Vec<PlanetSpawnConfig> = Vec::with_capacity(PLANET_COUNT);
-
-    for _ in 0..PLANET_COUNT {
-        let size = rng.gen_range(7.0..25.0);
-
-        let color = Color::hsl(
-            rng.gen_range(0.0..360.0),
-            rng.gen_range(0.5..1.0),
-            rng.gen_range(0.5..0.8),
-        );
-
-        // Try 10 times to find a suitable position for the planet, then abort
-        for _ in 0..10 {
-            let pos = Vec3::new(
-                rng.gen_range(-200.0..200.0),
-                0.0,
-                rng.gen_range(-200.0..200.0),
-            );
-            // Planet should not be too close to other planets
-            if planets
-                .iter()
-                .any(|x| pos.distance(x.pos) < (size + x.size) * 1.5)
-            {
-                continue;
-            }
-            // Planet should not be too close to space stations
-            if space_stations
-                .iter()
-                .any(|station| station.translation.distance(pos) < size * 1.5)
-            {
-                continue;
-            }
-
-            planets.push(PlanetSpawnConfig { color, size, pos });
-            break;
-        }
-    }
-
-    for config in planets {
-        commands.add(spawn_planet.to_command(config));
-    }
-}
-
 pub fn spawn_planet(
     In(config): In<PlanetSpawnConfig>,
     mut commands: Commands,
@@ -125,7 +90,31 @@ pub fn spawn_planet(
     minimap_assets: Res<MinimapAssets>,
     planet_res: Res<PlanetRes>,
 ) {
-    let PlanetSpawnConfig { color, size, pos } = config;
+    spawn_planet_body(
+        &mut commands,
+        &mut materials,
+        &planet_assets,
+        &minimap_assets,
+        &planet_res,
+        config,
+    );
+}
+
+fn spawn_planet_body(
+    commands: &mut Commands,
+    materials: &mut Assets<PlanetMaterial>,
+    planet_assets: &PlanetAssets,
+    minimap_assets: &MinimapAssets,
+    planet_res: &PlanetRes,
+    config: PlanetSpawnConfig,
+) -> Entity {
+    let PlanetSpawnConfig {
+        color,
+        size,
+        pos,
+        orbit,
+        moons,
+    } = config;
 
     let mut rng = rand::thread_rng();
 
@@ -140,7 +129,7 @@ pub fn spawn_planet(
         ..Vec3::ZERO
     };
 
-    commands.spawn((
+    let mut planet = commands.spawn((
         DespawnOnCleanup,
         MaterialMeshBundle {
             mesh: planet_res.mesh.clone(),
@@ -169,17 +158,14 @@ pub fn spawn_planet(
         ActiveCollisionTypes::all(),
         ActiveEvents::COLLISION_EVENTS,
         GravitySource {
-            mass: size * 500.0,
+            mass: planet_mass(size),
             ..default()
         },
         OutlineBundle {
             outline: default_outline(),
             ..default()
         },
-        SpaceshipCollisions {
-            collision_damage: 10.0,
-            bound_radius: size,
-        },
+        SpaceshipCollisions { bound_radius: size },
         BulletTarget {
             target_type: BulletType::Both,
             bullet_damage: None,
@@ -187,6 +173,43 @@ pub fn spawn_planet(
         ShowOnMinimap {
             sprite: minimap_assets.planet_indicator.clone(),
             size: MinimapSize::Custom(Vec2::splat(size / MINIMAP_RANGE * MINIMAP_SIZE)),
+            ..default()
         },
     ));
+    if let Some(orbit) = orbit {
+        planet.insert(orbit);
+    }
+    let planet = planet.id();
+
+    for mut moon in moons {
+        if let Some(orbit) = moon.orbit.as_mut() {
+            orbit.center = OrbitCenter::Body(planet);
+        }
+        spawn_planet_body(
+            commands,
+            materials,
+            planet_assets,
+            minimap_assets,
+            planet_res,
+            moon,
+        );
+    }
+
+    planet
+}
+
+/// Keeps the lighting center of orbiting planets in sync with their position.
+fn planet_material_update(
+    planets: Query<
+        (&GlobalTransform, &Handle<PlanetMaterial>),
+        (With<Orbit>, Changed<GlobalTransform>),
+    >,
+    mut materials: ResMut<Assets<PlanetMaterial>>,
+) {
+    for (transform, material) in &planets {
+        let Some(material) = materials.get_mut(material) else {
+            continue;
+        };
+        material.center = transform.translation().extend(0.0);
+    }
 }
@@ -1,4 +1,7 @@
-use std::{f32::consts::FRAC_PI_2, time::Duration};
+use std::{
+    f32::consts::{FRAC_PI_2, TAU},
+    time::Duration,
+};
 
 use bevy::{
     ecs::world::Command,
@@ -16,7 +19,9 @@ use bevy_asset_loader::{
 use bevy_mod_outline::OutlineBundle;
 use bevy_rapier3d::{
     dynamics::RigidBody,
-    geometry::{ActiveCollisionTypes, Collider, CollidingEntities, CollisionGroups},
+    geometry::{
+        ActiveCollisionTypes, Collider, CollidingEntities, CollisionGroups, Group, SolverGroups,
+    },
 };
 use rand::{rngs::ThreadRng, Rng};
 
@@ -26,7 +31,8 @@ use crate::{
         shield::{ShieldBundle, ShieldMaterial},
         toon::{replace_with_toon_materials, ToonMaterial},
     },
-    states::{AppState, DespawnOnCleanup},
+    states::{game_running, AppState, DespawnOnCleanup},
+    ui::game_hud::Score,
     utils::{
         materials::default_outline, misc::CollidingEntitiesExtension, scene::ReplaceMaterialPlugin,
     },
@@ -35,17 +41,23 @@ use crate::{
 use super::{
     bullet::{BulletTarget, BulletType},
     spaceship::{
-        player::{Player, PlayerInventory},
-        SpaceshipBundle,
+        player::{Player, PlayerInventory, MAX_BOMBS, MAX_TURRETS},
+        IsPlayer, SpaceshipBundle,
     },
     turret::Turret,
 };
 
+/// Ore closer to the player than this is pulled in by the tractor beam
+const TRACTOR_BEAM_RANGE: f32 = 15.0;
+const TRACTOR_BEAM_SPEED: f32 = 25.0;
+
 #[derive(Component, Clone, Copy)]
 pub enum PowerUp {
     Shield,
     Bomb,
     Turret,
+    /// Dropped by asteroids, can be spent at space stations
+    Ore,
 }
 
 #[derive(Component)]
@@ -66,6 +78,7 @@ struct PowerupBundle {
     despawn_timer: DespawnTimer,
     active_collision_types: ActiveCollisionTypes,
     collision_groups: CollisionGroups,
+    solver_groups: SolverGroups,
     outline_bundle: OutlineBundle,
 }
 
@@ -79,6 +92,8 @@ impl Default for PowerupBundle {
             despawn_timer: DespawnTimer::new(Duration::from_secs(20)),
             active_collision_types: ActiveCollisionTypes::KINEMATIC_STATIC,
             collision_groups: SpaceshipBundle::COLLISION_GROUPS,
+            // Powerups are only picked up, spaceships should not bounce off them
+            solver_groups: SolverGroups::new(Group::NONE, Group::NONE),
             outline_bundle: OutlineBundle {
                 outline: default_outline(),
                 ..default()
@@ -169,6 +184,32 @@ impl Command for SpawnPowerup {
                         ));
                     });
             }
+            PowerUp::Ore => {
+                let mut rng = rand::thread_rng();
+                world.spawn((
+                    PowerupBundle {
+                        collider: Collider::ball(1.0),
+                        despawn_timer: DespawnTimer::new(Duration::from_secs(30)),
+                        ..default()
+                    },
+                    PowerUp::Ore,
+                    MaterialMeshBundle {
+                        transform: Transform {
+                            translation: self.pos,
+                            rotation: Quat::from_euler(
+                                EulerRot::XYZ,
+                                rng.gen_range(0.0..TAU),
+                                rng.gen_range(0.0..TAU),
+                                0.0,
+                            ),
+                            scale: Vec3::splat(rng.gen_range(0.4..0.6)),
+                        },
+                        mesh: res.ore_mesh.clone(),
+                        material: res.ore_material.clone(),
+                        ..default()
+                    },
+                ));
+            }
         }
     }
 }
@@ -186,13 +227,16 @@ fn shield_death(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn powerup_collisions(
     powerups: Query<(&CollidingEntities, &PowerUp, Entity)>,
     player: Query<Entity, With<Player>>,
     player_shields: Query<(), With<PlayerShield>>,
     mut commands: Commands,
     powerup_res: Res<PowerUpRes>,
+    mut shield_materials: ResMut<Assets<ShieldMaterial>>,
     mut player_inventory: ResMut<PlayerInventory>,
+    mut score: ResMut<Score>,
 ) {
     for (colliding_entities, powerup, entity) in powerups.iter() {
         let Some(player_entity) = colliding_entities.filter_fulfills_query(&player).next() else {
@@ -208,7 +252,8 @@ fn powerup_collisions(
                         ShieldBundle {
                             material_mesh: MaterialMeshBundle {
                                 mesh: powerup_res.shield_mesh.clone(),
-                                material: powerup_res.shield_material.clone(),
+                                // Every shield needs its own material for the impact ripples
+                                material: shield_materials.add(ShieldMaterial::default()),
                                 transform: Transform::from_scale(Vec3 {
                                     z: 1.3,
                                     ..Vec3::ONE
@@ -228,6 +273,8 @@ fn powerup_collisions(
                         PlayerShield,
                         DespawnTimer::new(Duration::from_secs(20)),
                         SpaceshipBundle::COLLISION_GROUPS,
+                        // The shield surrounds the player, so it must not push the player away
+                        SolverGroups::new(Group::NONE, Group::NONE),
                     ))
                     .id();
                 commands
@@ -236,33 +283,58 @@ fn powerup_collisions(
                     .insert(ShieldEnabled);
             }
             PowerUp::Bomb => {
-                if player_inventory.bombs < 3 {
+                if player_inventory.bombs < MAX_BOMBS {
                     player_inventory.bombs += 1;
                 } else {
                     continue;
                 }
             }
             PowerUp::Turret => {
-                if player_inventory.turrets < 3 {
+                if player_inventory.turrets < MAX_TURRETS {
                     player_inventory.turrets += 1;
                 } else {
                     continue;
                 }
             }
+            PowerUp::Ore => {
+                player_inventory.ore += 1;
+                score.ore_collected += 1;
+            }
         }
         commands.entity(entity).despawn_recursive();
     }
 }
 
+fn tractor_beam(
+    mut ores: Query<(&PowerUp, &mut Transform), Without<Player>>,
+    player: Query<&Transform, IsPlayer>,
+    time: Res<Time>,
+) {
+    let Ok(player_transform) = player.get_single() else {
+        return;
+    };
+    for (powerup, mut transform) in &mut ores {
+        if !matches!(powerup, PowerUp::Ore) {
+            continue;
+        }
+        let offset = player_transform.translation - transform.translation;
+        let distance = offset.length();
+        if distance > TRACTOR_BEAM_RANGE || distance < f32::EPSILON {
+            continue;
+        }
+        // The closer the ore gets, the faster it is pulled in
+        let speed = TRACTOR_BEAM_SPEED * (1.0 - distance / TRACTOR_BEAM_RANGE) + 5.0;
+        transform.translation += offset / distance * (speed * time.delta_seconds()).min(distance);
+    }
+}
+
 fn powerup_setup(
     mut commands: Commands,
-    mut shield_materials: ResMut<Assets<ShieldMaterial>>,
     mut toon_materials: ResMut<Assets<ToonMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
     commands.insert_resource(PowerUpRes {
         shield_mesh: meshes.add(Sphere { radius: 2. }),
-        shield_material: shield_materials.add(ShieldMaterial::default()),
         turret_halo: toon_materials.add(ToonMaterial {
             cull_mode: Some(Face::Front),
             disable_outline: true,
@@ -270,6 +342,11 @@ fn powerup_setup(
             ..default()
         }),
         turret_halo_mesh: meshes.add(Sphere { radius: 1. }),
+        ore_mesh: meshes.add(Sphere { radius: 1. }.mesh().ico(0).unwrap()),
+        ore_material: toon_materials.add(ToonMaterial {
+            color: Srgba::hex("E8A33D").unwrap().into(),
+            ..default()
+        }),
     });
 }
 
@@ -286,9 +363,10 @@ pub struct PowerUpAssets {
 #[derive(Resource)]
 struct PowerUpRes {
     pub shield_mesh: Handle<Mesh>,
-    pub shield_material: Handle<ShieldMaterial>,
     pub turret_halo: Handle<ToonMaterial>,
     pub turret_halo_mesh: Handle<Mesh>,
+    pub ore_mesh: Handle<Mesh>,
+    pub ore_material: Handle<ToonMaterial>,
 }
 
 pub struct PowerupPlugin;
@@ -308,6 +386,9 @@ impl Plugin for PowerupPlugin {
             LoadingStateConfig::new(AppState::MainSceneLoading).load_collection::<PowerUpAssets>(),
         )
         .add_systems(Startup, powerup_setup)
-        .add_systems(Update, (powerup_collisions, shield_death));
+        .add_systems(
+            Update,
+            (powerup_collisions, shield_death, tractor_beam).run_if(game_running()),
+        );
     }
 }
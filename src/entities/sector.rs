@@ -0,0 +1,731 @@
+use std::f32::consts::{PI, TAU};
+use std::ops::Range;
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    components::orbit::{Orbit, OrbitCenter},
+    states::ON_GAME_STARTED,
+    ui::minimap::MinimapAssets,
+    utils::misc::AsCommand,
+};
+
+use super::{
+    asteroid::{spawn_asteroid_field, AsteroidFieldSpawnConfig},
+    asteroid_belt::{spawn_asteroid_belt, AsteroidBeltSpawnConfig},
+    black_hole::{spawn_black_hole, BlackHoleKind, BlackHoleSpawnConfig, ACCRETION_DISK_RADIUS},
+    nebula::{spawn_nebula, NebulaSpawnConfig},
+    planet::{planet_mass, random_planet_color, spawn_planet, PlanetSpawnConfig},
+    space_station::{spawn_space_station, SpaceStationRes},
+    wormhole::spawn_wormhole_pair,
+};
+
+/// Where the player spawns, every sector is generated around this point
+pub const PLAYER_SPAWN: Vec3 = Vec3::ZERO;
+/// Half the side length of the square hazards, nebulae and asteroid fields are placed in
+const SECTOR_SIZE: f32 = 300.0;
+
+const STATION_DISTANCE: Range<f32> = 30.0..70.0;
+const STATION_SPACING: f32 = 40.0;
+/// Half the width of the corridor between the player spawn and a station that is kept free
+const STATION_CORRIDOR: f32 = 15.0;
+
+/// Distance between the player spawn and the barycenter of systems with a central body
+const CENTRAL_BODY_DISTANCE: f32 = 300.0;
+/// Mass of the system the planets orbit if there is no central body. It doesn't attract
+/// anything itself.
+const BARYCENTER_MASS: f32 = 2000.0;
+
+/// Space around black holes, white holes and wormholes that is kept free of planets and space
+/// stations
+const ANOMALY_CLEARANCE: f32 = ACCRETION_DISK_RADIUS + 20.0;
+/// Wormholes don't attract anything, so they need a lot less space
+const WORMHOLE_CLEARANCE: f32 = 15.0;
+/// Wormholes that are closer together than this wouldn't be much of a shortcut
+const WORMHOLE_MIN_DISTANCE: f32 = 200.0;
+
+const NEBULA_RADIUS: Range<f32> = 30.0..60.0;
+/// Space between nebulae and space stations, the stations should always be visible
+const NEBULA_CLEARANCE: f32 = 30.0;
+
+const ASTEROID_FIELD_RADIUS: f32 = 20.0;
+const ASTEROID_FIELD_SIZE: Range<usize> = 10..50;
+const BELT_WIDTH: f32 = 50.0;
+/// Asteroids per square unit of a belt
+const BELT_DENSITY: f32 = 0.015;
+/// Chance of a gas giant to have a ring of asteroids
+const RING_CHANCE: f64 = 0.6;
+/// Inner and outer radius of a ring relative to the size of the gas giant. The ring stays inside
+/// the space reserved for the gas giant.
+const RING_RADIUS: Range<f32> = 1.15..1.45;
+const RING_DENSITY: f32 = 0.04;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Biome {
+    /// A few planets inside a ring of asteroids
+    AsteroidBelt,
+    /// Small planets orbiting a single huge one
+    GasGiantSystem,
+    /// Asteroids everywhere and more hazards than anywhere else
+    DebrisField,
+    /// Planets orbiting two stars, which orbit each other
+    BinaryStar,
+}
+
+impl Biome {
+    pub const ALL: [Biome; 4] = [
+        Biome::AsteroidBelt,
+        Biome::GasGiantSystem,
+        Biome::DebrisField,
+        Biome::BinaryStar,
+    ];
+
+    pub fn from_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        Self::ALL[rng.gen_range(0..Self::ALL.len())]
+    }
+
+    fn rules(self) -> BiomeRules {
+        match self {
+            Biome::AsteroidBelt => BiomeRules {
+                central_body: CentralBody::None,
+                planet_count: 5,
+                planet_size: 7.0..20.0,
+                moon_chance: 0.3,
+                orbit_radius: 60.0..450.0,
+                station_count: 1,
+                black_holes: 0,
+                white_holes: 1,
+                wormhole_pairs: 1,
+                nebulae: 2,
+                belt_radius: Some(110.0..170.0),
+                asteroid_fields: 2,
+            },
+            Biome::GasGiantSystem => BiomeRules {
+                central_body: CentralBody::GasGiant { size: 30.0..40.0 },
+                planet_count: 5,
+                planet_size: 4.0..10.0,
+                moon_chance: 0.1,
+                orbit_radius: 70.0..200.0,
+                station_count: 1,
+                black_holes: 0,
+                white_holes: 0,
+                wormhole_pairs: 1,
+                nebulae: 3,
+                belt_radius: None,
+                asteroid_fields: 3,
+            },
+            Biome::DebrisField => BiomeRules {
+                central_body: CentralBody::None,
+                planet_count: 3,
+                planet_size: 7.0..18.0,
+                moon_chance: 0.2,
+                orbit_radius: 90.0..450.0,
+                station_count: 2,
+                black_holes: 1,
+                white_holes: 1,
+                wormhole_pairs: 1,
+                nebulae: 3,
+                belt_radius: None,
+                asteroid_fields: 14,
+            },
+            Biome::BinaryStar => BiomeRules {
+                central_body: CentralBody::BinaryStar {
+                    size: 10.0..14.0,
+                    separation: 25.0..35.0,
+                },
+                planet_count: 5,
+                planet_size: 7.0..22.0,
+                moon_chance: 0.4,
+                orbit_radius: 80.0..210.0,
+                station_count: 1,
+                black_holes: 1,
+                white_holes: 0,
+                wormhole_pairs: 1,
+                nebulae: 2,
+                belt_radius: None,
+                asteroid_fields: 3,
+            },
+        }
+    }
+}
+
+enum CentralBody {
+    None,
+    GasGiant {
+        size: Range<f32>,
+    },
+    BinaryStar {
+        size: Range<f32>,
+        /// Distance of each star from the barycenter
+        separation: Range<f32>,
+    },
+}
+
+struct BiomeRules {
+    central_body: CentralBody,
+    planet_count: usize,
+    planet_size: Range<f32>,
+    moon_chance: f64,
+    /// Semi-major axes of the planets around the barycenter
+    orbit_radius: Range<f32>,
+    station_count: usize,
+    black_holes: usize,
+    white_holes: usize,
+    wormhole_pairs: usize,
+    nebulae: usize,
+    /// Distance of the asteroid belt from the barycenter
+    belt_radius: Option<Range<f32>>,
+    /// Asteroid fields scattered across the sector, in addition to the belt
+    asteroid_fields: usize,
+}
+
+/// The sector that is being played. Inserted when a game starts.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct Sector {
+    pub seed: u64,
+    pub biome: Biome,
+}
+
+/// Everything a sector consists of, before it is spawned.
+#[derive(Default)]
+pub struct SectorLayout {
+    pub stations: Vec<Vec3>,
+    pub planets: Vec<PlanetSpawnConfig>,
+    pub black_holes: Vec<BlackHoleSpawnConfig>,
+    pub wormholes: Vec<(Vec3, Vec3)>,
+    pub nebulae: Vec<NebulaSpawnConfig>,
+    pub asteroid_fields: Vec<AsteroidFieldSpawnConfig>,
+    pub asteroid_belts: Vec<AsteroidBeltSpawnConfig>,
+}
+
+#[inline]
+fn overlaps(a: &Range<f32>, b: &Range<f32>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+fn closest_point_on_segment(start: Vec3, end: Vec3, point: Vec3) -> Vec3 {
+    let segment = end - start;
+    if segment.length_squared() < f32::EPSILON {
+        return start;
+    }
+    let t = ((point - start).dot(segment) / segment.length_squared()).clamp(0.0, 1.0);
+    start + segment * t
+}
+
+/// The smallest and the largest distance of the points of a segment from `center`.
+fn distance_range(start: Vec3, end: Vec3, center: Vec3) -> Range<f32> {
+    closest_point_on_segment(start, end, center).distance(center)
+        ..start.distance(center).max(end.distance(center))
+}
+
+struct SectorGenerator {
+    rng: StdRng,
+    rules: BiomeRules,
+    layout: SectorLayout,
+    /// The point the planets orbit around
+    barycenter: Vec3,
+    /// Mass the planets orbit
+    system_mass: f32,
+    /// Ways from the player spawn to the stations, which must never be blocked
+    corridors: Vec<(Vec3, Vec3)>,
+    anomalies: Vec<Vec3>,
+    /// Distances from the barycenter that are occupied by the central body or the belt
+    solid_bands: Vec<Range<f32>>,
+    /// Distances from the barycenter that are swept by the planets and their moons
+    orbit_bands: Vec<Range<f32>>,
+    /// Distances from the barycenter that planets have to stay out of
+    bands: Vec<Range<f32>>,
+}
+
+impl SectorGenerator {
+    fn random_position(&mut self) -> Vec3 {
+        PLAYER_SPAWN
+            + Vec3::new(
+                self.rng.gen_range(-SECTOR_SIZE..SECTOR_SIZE),
+                0.0,
+                self.rng.gen_range(-SECTOR_SIZE..SECTOR_SIZE),
+            )
+    }
+
+    /// Whether `pos` is at least `clearance` away from the stations and the ways to them.
+    fn is_clear(&self, pos: Vec3, clearance: f32) -> bool {
+        self.corridors.iter().all(|(start, end)| {
+            closest_point_on_segment(*start, *end, pos).distance(pos) >= clearance
+        })
+    }
+
+    fn place_stations(&mut self) {
+        for _ in 0..self.rules.station_count {
+            // Try 10 times to find a suitable position, then abort
+            for _ in 0..10 {
+                let direction = Vec2::from_angle(self.rng.gen_range(0.0..TAU));
+                let distance = self.rng.gen_range(STATION_DISTANCE);
+                let pos = PLAYER_SPAWN + Vec3::new(direction.x, 0.0, direction.y) * distance;
+                if self
+                    .layout
+                    .stations
+                    .iter()
+                    .any(|other| other.distance(pos) < STATION_SPACING)
+                {
+                    continue;
+                }
+
+                self.layout.stations.push(pos);
+                self.corridors.push((PLAYER_SPAWN, pos));
+                break;
+            }
+        }
+    }
+
+    fn place_barycenter(&mut self) {
+        self.barycenter = match self.rules.central_body {
+            // The player starts in the middle of the system
+            CentralBody::None => PLAYER_SPAWN,
+            // The player shouldn't start right next to the central body
+            _ => {
+                let direction = Vec2::from_angle(self.rng.gen_range(0.0..TAU));
+                PLAYER_SPAWN + Vec3::new(direction.x, 0.0, direction.y) * CENTRAL_BODY_DISTANCE
+            }
+        };
+
+        // A planet whose orbit crosses a corridor would block it sooner or later
+        let barycenter = self.barycenter;
+        for (start, end) in &self.corridors {
+            let range = distance_range(*start, *end, barycenter);
+            self.bands
+                .push((range.start - STATION_CORRIDOR)..(range.end + STATION_CORRIDOR));
+        }
+    }
+
+    fn place_central_body(&mut self) {
+        let barycenter = self.barycenter;
+        match &self.rules.central_body {
+            CentralBody::None => {
+                self.system_mass = BARYCENTER_MASS;
+            }
+            CentralBody::GasGiant { size } => {
+                let size = self.rng.gen_range(size.clone());
+                self.layout.planets.push(PlanetSpawnConfig {
+                    color: random_planet_color(&mut self.rng),
+                    size,
+                    pos: barycenter,
+                    orbit: None,
+                    moons: Vec::new(),
+                });
+                self.system_mass = planet_mass(size);
+                self.solid_bands.push(0.0..size * 1.5);
+                if self.rng.gen_bool(RING_CHANCE) {
+                    self.layout.asteroid_belts.push(AsteroidBeltSpawnConfig {
+                        center: barycenter,
+                        radius: (size * RING_RADIUS.start)..(size * RING_RADIUS.end),
+                        density: RING_DENSITY,
+                        seed: self.rng.gen(),
+                    });
+                }
+            }
+            CentralBody::BinaryStar { size, separation } => {
+                let size = self.rng.gen_range(size.clone());
+                let separation = self.rng.gen_range(separation.clone());
+                let star_mass = planet_mass(size);
+                let argument_of_periapsis = self.rng.gen_range(0.0..TAU);
+
+                for mean_anomaly in [0.0, PI] {
+                    let orbit = Orbit {
+                        center: OrbitCenter::Point(barycenter),
+                        semi_major_axis: separation,
+                        eccentricity: 0.0,
+                        argument_of_periapsis,
+                        mean_anomaly,
+                        // Both stars attract each other from twice the distance to the barycenter,
+                        // which is like a quarter of the mass at the barycenter
+                        central_mass: star_mass / 4.0,
+                    };
+                    self.layout.planets.push(PlanetSpawnConfig {
+                        color: Color::hsl(self.rng.gen_range(20.0..55.0), 1.0, 0.6),
+                        size,
+                        pos: barycenter + orbit.offset_at(0.0),
+                        orbit: Some(orbit),
+                        moons: Vec::new(),
+                    });
+                }
+                self.system_mass = BARYCENTER_MASS + star_mass * 2.0;
+                self.solid_bands.push(0.0..(separation + size * 1.5));
+            }
+        }
+        self.bands.extend(self.solid_bands.iter().cloned());
+    }
+
+    fn place_belt(&mut self) {
+        let Some(belt_radius) = self.rules.belt_radius.clone() else {
+            return;
+        };
+        let barycenter = self.barycenter;
+        // Leave some space for asteroids drifting out of the belt
+        let half_width = BELT_WIDTH / 2.0 + 10.0;
+
+        // Try 10 times to find a suitable radius, then abort
+        for _ in 0..10 {
+            let radius = self.rng.gen_range(belt_radius.clone());
+            let band = (radius - half_width)..(radius + half_width);
+            if self.bands.iter().any(|other| overlaps(&band, other)) {
+                continue;
+            }
+
+            self.layout.asteroid_belts.push(AsteroidBeltSpawnConfig {
+                center: barycenter,
+                radius: (radius - BELT_WIDTH / 2.0)..(radius + BELT_WIDTH / 2.0),
+                density: BELT_DENSITY,
+                seed: self.rng.gen(),
+            });
+
+            self.solid_bands.push(band.clone());
+            self.bands.push(band);
+            break;
+        }
+    }
+
+    /// Places a black hole, white hole or wormhole. `min_distance` keeps it away from another
+    /// point, e.g. the other end of a wormhole.
+    fn place_anomaly(&mut self, clearance: f32, min_distance: Option<(Vec3, f32)>) -> Option<Vec3> {
+        // Try 10 times to find a suitable position between the planets, then 10 times outside of
+        // the planetary system, then abort
+        for attempt in 0..20 {
+            let pos = if attempt < 10 {
+                self.random_position()
+            } else {
+                let outermost = self.bands.iter().map(|band| band.end).fold(0.0, f32::max);
+                let direction = Vec2::from_angle(self.rng.gen_range(0.0..TAU));
+                let distance = outermost + clearance + self.rng.gen_range(0.0..50.0);
+                self.barycenter + Vec3::new(direction.x, 0.0, direction.y) * distance
+            };
+            if let Some((other, distance)) = min_distance
+                && other.distance(pos) < distance
+            {
+                continue;
+            }
+            // Their gravity should not make the space stations unreachable
+            if !self.is_clear(pos, clearance * 2.0) {
+                continue;
+            }
+            if self
+                .anomalies
+                .iter()
+                .any(|other| other.distance(pos) < clearance * 2.0)
+            {
+                continue;
+            }
+            let distance = pos.distance(self.barycenter);
+            let band = (distance - clearance)..(distance + clearance);
+            // Nothing should orbit through it
+            if self
+                .solid_bands
+                .iter()
+                .chain(self.orbit_bands.iter())
+                .any(|other| overlaps(&band, other))
+            {
+                continue;
+            }
+
+            self.bands.push(band);
+            self.anomalies.push(pos);
+            return Some(pos);
+        }
+        None
+    }
+
+    fn place_anomalies(&mut self) {
+        let kinds = std::iter::repeat(BlackHoleKind::Black)
+            .take(self.rules.black_holes)
+            .chain(std::iter::repeat(BlackHoleKind::White).take(self.rules.white_holes));
+        for kind in kinds {
+            if let Some(pos) = self.place_anomaly(ANOMALY_CLEARANCE, None) {
+                self.layout
+                    .black_holes
+                    .push(BlackHoleSpawnConfig { kind, pos });
+            }
+        }
+
+        for _ in 0..self.rules.wormhole_pairs {
+            let Some(pos_a) = self.place_anomaly(WORMHOLE_CLEARANCE, None) else {
+                continue;
+            };
+            // A single end is harmless, it is just never spawned
+            if let Some(pos_b) =
+                self.place_anomaly(WORMHOLE_CLEARANCE, Some((pos_a, WORMHOLE_MIN_DISTANCE)))
+            {
+                self.layout.wormholes.push((pos_a, pos_b));
+            }
+        }
+    }
+
+    fn place_planets(&mut self) {
+        let barycenter = self.barycenter;
+
+        for _ in 0..self.rules.planet_count {
+            let size = self.rng.gen_range(self.rules.planet_size.clone());
+            let color = random_planet_color(&mut self.rng);
+
+            let moon = self.rng.gen_bool(self.rules.moon_chance).then(|| {
+                let moon_size = size * self.rng.gen_range(0.15..0.3);
+                let orbit = Orbit {
+                    center: OrbitCenter::Point(Vec3::ZERO),
+                    semi_major_axis: size * self.rng.gen_range(2.5..3.5),
+                    eccentricity: self.rng.gen_range(0.0..0.1),
+                    argument_of_periapsis: self.rng.gen_range(0.0..TAU),
+                    mean_anomaly: self.rng.gen_range(0.0..TAU),
+                    central_mass: planet_mass(size),
+                };
+                (moon_size, orbit)
+            });
+            let reach = moon
+                .as_ref()
+                .map_or(size, |(moon_size, orbit)| orbit.apoapsis() + moon_size)
+                * 1.5;
+
+            // Try 10 times to find a suitable orbit for the planet, then abort
+            for _ in 0..10 {
+                let orbit = Orbit {
+                    center: OrbitCenter::Point(barycenter),
+                    semi_major_axis: self.rng.gen_range(self.rules.orbit_radius.clone()),
+                    eccentricity: self.rng.gen_range(0.0..0.1),
+                    argument_of_periapsis: self.rng.gen_range(0.0..TAU),
+                    mean_anomaly: self.rng.gen_range(0.0..TAU),
+                    central_mass: self.system_mass,
+                };
+                let band = (orbit.periapsis() - reach)..(orbit.apoapsis() + reach);
+
+                // Orbits should not cross, otherwise the planets would collide eventually
+                if self.bands.iter().any(|other| overlaps(&band, other)) {
+                    continue;
+                }
+
+                let pos = barycenter + orbit.offset_at(0.0);
+                let moons = moon
+                    .iter()
+                    .map(|(moon_size, moon_orbit)| PlanetSpawnConfig {
+                        color: random_planet_color(&mut self.rng),
+                        size: *moon_size,
+                        pos: pos + moon_orbit.offset_at(0.0),
+                        orbit: Some(moon_orbit.clone()),
+                        moons: Vec::new(),
+                    })
+                    .collect();
+
+                self.orbit_bands.push(band.clone());
+                self.bands.push(band);
+                self.layout.planets.push(PlanetSpawnConfig {
+                    color,
+                    size,
+                    pos,
+                    orbit: Some(orbit),
+                    moons,
+                });
+                break;
+            }
+        }
+    }
+
+    fn place_nebulae(&mut self) {
+        for _ in 0..self.rules.nebulae {
+            let radius = self.rng.gen_range(NEBULA_RADIUS);
+            // Try 10 times to find a suitable position, then abort
+            for _ in 0..10 {
+                let pos = self.random_position();
+                if !self.is_clear(pos, radius + NEBULA_CLEARANCE) {
+                    continue;
+                }
+                if self
+                    .layout
+                    .nebulae
+                    .iter()
+                    .any(|other| other.pos.distance(pos) < radius + other.radius)
+                {
+                    continue;
+                }
+
+                self.layout.nebulae.push(NebulaSpawnConfig { pos, radius });
+                break;
+            }
+        }
+    }
+
+    fn place_asteroid_fields(&mut self) {
+        let barycenter = self.barycenter;
+
+        for _ in 0..self.rules.asteroid_fields {
+            // Try 10 times to find a suitable position, then abort
+            for _ in 0..10 {
+                let pos = self.random_position();
+                if !self.is_clear(pos, ASTEROID_FIELD_RADIUS + STATION_CORRIDOR) {
+                    continue;
+                }
+                // Planets would plow through the field
+                let distance = pos.distance(barycenter);
+                let band = (distance - ASTEROID_FIELD_RADIUS)..(distance + ASTEROID_FIELD_RADIUS);
+                if self
+                    .solid_bands
+                    .iter()
+                    .chain(self.orbit_bands.iter())
+                    .any(|other| overlaps(&band, other))
+                {
+                    continue;
+                }
+                if self
+                    .anomalies
+                    .iter()
+                    .any(|other| other.distance(pos) < ANOMALY_CLEARANCE + ASTEROID_FIELD_RADIUS)
+                {
+                    continue;
+                }
+                if self
+                    .layout
+                    .asteroid_fields
+                    .iter()
+                    .any(|other| other.pos.distance(pos) < other.radius + ASTEROID_FIELD_RADIUS)
+                {
+                    continue;
+                }
+
+                let count = self.rng.gen_range(ASTEROID_FIELD_SIZE);
+                self.layout.asteroid_fields.push(AsteroidFieldSpawnConfig {
+                    pos,
+                    radius: ASTEROID_FIELD_RADIUS,
+                    count,
+                });
+                break;
+            }
+        }
+    }
+}
+
+/// Generates the layout of a sector. The same seed and biome always result in the same layout.
+///
+/// There is always at least one station, and the straight way from the player spawn to every
+/// station is never crossed by a planet or blocked by a hazard.
+pub fn generate_sector(seed: u64, biome: Biome) -> SectorLayout {
+    let mut generator = SectorGenerator {
+        rng: StdRng::seed_from_u64(seed),
+        rules: biome.rules(),
+        layout: SectorLayout::default(),
+        barycenter: PLAYER_SPAWN,
+        system_mass: BARYCENTER_MASS,
+        corridors: Vec::new(),
+        anomalies: Vec::new(),
+        solid_bands: Vec::new(),
+        orbit_bands: Vec::new(),
+        bands: Vec::new(),
+    };
+
+    // Stations come first, everything else has to keep the way to them free
+    generator.place_stations();
+    generator.place_barycenter();
+    generator.place_central_body();
+    generator.place_belt();
+    generator.place_planets();
+    // Hazards go into the gaps between the orbits, or outside of the planetary system
+    generator.place_anomalies();
+    generator.place_nebulae();
+    generator.place_asteroid_fields();
+
+    generator.layout
+}
+
+fn sector_setup_main_scene(
+    mut commands: Commands,
+    station_res: Res<SpaceStationRes>,
+    minimap_assets: Res<MinimapAssets>,
+) {
+    let seed = rand::random();
+    let sector = Sector {
+        seed,
+        biome: Biome::from_seed(seed),
+    };
+    info!("Generating sector {} ({:?})", sector.seed, sector.biome);
+    commands.insert_resource(sector);
+
+    let layout = generate_sector(sector.seed, sector.biome);
+
+    for pos in layout.stations {
+        spawn_space_station(&mut commands, &station_res, &minimap_assets, pos, true);
+    }
+    for config in layout.black_holes {
+        commands.add(spawn_black_hole.to_command(config));
+    }
+    for positions in layout.wormholes {
+        commands.add(spawn_wormhole_pair.to_command(positions));
+    }
+    for config in layout.planets {
+        commands.add(spawn_planet.to_command(config));
+    }
+    for config in layout.nebulae {
+        commands.add(spawn_nebula.to_command(config));
+    }
+    for config in layout.asteroid_fields {
+        commands.add(spawn_asteroid_field.to_command(config));
+    }
+    for config in layout.asteroid_belts {
+        commands.add(spawn_asteroid_belt.to_command(config));
+    }
+}
+
+pub struct SectorPlugin;
+
+impl Plugin for SectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(ON_GAME_STARTED, sector_setup_main_scene);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_generates_same_sector() {
+        for biome in Biome::ALL {
+            let a = generate_sector(42, biome);
+            let b = generate_sector(42, biome);
+            assert_eq!(a.stations, b.stations);
+            assert_eq!(
+                a.planets.iter().map(|p| p.pos).collect::<Vec<_>>(),
+                b.planets.iter().map(|p| p.pos).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn stations_are_reachable_without_crossing_planets() {
+        for biome in Biome::ALL {
+            for seed in 0..20 {
+                let layout = generate_sector(seed, biome);
+                assert!(!layout.stations.is_empty());
+
+                for station in &layout.stations {
+                    for planet in &layout.planets {
+                        match &planet.orbit {
+                            Some(orbit) => {
+                                let OrbitCenter::Point(center) = orbit.center else {
+                                    continue;
+                                };
+                                let band = (orbit.periapsis() - planet.size)
+                                    ..(orbit.apoapsis() + planet.size);
+                                let way = distance_range(PLAYER_SPAWN, *station, center);
+                                assert!(!overlaps(&band, &way), "{biome:?} seed {seed}");
+                            }
+                            None => {
+                                let closest =
+                                    closest_point_on_segment(PLAYER_SPAWN, *station, planet.pos);
+                                assert!(
+                                    closest.distance(planet.pos) > planet.size,
+                                    "{biome:?} seed {seed}"
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
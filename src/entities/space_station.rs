@@ -2,49 +2,75 @@ use bevy::prelude::*;
 use bevy_asset_loader::asset_collection::AssetCollection;
 use bevy_mod_outline::{OutlineBundle, OutlineVolume};
 use bevy_rapier3d::prelude::*;
-use rand::Rng;
 
 use crate::components::health::Regeneration;
-use crate::entities::spaceship::player::LastHit;
+use crate::components::hull_damage::HullDamage;
+use crate::entities::spaceship::player::{LastHit, PlayerInventory, MAX_BOMBS, MAX_TURRETS};
 use crate::materials::toon::replace_with_toon_materials;
 use crate::states::DespawnOnCleanup;
 use crate::ui::game_over::GameOverEvent;
 use crate::ui::minimap::{MinimapAssets, ShowOnMinimap};
 use crate::utils::asset_loading::AppExtension;
 use crate::utils::materials::default_outline;
+use crate::utils::misc::Comparef32;
 use crate::utils::scene::{AnimationRoot, ReplaceMaterialPlugin};
 use crate::{
     components::health::Health,
     materials::toon::ToonMaterial,
-    states::{game_running, AppState, ON_GAME_STARTED},
+    states::{game_running, AppState},
     ui::health_bar_3d::SpawnHealthBar,
 };
 
 use super::{
     bullet::{BulletTarget, BulletType},
+    defense::BuildMode,
     explosion::ExplosionEvent,
-    spaceship::{bot::EnemyTarget, SpaceshipCollisions},
+    spaceship::{bot::EnemyTarget, player::Player, IsPlayer, SpaceshipCollisions},
 };
 
-#[derive(Component)]
-pub struct SpaceStation;
+/// Distance from the center of a space station within which the player can dock
+const DOCKING_RANGE: f32 = 15.0;
+/// Ships faster than this fly through the docking zone without being captured
+const DOCKING_SPEED: f32 = 4.0;
+/// Distance between the center of the station and a docked ship
+const DOCKED_DISTANCE: f32 = 9.0;
+/// Health per second a docked ship is repaired by
+const DOCKED_HEAL_SPEED: f32 = 30.0;
+/// Lights around the rim of the station that blink while it is damaged
+const WARNING_LIGHTS: [Vec3; 4] = [
+    Vec3::new(5.3, 2.0, 0.0),
+    Vec3::new(0.0, 2.0, 5.3),
+    Vec3::new(-5.3, 2.0, 0.0),
+    Vec3::new(0.0, 2.0, -5.3),
+];
 
-pub fn setup_space_station(
-    mut commands: Commands,
-    res: Res<SpaceStationRes>,
-    minimap_res: Res<MinimapAssets>,
-) {
-    let mut rng = rand::thread_rng();
-
-    spawn_space_station(
-        &mut commands,
-        &res,
-        &minimap_res,
-        Vec3::new(rng.gen_range(-50.0..50.0), 0., rng.gen_range(-50.0..50.0)),
-        true,
-    );
+pub const REPAIR_COST: u32 = 5;
+pub const TURRET_COST: u32 = 10;
+pub const UPGRADE_COST: u32 = 20;
+const UPGRADE_HEALTH: f32 = 50.0;
+const UPGRADE_REGEN_SPEED: f32 = 5.0;
+/// How often the health and regeneration of a single station can be upgraded
+const MAX_STATION_UPGRADES: u32 = 3;
+
+#[derive(Component, Default)]
+pub struct SpaceStation {
+    upgrades: u32,
+}
+
+/// Added to the player while it is docked at a space station. Docked ships are held in place,
+/// repaired and can spend ore at the station.
+#[derive(Component)]
+pub struct Docked {
+    pub station: Entity,
+    /// Where the ship is held relative to the station
+    offset: Vec3,
 }
 
+/// Keeps the player from docking again right after leaving a station, until it has left the
+/// docking zone.
+#[derive(Component)]
+struct DockingBlocked;
+
 pub fn spawn_space_station(
     commands: &mut Commands,
     res: &SpaceStationRes,
@@ -59,7 +85,7 @@ pub fn spawn_space_station(
                 transform: Transform::from_translation(position),
                 ..default()
             },
-            SpaceStation,
+            SpaceStation::default(),
             RigidBody::Fixed,
             Collider::cylinder(5., 5.25),
             CollisionGroups::new(Group::all(), Group::all()), // TODO,
@@ -67,15 +93,13 @@ pub fn spawn_space_station(
                 target_type: BulletType::Bot,
                 bullet_damage: Some(10.0),
             },
-            SpaceshipCollisions {
-                collision_damage: 5.,
-                bound_radius: 5.,
-            },
+            SpaceshipCollisions { bound_radius: 5. },
             ShowOnMinimap {
                 sprite: minimap_res.space_station_indicator.clone(),
                 size: 0.1.into(),
+                ..default()
             },
-            EnemyTarget,
+            EnemyTarget::default(),
             Regeneration {
                 heal_cooldown: 5.0,
                 regen_speed: 10.0,
@@ -85,6 +109,9 @@ pub fn spawn_space_station(
             Health::new(200.),
             (
                 DespawnOnCleanup,
+                HullDamage::default()
+                    .with_effect_scale(3.0)
+                    .with_warning_lights(&WARNING_LIGHTS),
                 OutlineBundle {
                     outline: OutlineVolume {
                         width: 1.0,
@@ -144,9 +171,134 @@ fn space_station_death(
             commands.entity(entity).despawn_recursive();
             count -= 1;
             if count == 0 {
-                game_over_events.send(GameOverEvent);
+                game_over_events.send(GameOverEvent {
+                    position: transform.translation,
+                });
+            }
+        }
+    }
+}
+
+fn space_station_docking(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut players: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Velocity,
+            &mut Health,
+            Option<&Docked>,
+            Has<DockingBlocked>,
+        ),
+        IsPlayer,
+    >,
+    stations: Query<(Entity, &Transform), (With<SpaceStation>, Without<Player>)>,
+    mut inventory: ResMut<PlayerInventory>,
+    time: Res<Time>,
+) {
+    for (player, mut transform, mut velocity, mut health, docked, docking_blocked) in &mut players {
+        if let Some(docked) = docked {
+            let Ok((_, station_transform)) = stations.get(docked.station) else {
+                // The station was destroyed
+                commands.entity(player).remove::<Docked>();
+                continue;
+            };
+            if keyboard_input.any_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+                commands
+                    .entity(player)
+                    .remove::<Docked>()
+                    .insert(DockingBlocked);
+                continue;
+            }
+
+            velocity.linvel = Vec3::ZERO;
+            velocity.angvel = Vec3::ZERO;
+            let target = station_transform.translation + docked.offset;
+            transform.translation = transform
+                .translation
+                .lerp(target, (time.delta_seconds() * 5.0).min(1.0));
+            health.heal(DOCKED_HEAL_SPEED * time.delta_seconds());
+            continue;
+        }
+
+        let station = stations
+            .iter()
+            .filter(|(_, station_transform)| {
+                station_transform
+                    .translation
+                    .distance(transform.translation)
+                    < DOCKING_RANGE
+            })
+            .min_by_key(|(_, station_transform)| {
+                Comparef32(
+                    station_transform
+                        .translation
+                        .distance(transform.translation),
+                )
+            });
+
+        let Some((station, station_transform)) = station else {
+            if docking_blocked {
+                commands.entity(player).remove::<DockingBlocked>();
             }
+            continue;
+        };
+        if docking_blocked || velocity.linvel.length() > DOCKING_SPEED {
+            continue;
         }
+
+        let direction = (transform.translation - station_transform.translation)
+            .with_y(0.0)
+            .normalize_or(Vec3::Z);
+        commands.entity(player).insert(Docked {
+            station,
+            offset: direction * DOCKED_DISTANCE,
+        });
+        inventory.bombs = MAX_BOMBS;
+        inventory.turrets = MAX_TURRETS;
+    }
+}
+
+fn space_station_trade(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    players: Query<&Docked, IsPlayer>,
+    mut stations: Query<(&mut SpaceStation, &mut Health, &mut Regeneration)>,
+    mut inventory: ResMut<PlayerInventory>,
+) {
+    let Ok(docked) = players.get_single() else {
+        return;
+    };
+    let Ok((mut station, mut health, mut regeneration)) = stations.get_mut(docked.station) else {
+        return;
+    };
+
+    if keyboard_input.just_pressed(KeyCode::Digit1)
+        && inventory.ore >= REPAIR_COST
+        && health.health < health.max_health
+    {
+        inventory.ore -= REPAIR_COST;
+        let max_health = health.max_health;
+        health.heal(max_health);
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Digit2)
+        && inventory.ore >= TURRET_COST
+        && inventory.turrets < MAX_TURRETS
+    {
+        inventory.ore -= TURRET_COST;
+        inventory.turrets += 1;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Digit3)
+        && inventory.ore >= UPGRADE_COST
+        && station.upgrades < MAX_STATION_UPGRADES
+    {
+        inventory.ore -= UPGRADE_COST;
+        station.upgrades += 1;
+        health.max_health += UPGRADE_HEALTH;
+        health.heal(UPGRADE_HEALTH);
+        regeneration.regen_speed += UPGRADE_REGEN_SPEED;
     }
 }
 
@@ -172,11 +324,16 @@ impl Plugin for SpaceStationPlugin {
                 ..default()
             }),
         ))
-        .add_systems(ON_GAME_STARTED, (setup_space_station,))
         .add_systems(
             Update,
             (
-                space_station_death.run_if(game_running()),
+                (
+                    space_station_death,
+                    space_station_docking,
+                    // The number keys select defenses while building
+                    space_station_trade.run_if(not(resource_exists::<BuildMode>)),
+                )
+                    .run_if(game_running()),
                 space_station_animation
                     .run_if(game_running().or_else(in_state(AppState::StartScreen))),
             ),
@@ -1,6 +1,6 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 use bevy_asset_loader::loading_state::config::{ConfigureLoadingState, LoadingStateConfig};
 use bevy_asset_loader::{asset_collection::AssetCollection, loading_state::LoadingStateAppExt};
 use bevy_mod_outline::{OutlineBundle, OutlineVolume};
@@ -8,13 +8,21 @@ use bevy_rapier3d::prelude::*;
 use rand::{seq::SliceRandom, Rng};
 
 use crate::materials::toon::{replace_with_toon_materials, ToonMaterial};
-use crate::states::{AppState, DespawnOnCleanup};
+use crate::states::AppState;
 use crate::utils::scene::ReplaceMaterialPlugin;
 use crate::{
-    components::{colliders::VelocityColliderBundle, despawn_after::DespawnTimer, health::Health},
-    particles::fire_particles::FireParticleRes,
+    components::{
+        colliders::VelocityColliderBundle,
+        health::{Health, Shield},
+        hull_damage::HullDamage,
+    },
+    materials::shield::ShieldImpactEvent,
+    particles::{
+        fire_particles::FireParticleRes,
+        simulation::{Particle, ParticleCurve, ParticleCurves, Particles},
+    },
     states::game_running,
-    utils::{collisions::BULLET_COLLISION_GROUP, misc::CollidingEntitiesExtension, sets::Set},
+    utils::{collisions::BULLET_COLLISION_GROUP, sets::Set},
 };
 
 use self::{bot::Bot, player::Player};
@@ -31,21 +39,27 @@ pub type IsBot = (With<Bot>, Without<Player>);
 
 const BULLET_COOLDOWN: f32 = 0.2;
 
-#[derive(Component)]
+pub const SPACESHIP_MASS: f32 = 1.0;
+/// Bounciness of spaceships. Used for every contact with a spaceship.
+pub const SPACESHIP_RESTITUTION: Restitution = Restitution {
+    coefficient: 0.5,
+    combine_rule: CoefficientCombineRule::Max,
+};
+/// Change of velocity caused by an impact below which a spaceship takes no damage
+const MIN_DAMAGE_VELOCITY_CHANGE: f32 = 5.0;
+const DAMAGE_PER_VELOCITY_CHANGE: f32 = 0.75;
+/// Bots rammed by the player with at least this change of velocity are destroyed
+const RAM_KILL_VELOCITY_CHANGE: f32 = 15.0;
+/// Contact forces are reported on every physics step while two bodies touch, so a pair only
+/// causes damage again after this many seconds
+const COLLISION_COOLDOWN: f32 = 0.5;
+
+/// Obstacles that spaceships bounce off and bots steer around.
+#[derive(Component, Default)]
 pub struct SpaceshipCollisions {
-    pub collision_damage: f32,
     pub bound_radius: f32,
 }
 
-impl Default for SpaceshipCollisions {
-    fn default() -> Self {
-        Self {
-            collision_damage: 10.,
-            bound_radius: 0.,
-        }
-    }
-}
-
 #[derive(Resource, Component)]
 struct LastBulletInfo {
     side: BulletSide,
@@ -152,10 +166,16 @@ impl Spaceship {
 #[derive(Bundle)]
 pub struct SpaceshipBundle {
     pub velocity_collider_bundle: VelocityColliderBundle,
+    pub mass_properties: ColliderMassProperties,
+    pub read_mass_properties: ReadMassProperties,
+    pub restitution: Restitution,
+    pub locked_axes: LockedAxes,
+    pub contact_force_threshold: ContactForceEventThreshold,
     pub outline_bundle: OutlineBundle,
     pub scene_bundle: SceneBundle,
     pub spaceship: Spaceship,
     pub collision_groups: CollisionGroups,
+    pub hull_damage: HullDamage,
 }
 
 impl SpaceshipBundle {
@@ -170,8 +190,16 @@ impl SpaceshipBundle {
                     linvel: Vec3::X,
                     ..default()
                 },
+                rigid_body: RigidBody::Dynamic,
+                active_events: ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS,
                 ..default()
             },
+            mass_properties: ColliderMassProperties::Mass(SPACESHIP_MASS),
+            read_mass_properties: default(),
+            restitution: SPACESHIP_RESTITUTION,
+            // Spaceships are steered by rotating them directly, so contacts must not spin them
+            locked_axes: LockedAxes::ROTATION_LOCKED | LockedAxes::TRANSLATION_LOCKED_Y,
+            contact_force_threshold: ContactForceEventThreshold(0.0),
             outline_bundle: OutlineBundle {
                 outline: OutlineVolume {
                     visible: true,
@@ -188,47 +216,106 @@ impl SpaceshipBundle {
             },
             spaceship: default(),
             collision_groups: Self::COLLISION_GROUPS,
+            hull_damage: default(),
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spaceship_collisions(
-    mut spaceship: Query<
+    mut contact_forces: EventReader<ContactForceEvent>,
+    mut spaceships: Query<
         (
-            &mut Velocity,
-            &mut Transform,
-            &CollidingEntities,
-            Entity,
-            Option<&mut Health>,
-            Option<&ShieldEnabled>,
+            &mut Health,
+            Has<ShieldEnabled>,
+            Has<Player>,
+            Option<&Children>,
         ),
         With<Spaceship>,
     >,
-    colliders: Query<(&GlobalTransform, &SpaceshipCollisions), Without<Spaceship>>,
+    shields: Query<(), With<Shield>>,
+    transforms: Query<&GlobalTransform>,
+    masses: Query<&ReadMassProperties>,
+    rapier_context: Res<RapierContext>,
+    time: Res<Time>,
+    mut recent_contacts: Local<HashMap<(Entity, Entity), f32>>,
     mut explosions: EventWriter<ExplosionEvent>,
+    mut shield_impacts: EventWriter<ShieldImpactEvent>,
 ) {
-    for (mut velocity, mut transform, colliding_entities, entity, mut health, shield_enabled) in
-        &mut spaceship
-    {
-        for (global_transform, collisions) in colliding_entities.filter_fulfills_query(&colliders) {
+    let dt = rapier_context.integration_parameters.dt;
+    let now = time.elapsed_seconds();
+    recent_contacts.retain(|_, last_contact| now - *last_contact < COLLISION_COOLDOWN);
+
+    for event in contact_forces.read() {
+        let pair = if event.collider1 < event.collider2 {
+            (event.collider1, event.collider2)
+        } else {
+            (event.collider2, event.collider1)
+        };
+        if recent_contacts.contains_key(&pair) {
+            continue;
+        }
+
+        // The impulse of the contact already accounts for the relative speed and the masses of
+        // both bodies, so the resulting change of velocity of each body is used as damage.
+        let impulse = event.total_force_magnitude * dt;
+        for (entity, other) in [
+            (event.collider1, event.collider2),
+            (event.collider2, event.collider1),
+        ] {
+            let mass = masses
+                .get(entity)
+                .map(|mass| mass.get().mass)
+                .ok()
+                .filter(|mass| *mass > 0.0)
+                .unwrap_or(SPACESHIP_MASS);
+            let velocity_change = impulse / mass;
+            if velocity_change < MIN_DAMAGE_VELOCITY_CHANGE {
+                continue;
+            }
+            recent_contacts.insert(pair, now);
+
+            let other_position = transforms.get(other).map(GlobalTransform::translation);
+
+            // Spaceships ramming the shield of a cruiser or a space station defense
+            if shields.contains(entity) {
+                if let Ok(position) = other_position {
+                    shield_impacts.send(ShieldImpactEvent {
+                        shield: entity,
+                        position,
+                    });
+                }
+                continue;
+            }
+
+            let rammed_by_player = spaceships
+                .get(other)
+                .is_ok_and(|(_, _, is_player, _)| is_player);
+
+            let Ok((mut health, shield_enabled, is_player, children)) = spaceships.get_mut(entity)
+            else {
+                continue;
+            };
+
             explosions.send(ExplosionEvent {
                 parent: Some(entity),
                 ..default()
             });
-            let colliding_transform = global_transform.compute_transform();
-            let delta = transform.translation - colliding_transform.translation;
-            let distance = delta.length();
-            let normal = delta.normalize();
-            let v_norm = velocity.linvel.normalize();
-            velocity.linvel =
-                (v_norm - 2.0 * v_norm.dot(normal) * normal) * velocity.linvel.length() * 0.5;
-
-            transform.translation = colliding_transform.translation + normal * (distance + 2.0);
-
-            if let Some(ref mut health) = health
-                && shield_enabled.is_none()
+
+            if rammed_by_player && !is_player && velocity_change >= RAM_KILL_VELOCITY_CHANGE {
+                health.kill();
+            } else if !shield_enabled {
+                health.take_damage(
+                    (velocity_change - MIN_DAMAGE_VELOCITY_CHANGE) * DAMAGE_PER_VELOCITY_CHANGE,
+                );
+            } else if let Ok(position) = other_position
+                && let Some(shield) = children
+                    .and_then(|children| children.iter().find(|child| shields.contains(**child)))
             {
-                health.take_damage(collisions.collision_damage);
+                shield_impacts.send(ShieldImpactEvent {
+                    shield: *shield,
+                    position,
+                });
             }
         }
     }
@@ -276,9 +363,6 @@ struct SpaceshipAssets {
     enemy_ship: Handle<Scene>,
 }
 
-#[derive(Component)]
-struct SpaceshipExhaustParticle;
-
 #[derive(Event)]
 struct ParticleSpawnEvent {
     entity: Entity,
@@ -296,55 +380,41 @@ impl ParticleSpawnEvent {
 
 fn spawn_exhaust_particle(
     mut events: EventReader<ParticleSpawnEvent>,
-    mut commands: Commands,
+    mut particles: ResMut<Particles>,
     res: Res<FireParticleRes>,
     space_ship_query: Query<(&Transform, &Velocity), With<Spaceship>>,
 ) {
     let mut rng = rand::thread_rng();
     const RANDOM_VEL_RANGE: std::ops::Range<f32> = -4.0..4.0;
-    const LIFE_TIME_RANGE: std::ops::Range<u64> = 100..200;
+    const LIFE_TIME_RANGE: std::ops::Range<f32> = 0.1..0.2;
+    const PARTICLE_SIZE: f32 = 0.4;
+
+    // Exhaust particles grow slightly over their lifetime
+    let curves = Arc::new(ParticleCurves {
+        scale: ParticleCurve::new([(0.0, 1.0), (1.0, 1.2)]),
+        ..default()
+    });
 
     for event in events.read() {
         let Ok((transform, velocity)) = space_ship_query.get(event.entity) else {
             continue;
         };
-        let scale = Vec3::splat(rng.gen_range(0.7..1.4));
-        let lifetime = rng.gen_range(LIFE_TIME_RANGE);
 
         let direction = event.direction.unwrap_or(-*transform.forward());
 
         let linvel = velocity.linvel +
             direction * 10.0 + // Speed relative to spaceship
             direction.cross(Vec3::Y).normalize() * rng.gen_range(RANDOM_VEL_RANGE); // Random sideways velocity
-        commands.spawn((
-            MaterialMeshBundle {
-                material: res.materials.choose(&mut rng).unwrap().clone(),
-                mesh: res.mesh.clone(),
-                transform: Transform {
-                    translation: transform.translation - transform.forward() * 0.4,
-                    scale,
-                    rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2),
-                },
-                ..default()
-            },
-            SpaceshipExhaustParticle,
-            DespawnOnCleanup,
-            Velocity {
+        particles.spawn(
+            res.materials.choose(&mut rng).unwrap(),
+            Particle::new(
+                transform.translation - transform.forward() * 0.4,
                 linvel,
-                ..default()
-            },
-            RigidBody::KinematicVelocityBased,
-            DespawnTimer::new(Duration::from_millis(lifetime)),
-        ));
-    }
-}
-
-fn exhaust_particle_update(
-    time: Res<Time>,
-    mut particles: Query<&mut Transform, With<SpaceshipExhaustParticle>>,
-) {
-    for mut transform in &mut particles {
-        transform.scale += Vec3::splat(1.0) * time.delta_seconds();
+                PARTICLE_SIZE * rng.gen_range(0.7..1.4),
+                rng.gen_range(LIFE_TIME_RANGE),
+                curves.clone(),
+            ),
+        );
     }
 }
 
@@ -368,7 +438,6 @@ impl Plugin for SpaceshipPlugin {
             Update,
             (
                 spawn_exhaust_particle,
-                exhaust_particle_update,
                 spaceship_exhaust_timers,
                 spaceship_collisions.in_set(Set::ExplosionEvents),
                 auxiliary_drive,
@@ -11,7 +11,9 @@ use crate::{
     entities::{
         bullet::{BulletSpawnEvent, BulletTarget, BulletType},
         explosion::ExplosionEvent,
+        nebula::Nebula,
         powerup::SpawnPowerup,
+        wormhole::Wormhole,
         Enemy,
     },
     states::{game_running, DespawnOnCleanup, ON_GAME_STARTED},
@@ -37,13 +39,46 @@ const COLLISION_GROUPS: CollisionGroups = CollisionGroups::new(
     Group::ALL.difference(CRUISER_COLLISION_GROUP),
 );
 const POWERUP_SPAWN_PROBABILITY: f64 = 0.3;
-
+/// A wormhole is only taken if the path through it is at most this fraction of the direct path
+const WORMHOLE_SHORTCUT_FACTOR: f32 = 0.8;
+/// Bots retreat when a hit leaves them at or below this fraction of their max health. A single
+/// bullet from the player is not enough, it takes an additional collision or explosion.
+const RETREAT_HEALTH: f32 = 0.4;
+/// Seconds a bot retreats before it attacks again, unless it is hit in the meantime
+const RETREAT_TIME: f32 = 8.0;
+
+/// Something bots attack. Bots go for the target with the smallest distance relative to its
+/// priority.
 #[derive(Component)]
-pub struct EnemyTarget;
+pub struct EnemyTarget {
+    pub priority: f32,
+}
+
+impl Default for EnemyTarget {
+    fn default() -> Self {
+        Self { priority: 1.0 }
+    }
+}
+
+impl EnemyTarget {
+    fn weighted_distance(&self, from: Vec3, to: Vec3) -> Comparef32 {
+        Comparef32(from.distance(to) / self.priority)
+    }
+}
 
 #[derive(Component)]
 pub struct Bot;
 
+/// A badly damaged bot that hides in a nebula or flees from its target for a while
+#[derive(Component)]
+struct Retreat(Timer);
+
+impl Default for Retreat {
+    fn default() -> Self {
+        Self(Timer::from_seconds(RETREAT_TIME, TimerMode::Once))
+    }
+}
+
 #[derive(Component)]
 pub struct SquadLeader;
 
@@ -126,6 +161,7 @@ fn spawn_bot_from_world(world: &mut World, spawn_bot: SpawnBot) -> Result<Entity
         ShowOnMinimap {
             sprite: minimap_assets.enemy_indicator.clone(),
             size: 0.1.into(),
+            ..default()
         },
         Enemy,
         DespawnOnCleanup,
@@ -195,7 +231,7 @@ fn bot_update(
         ),
         (IsBot, Without<EnemyTarget>),
     >,
-    target_query: Query<(&Transform, Entity), With<EnemyTarget>>,
+    target_query: Query<(&Transform, &EnemyTarget)>,
     time: Res<Time>,
     mut bullet_spawn_events: EventWriter<BulletSpawnEvent>,
 ) {
@@ -203,7 +239,7 @@ fn bot_update(
         let current_pos = transform.translation;
         let Some((target_transform, _)) = target_query
             .iter()
-            .min_by_key(|(t, _)| Comparef32((t.translation - current_pos).length()))
+            .min_by_key(|(t, target)| target.weighted_distance(t.translation, current_pos))
         else {
             continue;
         };
@@ -234,28 +270,69 @@ fn bot_update(
 
 fn bot_movement(
     mut bots: Query<
-        (&mut Transform, &mut Velocity, &Bot, &Spaceship, Entity),
+        (
+            &mut Transform,
+            &mut Velocity,
+            &Bot,
+            &Spaceship,
+            Has<Retreat>,
+            Entity,
+        ),
         Without<SquadMember>,
     >,
     enemy_targets: Query<(&Transform, &EnemyTarget), Without<Bot>>,
     spaceship_collisions: Query<(&Transform, &SpaceshipCollisions), Without<Bot>>,
+    wormholes: Query<(&Transform, &Wormhole), Without<Bot>>,
+    nebulae: Query<(&Transform, &Nebula), Without<Bot>>,
     time: Res<Time>,
     mut exhaust_particles: EventWriter<ParticleSpawnEvent>,
 ) {
     const C: f32 = 5.0;
 
-    for (mut transform, mut velocity, _bot, spaceship, entity) in &mut bots {
+    for (mut transform, mut velocity, _bot, spaceship, retreating, entity) in &mut bots {
         // Determine target direction by potential field path-planning
-        let Some(target) = enemy_targets
-            .iter()
-            .min_by_key(|(t, _)| Comparef32((t.translation - transform.translation).length()))
-        else {
+        let Some(target) = enemy_targets.iter().min_by_key(|(t, target)| {
+            target.weighted_distance(t.translation, transform.translation)
+        }) else {
             continue;
         };
         let distance = (target.0.translation - transform.translation).length();
-        let f_attract = C
-            * (target.0.translation - transform.translation).normalize()
-            * if distance < 20.0 { -1.0 } else { 1.0 };
+
+        // Fly through a wormhole if it is a shortcut to the target
+        let shortcut = wormholes
+            .iter()
+            .filter_map(|(entry, wormhole)| {
+                let exit = wormholes.get(wormhole.partner).ok()?.0;
+                let length = entry.translation.distance(transform.translation)
+                    + exit.translation.distance(target.0.translation);
+                (length < distance * WORMHOLE_SHORTCUT_FACTOR).then_some((entry, length))
+            })
+            .min_by_key(|(_, length)| Comparef32(*length));
+
+        // Damaged bots hide in the closest nebula, where sensors can't find them
+        let cover = nebulae
+            .iter()
+            .filter(|_| retreating)
+            .min_by_key(|(t, _)| Comparef32(t.translation.distance(transform.translation)));
+
+        let f_attract = match (cover, shortcut) {
+            (Some((cover, nebula)), _) => {
+                let delta = cover.translation - transform.translation;
+                if delta.length() < nebula.radius * 0.5 {
+                    Vec3::ZERO
+                } else {
+                    C * delta.normalize()
+                }
+            }
+            (None, _) if retreating => {
+                -C * (target.0.translation - transform.translation).normalize()
+            }
+            (None, Some((entry, _))) => C * (entry.translation - transform.translation).normalize(),
+            (None, None) => {
+                C * (target.0.translation - transform.translation).normalize()
+                    * if distance < 20.0 { -1.0 } else { 1.0 }
+            }
+        };
         let f_repulse = spaceship_collisions
             .iter()
             .map(|(t, collisions)| {
@@ -272,6 +349,10 @@ fn bot_movement(
             .sum::<Vec3>();
 
         let f = f_attract + f_repulse;
+        // Nothing to steer towards, e.g. while hiding in a nebula
+        if f.length_squared() < 1e-6 {
+            continue;
+        }
 
         let angle = transform.forward().angle_between(f);
         let sign = angle_between_sign(*transform.forward(), f);
@@ -286,6 +367,26 @@ fn bot_movement(
     }
 }
 
+fn bot_retreat(
+    mut commands: Commands,
+    mut bots: Query<(Entity, Ref<Health>, Option<&mut Retreat>), IsBot>,
+    time: Res<Time>,
+) {
+    for (entity, health, retreat) in &mut bots {
+        if health.is_changed()
+            && !health.is_dead()
+            && health.health <= health.max_health * RETREAT_HEALTH
+        {
+            // Every new hit restarts the retreat
+            commands.entity(entity).insert(Retreat::default());
+        } else if let Some(mut retreat) = retreat
+            && retreat.0.tick(time.delta()).finished()
+        {
+            commands.entity(entity).remove::<Retreat>();
+        }
+    }
+}
+
 fn bot_squad_update(
     mut squad_bots: Query<
         (
@@ -368,7 +469,7 @@ impl Plugin for BotPlugin {
                 bot_death,
                 bot_repulsion,
                 bot_squad_update,
-                bot_movement,
+                (bot_retreat, bot_movement).chain(),
                 // bot_avoid_collisions,
             )
                 .run_if(game_running()),
@@ -7,28 +7,37 @@ use bevy::{
 };
 
 use bevy_mod_outline::OutlineBundle;
-use bevy_rapier3d::{dynamics::Velocity, geometry::Collider};
+use bevy_rapier3d::{
+    dynamics::{RigidBody, Velocity},
+    geometry::{ActiveCollisionTypes, Collider},
+};
 
 use crate::{
     components::{
         gravity::{gravity_step, GravityAffected, GravitySource},
         health::{HasShield, Regeneration},
         movement::MaxSpeed,
+        orbit::OrbitPositions,
     },
     entities::{
         bullet::{BulletSpawnEvent, BulletTarget, BulletType},
         explosion::ExplosionEvent,
         planet::Planet,
         powerup::PowerUpAssets,
+        sector::PLAYER_SPAWN,
         turret::Turret,
+        wormhole::{wormhole_transfer, Wormhole, WormholeTravelEvent, WORMHOLE_RADIUS},
     },
     materials::{
         blink::BlinkMaterial,
+        emissive,
         toon::{replace_with_toon_materials, ToonMaterial},
+        EMISSIVE_INTENSITY,
     },
     states::{game_running, AppState, DespawnOnCleanup, ON_GAME_STARTED},
     ui::{
         fonts::FontsResource,
+        health_bar_3d::SpawnHealthBar,
         minimap::{MinimapAssets, ShowOnMinimap},
         theme::default_font,
     },
@@ -38,16 +47,29 @@ use crate::{
 use super::bot::EnemyTarget;
 use super::{
     Health, IsPlayer, LastBulletInfo, ParticleSpawnEvent, Spaceship, SpaceshipAssets,
-    SpaceshipBundle,
+    SpaceshipBundle, SpaceshipCollisions,
 };
 
 #[derive(Component)]
 pub struct Player;
 
+pub const MAX_BOMBS: u32 = 3;
+pub const MAX_TURRETS: u32 = 3;
+/// Number of turrets that can be placed at the same time
+pub const MAX_ACTIVE_TURRETS: usize = 4;
+const TURRET_HEALTH: f32 = 50.0;
+/// Shots a turret fires before it is used up
+const TURRET_AMMO: u32 = 60;
+/// Turrets are in the way of the bots, so they are attacked before targets further away
+const TURRET_TARGET_PRIORITY: f32 = 2.0;
+/// Turrets are dropped this far behind the player, so they do not overlap with its collider
+const TURRET_DROP_DISTANCE: f32 = 4.0;
+
 #[derive(Resource, Default)]
 pub struct PlayerInventory {
     pub bombs: u32,
     pub turrets: u32,
+    pub ore: u32,
 }
 
 #[derive(Component)]
@@ -99,11 +121,11 @@ fn spawn_player(
 ) {
     commands.spawn((
         Player,
-        SpaceshipBundle::new(assets.player_ship.clone(), Vec3::ZERO),
+        SpaceshipBundle::new(assets.player_ship.clone(), PLAYER_SPAWN),
         Health::new(100.0),
         MaxSpeed { max_speed: 30.0 },
         LastHit::default(),
-        EnemyTarget,
+        EnemyTarget::default(),
         GravityAffected,
         Regeneration {
             heal_cooldown: HEAL_COOLDOWN,
@@ -112,6 +134,7 @@ fn spawn_player(
         ShowOnMinimap {
             sprite: minimap_assets.player_indicator.clone(),
             size: 0.1.into(),
+            ..default()
         },
         BulletTarget {
             target_type: BulletType::Bot,
@@ -141,6 +164,7 @@ impl ExhaustCooldown {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn player_input(
     timer: Res<Time>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
@@ -149,6 +173,7 @@ fn player_input(
     mut inventory: ResMut<PlayerInventory>,
     mut commands: Commands,
     powerup_assets: Res<PowerUpAssets>,
+    turrets: Query<(), With<PlayerTurret>>,
     mut exhaust_cooldown: Local<ExhaustCooldown>, 
 ) {
     exhaust_cooldown.tick(&timer);
@@ -211,28 +236,54 @@ fn player_input(
             ));
         }
 
-        if keyboard_input.just_pressed(KeyCode::KeyT) && inventory.turrets > 0 {
+        if keyboard_input.just_pressed(KeyCode::KeyT)
+            && inventory.turrets > 0
+            && turrets.iter().count() < MAX_ACTIVE_TURRETS
+        {
             inventory.turrets -= 1;
 
-            commands.spawn((
-                PlayerTurret,
-                Turret {
-                    bullet_timer: Timer::from_seconds(1.0, TimerMode::Repeating),
-                    bullet_type: BulletType::Player,
-                    base_orientation: Vec3::Z,
-                    rotation_bounds: (f32::NEG_INFINITY, f32::INFINITY),
-                },
-                DespawnOnCleanup,
-                SceneBundle {
-                    transform: Transform::from_translation(transform.translation),
-                    scene: powerup_assets.turret.clone(),
-                    ..default()
-                },
-                OutlineBundle {
-                    outline: default_outline(),
-                    ..default()
-                },
-            ));
+            let turret = commands
+                .spawn((
+                    PlayerTurret,
+                    Turret {
+                        bullet_timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+                        bullet_type: BulletType::Player,
+                        base_orientation: Vec3::Z,
+                        rotation_bounds: (f32::NEG_INFINITY, f32::INFINITY),
+                        ammo: Some(TURRET_AMMO),
+                    },
+                    DespawnOnCleanup,
+                    SceneBundle {
+                        transform: Transform::from_translation(
+                            transform.translation - transform.forward() * TURRET_DROP_DISTANCE,
+                        ),
+                        scene: powerup_assets.turret.clone(),
+                        ..default()
+                    },
+                    OutlineBundle {
+                        outline: default_outline(),
+                        ..default()
+                    },
+                    Health::new(TURRET_HEALTH),
+                    RigidBody::Fixed,
+                    Collider::cylinder(1.0, 1.5),
+                    ActiveCollisionTypes::DYNAMIC_STATIC | ActiveCollisionTypes::KINEMATIC_STATIC,
+                    BulletTarget {
+                        target_type: BulletType::Bot,
+                        bullet_damage: Some(10.0),
+                    },
+                    SpaceshipCollisions { bound_radius: 2.0 },
+                    EnemyTarget {
+                        priority: TURRET_TARGET_PRIORITY,
+                    },
+                ))
+                .id();
+            commands.add(SpawnHealthBar {
+                entity: turret,
+                shield_entity: None,
+                scale: 0.25,
+                offset: Vec2::new(0., 10.),
+            });
         }
     }
 }
@@ -462,11 +513,17 @@ fn player_line_update(
     player_query: Query<(&Transform, &Velocity, &Spaceship, Entity), IsPlayer>,
     player_changed: Query<(), (Changed<Spaceship>, IsPlayer)>,
     gravity_sources: Query<
-        (&Transform, &GravitySource, Option<&Planet>),
+        (Entity, &Transform, &GravitySource, Option<&Planet>),
         (Without<Player>, Without<PlayerLine>),
     >,
+    orbit_positions: OrbitPositions,
+    wormholes: Query<(&Transform, &Wormhole), (Without<Player>, Without<PlayerLine>)>,
     mut assets: ResMut<Assets<Mesh>>,
 ) {
+    let sources = gravity_sources.iter().collect::<Vec<_>>();
+    // Reused by every step of the prediction
+    let mut source_positions = Vec::with_capacity(sources.len());
+
     for (mesh_handle, mut transform) in &mut line_query {
         let Some(mesh) = assets.get_mut(mesh_handle.id()) else {
             continue;
@@ -486,32 +543,70 @@ fn player_line_update(
             let player_pos = player_transform.translation;
             let mut current_pos = Vec3::ZERO;
             let mut current_vel = player_velocity.linvel;
+            // The wormhole the prediction last came out of
+            let mut wormhole_exit: Option<Entity> = None;
 
             for i in 0..PREDICTION_LENGTH {
-                let perpendicular = current_vel.cross(Vec3::Y).normalize();
+                let mut perpendicular = current_vel.cross(Vec3::Y).normalize();
                 let thickness =
                     (1.0 - (i as f32 / PREDICTION_LENGTH as f32).powf(2.0)) * LINE_THICKNESS;
 
                 current_pos += current_vel * 0.02;
-                if !gravity_sources.iter().all(|(transform, _, planet)| {
+
+                // Continue the line on the other side of wormholes
+                let world_pos = current_pos + player_pos;
+                if let Some(exit) = wormhole_exit
+                    && !wormholes.get(exit).is_ok_and(|(exit, _)| {
+                        exit.translation.distance(world_pos) < WORMHOLE_RADIUS
+                    })
+                {
+                    wormhole_exit = None;
+                }
+                if wormhole_exit.is_none()
+                    && let Some((entry, wormhole)) = wormholes
+                        .iter()
+                        .find(|(entry, _)| entry.translation.distance(world_pos) < WORMHOLE_RADIUS)
+                    && let Ok((exit, _)) = wormholes.get(wormhole.partner)
+                {
+                    let (position, velocity, _) =
+                        wormhole_transfer(entry, exit, world_pos, current_vel);
+                    current_pos = position - player_pos;
+                    current_vel = velocity;
+                    perpendicular = current_vel.cross(Vec3::Y).normalize();
+                    wormhole_exit = Some(wormhole.partner);
+
+                    // Degenerate triangles keep the two parts of the line apart
+                    if let Some(last) = positions.last().copied() {
+                        positions.push(last);
+                        positions.push(current_pos - perpendicular * thickness);
+                    }
+                }
+
+                // Orbiting sources have to be evaluated where they will be at this step
+                let time = (i + 1) as f32 * 0.02;
+                source_positions.clear();
+                source_positions.extend(sources.iter().map(
+                    |(entity, transform, gravity_source, planet)| {
+                        let position = orbit_positions
+                            .position_in(*entity, time)
+                            .unwrap_or(transform.translation);
+                        (position, *gravity_source, *planet)
+                    },
+                ));
+
+                if !source_positions.iter().all(|(position, _, planet)| {
                     let Some(p) = planet else {
                         return true;
                     };
-                    (current_pos + player_pos).distance(transform.translation) > p.radius
+                    (current_pos + player_pos).distance(*position) > p.radius
                 }) {
                     break;
                 }
 
-                current_vel += gravity_sources
+                current_vel += source_positions
                     .iter()
-                    .map(|(gravity_transform, gravity_source, _)| {
-                        gravity_step(
-                            gravity_transform,
-                            gravity_source,
-                            0.02,
-                            current_pos + player_pos,
-                            current_vel,
-                        )
+                    .map(|(position, gravity_source, _)| {
+                        gravity_step(*position, gravity_source, 0.02, current_pos + player_pos)
                     })
                     .sum::<Vec3>();
 
@@ -630,6 +725,22 @@ fn return_to_mission_warning_update(
     }
 }
 
+/// The trail would otherwise stretch all the way between the two ends of a wormhole.
+fn player_trail_wormhole_reset(
+    players: Query<(), IsPlayer>,
+    mut trails: Query<&mut PlayerTrail>,
+    mut travel_events: EventReader<WormholeTravelEvent>,
+) {
+    if travel_events
+        .read()
+        .any(|event| players.contains(event.entity))
+    {
+        for mut trail in &mut trails {
+            trail.pos_history.clear();
+        }
+    }
+}
+
 #[derive(Resource, Deref, DerefMut)]
 pub struct PlayerRespawnTimer(pub Timer);
 
@@ -658,6 +769,24 @@ fn player_death(
     }
 }
 
+/// Blows up turrets that were shot down or ran out of ammo.
+fn player_turret_death(
+    turrets: Query<(Entity, &Health, &Turret, &Transform), With<PlayerTurret>>,
+    mut commands: Commands,
+    mut explosion_events: EventWriter<ExplosionEvent>,
+) {
+    for (entity, health, turret, transform) in &turrets {
+        if health.is_dead() || turret.ammo == Some(0) {
+            explosion_events.send(ExplosionEvent {
+                position: transform.translation,
+                parent: None,
+                radius: 5.0,
+            });
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
 fn player_respawn(
     mut player_respawn_timer: ResMut<PlayerRespawnTimer>,
     mut commands: Commands,
@@ -704,8 +833,8 @@ impl Plugin for PlayerPlugin {
                     }
                     Some(BlinkMaterial {
                         period: 1.0,
-                        color_1: Color::srgb(1.0, 0.0, 0.0),
-                        color_2: Color::srgb(0.5, 0.0, 0.0),
+                        color_1: emissive(Color::srgb(1.0, 0.0, 0.0), EMISSIVE_INTENSITY).into(),
+                        color_2: emissive(Color::srgb(0.5, 0.0, 0.0), EMISSIVE_INTENSITY).into(),
                     })
                 })),
                 ReplaceMaterialPlugin::<PlayerTurret, _>::new(replace_with_toon_materials(
@@ -724,6 +853,8 @@ impl Plugin for PlayerPlugin {
                     return_to_mission_warning_update,
                     return_to_mission_warning_despawn,
                     player_death,
+                    player_turret_death.in_set(Set::ExplosionEvents),
+                    player_trail_wormhole_reset,
                     bomb_update,
                     player_respawn.run_if(resource_exists::<PlayerRespawnTimer>),
                 )
@@ -6,6 +6,7 @@ use crate::states::game_running;
 use super::{
     bullet::{BulletSpawnEvent, BulletType},
     cruiser::CruiserTurret,
+    defense::Defense,
     spaceship::{bot::EnemyTarget, player::PlayerTurret},
     Enemy,
 };
@@ -19,6 +20,8 @@ pub struct Turret {
     pub bullet_type: BulletType,
     pub base_orientation: Vec3,
     pub rotation_bounds: (f32, f32),
+    /// Shots left before the turret runs dry. `None` if the ammo is unlimited.
+    pub ammo: Option<u32>,
 }
 
 fn turret_update<Filter, Target>(
@@ -40,6 +43,10 @@ fn turret_update<Filter, Target>(
             continue;
         };
 
+        if turret.ammo == Some(0) {
+            continue;
+        }
+
         turret.bullet_timer.tick(time.delta());
 
         let global_translation = global_transform.compute_transform();
@@ -71,6 +78,9 @@ fn turret_update<Filter, Target>(
             position: global_translation,
             direction,
         });
+        if let Some(ammo) = &mut turret.ammo {
+            *ammo -= 1;
+        }
     }
 }
 
@@ -82,6 +92,7 @@ impl Plugin for TurretPlugin {
             (
                 turret_update::<With<CruiserTurret>, With<EnemyTarget>>,
                 turret_update::<With<PlayerTurret>, With<Enemy>>,
+                turret_update::<With<Defense>, With<Enemy>>,
             )
                 .run_if(game_running()),
         );
@@ -0,0 +1,226 @@
+use bevy::prelude::*;
+use bevy_rapier3d::dynamics::Velocity;
+use rand::Rng;
+
+use crate::{
+    components::pool::InPool,
+    materials::accretion_disk::{AccretionDiskBundle, AccretionDiskMaterial},
+    postprocessing::lensing::GravitationalLens,
+    states::{game_running, DespawnOnCleanup},
+    ui::minimap::{MinimapAssets, MinimapSize, ShowOnMinimap, MINIMAP_RANGE, MINIMAP_SIZE},
+};
+
+use super::{asteroid::Asteroid, bullet::Bullet, spaceship::Spaceship};
+
+/// Bodies closer than this to the center of a wormhole are sent to its partner
+pub const WORMHOLE_RADIUS: f32 = 3.0;
+const WORMHOLE_DISK_RADIUS: f32 = WORMHOLE_RADIUS * 2.5;
+
+/// One end of a wormhole. Anything entering it comes out of the partner.
+#[derive(Component, Debug)]
+pub struct Wormhole {
+    pub partner: Entity,
+}
+
+/// Added to bodies that just came out of a wormhole, so they don't get sent back right away.
+/// It is removed as soon as the body has left the exit.
+#[derive(Component)]
+pub struct WormholeTransit {
+    exit: Entity,
+}
+
+#[derive(Event)]
+pub struct WormholeTravelEvent {
+    pub entity: Entity,
+}
+
+#[derive(Resource)]
+pub struct WormholeRes {
+    mesh: Handle<Mesh>,
+    material: Handle<AccretionDiskMaterial>,
+}
+
+/// Maps a body entering `entry` to the corresponding position and velocity at `exit`. Also returns
+/// the rotation that has to be applied to the body.
+pub fn wormhole_transfer(
+    entry: &Transform,
+    exit: &Transform,
+    position: Vec3,
+    velocity: Vec3,
+) -> (Vec3, Vec3, Quat) {
+    let rotation = exit.rotation * entry.rotation.inverse();
+    (
+        exit.translation + rotation * (position - entry.translation),
+        rotation * velocity,
+        rotation,
+    )
+}
+
+fn wormhole_setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<AccretionDiskMaterial>>,
+) {
+    commands.insert_resource(WormholeRes {
+        mesh: meshes.add(
+            Plane3d::default()
+                .mesh()
+                .size(WORMHOLE_DISK_RADIUS * 2.0, WORMHOLE_DISK_RADIUS * 2.0),
+        ),
+        material: materials.add(AccretionDiskMaterial {
+            core_color: Srgba::hex("0b0326").unwrap().into(),
+            inner_color: Srgba::hex("c9a6ff").unwrap().into(),
+            outer_color: Srgba::hex("2ad4c8").unwrap().into(),
+            horizon: WORMHOLE_RADIUS / WORMHOLE_DISK_RADIUS,
+            speed: 2.0,
+        }),
+    });
+}
+
+/// Spawns two linked wormholes at the given positions.
+pub fn spawn_wormhole_pair(
+    In((pos_a, pos_b)): In<(Vec3, Vec3)>,
+    mut commands: Commands,
+    res: Res<WormholeRes>,
+    minimap_assets: Res<MinimapAssets>,
+) {
+    let mut rng = rand::thread_rng();
+
+    let mut spawn = |pos: Vec3| {
+        commands
+            .spawn((
+                DespawnOnCleanup,
+                AccretionDiskBundle {
+                    material_mesh: MaterialMeshBundle {
+                        mesh: res.mesh.clone(),
+                        material: res.material.clone(),
+                        transform: Transform::from_translation(pos).with_rotation(
+                            Quat::from_rotation_y(rng.gen_range(0.0..std::f32::consts::TAU)),
+                        ),
+                        ..default()
+                    },
+                    ..default()
+                },
+                GravitationalLens {
+                    radius: WORMHOLE_RADIUS,
+                    strength: 0.4,
+                },
+                ShowOnMinimap {
+                    sprite: minimap_assets.planet_indicator.clone(),
+                    size: MinimapSize::Custom(Vec2::splat(
+                        WORMHOLE_DISK_RADIUS / MINIMAP_RANGE * MINIMAP_SIZE,
+                    )),
+                    color: Srgba::hex("2ad4c8").unwrap().into(),
+                },
+            ))
+            .id()
+    };
+    let a = spawn(pos_a);
+    let b = spawn(pos_b);
+
+    commands.entity(a).insert(Wormhole { partner: b });
+    commands.entity(b).insert(Wormhole { partner: a });
+}
+
+#[allow(clippy::type_complexity)]
+fn wormhole_travel(
+    mut commands: Commands,
+    wormholes: Query<(Entity, &Transform, &Wormhole)>,
+    mut bodies: Query<
+        (
+            Entity,
+            &mut Transform,
+            &GlobalTransform,
+            &mut Velocity,
+            Option<&mut Bullet>,
+            Option<&WormholeTransit>,
+            Option<&Parent>,
+        ),
+        (
+            Or<(With<Spaceship>, With<Bullet>, With<Asteroid>)>,
+            Without<Wormhole>,
+            Without<InPool>,
+        ),
+    >,
+    parents: Query<&GlobalTransform>,
+    mut travel_events: EventWriter<WormholeTravelEvent>,
+) {
+    for (entity, mut transform, global_transform, mut velocity, bullet, transit, parent) in
+        &mut bodies
+    {
+        // Asteroids are children of their field or belt chunk, so everything is done in world space
+        let world_transform = global_transform.compute_transform();
+        if let Some(transit) = transit {
+            let still_inside = wormholes.get(transit.exit).is_ok_and(|(_, exit, _)| {
+                exit.translation.distance(world_transform.translation) < WORMHOLE_RADIUS
+            });
+            if !still_inside {
+                commands.entity(entity).remove::<WormholeTransit>();
+            }
+            continue;
+        }
+
+        let Some((_, entry, wormhole)) = wormholes.iter().find(|(_, entry, _)| {
+            entry.translation.distance(world_transform.translation) < WORMHOLE_RADIUS
+        }) else {
+            continue;
+        };
+        let Ok((exit_entity, exit, _)) = wormholes.get(wormhole.partner) else {
+            continue;
+        };
+
+        let (position, linvel, rotation) =
+            wormhole_transfer(entry, exit, world_transform.translation, velocity.linvel);
+        let exit_transform = Transform {
+            translation: position,
+            rotation: rotation * world_transform.rotation,
+            scale: world_transform.scale,
+        };
+        *transform = match parent.and_then(|parent| parents.get(parent.get()).ok()) {
+            Some(parent) => GlobalTransform::from(exit_transform).reparented_to(parent),
+            None => exit_transform,
+        };
+        velocity.linvel = linvel;
+
+        // Bullets would otherwise check for hits all the way between the two wormholes
+        if let Some(mut bullet) = bullet {
+            bullet.last_position = position;
+            bullet.relative_speed = rotation * bullet.relative_speed;
+        }
+
+        commands
+            .entity(entity)
+            .insert(WormholeTransit { exit: exit_entity });
+        travel_events.send(WormholeTravelEvent { entity });
+    }
+}
+
+pub struct WormholePlugin;
+
+impl Plugin for WormholePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<WormholeTravelEvent>()
+            .add_systems(Startup, wormhole_setup)
+            .add_systems(Update, wormhole_travel.run_if(game_running()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    #[test]
+    fn transfer_rotates_into_exit_orientation() {
+        let entry = Transform::from_xyz(10.0, 0.0, 0.0);
+        let exit =
+            Transform::from_xyz(-50.0, 0.0, 20.0).with_rotation(Quat::from_rotation_y(FRAC_PI_2));
+
+        let (position, velocity, _) =
+            wormhole_transfer(&entry, &exit, Vec3::new(11.0, 0.0, 0.0), Vec3::NEG_Z * 5.0);
+
+        assert!(position.distance(Vec3::new(-50.0, 0.0, 19.0)) < 1e-4);
+        assert!(velocity.distance(Vec3::NEG_X * 5.0) < 1e-4);
+    }
+}
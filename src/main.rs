@@ -9,7 +9,6 @@ i18n!();
 use bevy::{
     asset::AssetMetaCheck,
     log::{self, LogPlugin},
-    pbr::DirectionalLightShadowMap,
     prelude::*,
     window::PresentMode,
 };
@@ -118,8 +117,7 @@ fn main() {
             MaterialsPlugin,
             ModelPlugin,
             UtilsPlugin,
-        ))
-        .insert_resource(DirectionalLightShadowMap { size: 4096 });
+        ));
     cfg_if! {
         if #[cfg(target_family = "wasm")] {
             // app.insert_resource(Msaa::Off);
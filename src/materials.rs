@@ -1,10 +1,32 @@
-use bevy::app::{App, Plugin};
+use bevy::{
+    app::{App, Plugin},
+    color::LinearRgba,
+};
 
+use crate::entities::camera::HDR;
+
+pub mod accretion_disk;
 pub mod blink;
 pub mod exhaust;
+pub mod nebula;
 pub mod shield;
 pub mod toon;
 
+/// Brightness of glowing materials like exhausts, shields and bullets. Colors above 1.0 are
+/// picked up by the bloom of an HDR camera, but would only clip to white without one.
+pub const EMISSIVE_INTENSITY: f32 = if HDR { 4.0 } else { 1.0 };
+
+/// Scales the color channels of `color` by `intensity`, but keeps its alpha.
+pub fn emissive(color: impl Into<LinearRgba>, intensity: f32) -> LinearRgba {
+    let color = color.into();
+    LinearRgba {
+        red: color.red * intensity,
+        green: color.green * intensity,
+        blue: color.blue * intensity,
+        alpha: color.alpha,
+    }
+}
+
 pub struct MaterialsPlugin;
 
 impl Plugin for MaterialsPlugin {
@@ -14,6 +36,8 @@ impl Plugin for MaterialsPlugin {
             exhaust::ExhaustPlugin,
             shield::ShieldMaterialPlugin,
             blink::BlinkMaterialPlugin,
+            accretion_disk::AccretionDiskMaterialPlugin,
+            nebula::NebulaMaterialPlugin,
         ));
     }
 }
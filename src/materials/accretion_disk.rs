@@ -0,0 +1,71 @@
+use bevy::{
+    pbr::{NotShadowCaster, NotShadowReceiver},
+    prelude::*,
+    render::{
+        render_asset::RenderAssets,
+        render_resource::{AsBindGroup, AsBindGroupShaderType, ShaderRef, ShaderType},
+        texture::GpuImage,
+    },
+};
+
+/// A swirling disk of matter on the XZ plane of a flat mesh, with the event horizon at its center.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+#[uniform(0, AccretionDiskMaterialUniform)]
+pub struct AccretionDiskMaterial {
+    /// Color inside the event horizon
+    pub core_color: Color,
+    pub inner_color: Color,
+    pub outer_color: Color,
+    /// Radius of the event horizon relative to the radius of the disk
+    pub horizon: f32,
+    /// Rotation speed of the disk, negative values rotate the other way
+    pub speed: f32,
+}
+
+#[derive(Debug, Clone, AsBindGroup, ShaderType)]
+struct AccretionDiskMaterialUniform {
+    pub core_color: LinearRgba,
+    pub inner_color: LinearRgba,
+    pub outer_color: LinearRgba,
+    pub horizon: f32,
+    pub speed: f32,
+}
+
+impl AsBindGroupShaderType<AccretionDiskMaterialUniform> for AccretionDiskMaterial {
+    fn as_bind_group_shader_type(
+        &self,
+        _images: &RenderAssets<GpuImage>,
+    ) -> AccretionDiskMaterialUniform {
+        AccretionDiskMaterialUniform {
+            core_color: self.core_color.into(),
+            inner_color: self.inner_color.into(),
+            outer_color: self.outer_color.into(),
+            horizon: self.horizon,
+            speed: self.speed,
+        }
+    }
+}
+
+impl Material for AccretionDiskMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/accretion_disk.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+}
+
+#[derive(Bundle, Default)]
+pub struct AccretionDiskBundle {
+    pub material_mesh: MaterialMeshBundle<AccretionDiskMaterial>,
+    pub not_shadow_caster: NotShadowCaster,
+    pub not_shadow_receiver: NotShadowReceiver,
+}
+
+pub struct AccretionDiskMaterialPlugin;
+impl Plugin for AccretionDiskMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<AccretionDiskMaterial>::default());
+    }
+}
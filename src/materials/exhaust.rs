@@ -12,7 +12,10 @@ use bevy_asset_loader::{
     loading_state::{config::ConfigureLoadingState, LoadingState, LoadingStateAppExt},
 };
 
-use crate::states::{AppState, ON_GAME_STARTED};
+use crate::{
+    materials::{emissive, EMISSIVE_INTENSITY},
+    states::{AppState, ON_GAME_STARTED},
+};
 
 #[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
 #[uniform(0, ExhaustMaterialUniform)]
@@ -40,8 +43,8 @@ impl ExhaustMaterial {
 impl Default for ExhaustMaterial {
     fn default() -> Self {
         Self {
-            inner_color: css::ORANGE.into(),
-            outer_color: css::ORANGE_RED.into(),
+            inner_color: emissive(css::ORANGE, EMISSIVE_INTENSITY).into(),
+            outer_color: emissive(css::ORANGE_RED, EMISSIVE_INTENSITY).into(),
             threshold_offset: 0.3,
             speed: 1.0,
             noise_texture: NOISE_TEXTURE.clone(),
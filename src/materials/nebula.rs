@@ -0,0 +1,67 @@
+use bevy::{
+    pbr::{NotShadowCaster, NotShadowReceiver},
+    prelude::*,
+    render::{
+        render_asset::RenderAssets,
+        render_resource::{AsBindGroup, AsBindGroupShaderType, ShaderRef, ShaderType},
+        texture::GpuImage,
+    },
+};
+
+/// One layer of a nebula cloud on a camera facing quad. The density of the cloud is quantized
+/// into a few bands, like the lighting of the toon material.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+#[uniform(0, NebulaMaterialUniform)]
+pub struct NebulaMaterial {
+    /// Color of the thin outer parts of the cloud
+    pub color: Color,
+    /// Color of the dense inner parts of the cloud
+    pub highlight_color: Color,
+    /// Offsets the noise, so the layers of a nebula don't look the same
+    pub seed: f32,
+    /// Alpha of the densest band
+    pub opacity: f32,
+}
+
+#[derive(Debug, Clone, AsBindGroup, ShaderType)]
+struct NebulaMaterialUniform {
+    pub color: LinearRgba,
+    pub highlight_color: LinearRgba,
+    pub seed: f32,
+    pub opacity: f32,
+}
+
+impl AsBindGroupShaderType<NebulaMaterialUniform> for NebulaMaterial {
+    fn as_bind_group_shader_type(&self, _images: &RenderAssets<GpuImage>) -> NebulaMaterialUniform {
+        NebulaMaterialUniform {
+            color: self.color.into(),
+            highlight_color: self.highlight_color.into(),
+            seed: self.seed,
+            opacity: self.opacity,
+        }
+    }
+}
+
+impl Material for NebulaMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/nebula.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+}
+
+#[derive(Bundle, Default)]
+pub struct NebulaLayerBundle {
+    pub material_mesh: MaterialMeshBundle<NebulaMaterial>,
+    pub not_shadow_caster: NotShadowCaster,
+    pub not_shadow_receiver: NotShadowReceiver,
+}
+
+pub struct NebulaMaterialPlugin;
+impl Plugin for NebulaMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<NebulaMaterial>::default());
+    }
+}
@@ -1,7 +1,11 @@
 use bevy::{
     pbr::{NotShadowCaster, NotShadowReceiver},
     prelude::*,
-    render::render_resource::{AsBindGroup, ShaderRef},
+    render::{
+        render_asset::RenderAssets,
+        render_resource::{AsBindGroup, AsBindGroupShaderType, ShaderRef, ShaderType},
+        texture::GpuImage,
+    },
 };
 use bevy_rapier3d::{
     dynamics::RigidBody,
@@ -10,13 +14,74 @@ use bevy_rapier3d::{
 
 use crate::{
     components::health::{Health, Shield},
-    entities::bullet::BulletTarget,
+    entities::{bullet::BulletTarget, camera::HDR},
 };
 
+use super::emissive;
+
+/// Shields are large, so they glow less than exhausts and bullets
+const SHIELD_INTENSITY: f32 = if HDR { 2.0 } else { 1.0 };
+/// Has to match `MAX_IMPACTS` in `shield.wgsl`
+const MAX_SHIELD_IMPACTS: usize = 8;
+/// Opacity of a shield that is about to break, relative to a full one
+const MIN_SHIELD_STRENGTH: f32 = 0.3;
+
 #[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+#[uniform(0, ShieldMaterialUniform)]
 pub struct ShieldMaterial {
-    #[uniform(0)]
     pub color: LinearRgba,
+    /// Scales the opacity, follows the health of the shield
+    pub strength: f32,
+    /// Ring buffer of the latest impacts in the local space of the shield, with the time of the
+    /// impact in `w`. Unused slots have a negative time.
+    impacts: [Vec4; MAX_SHIELD_IMPACTS],
+    next_impact: usize,
+}
+
+impl ShieldMaterial {
+    pub fn new(color: LinearRgba) -> Self {
+        Self {
+            color,
+            strength: 1.0,
+            impacts: [Vec4::NEG_ONE; MAX_SHIELD_IMPACTS],
+            next_impact: 0,
+        }
+    }
+
+    /// Starts a ripple at `position`, given in world space. `time` has to be the wrapped elapsed
+    /// time, like the time of the shader globals.
+    pub fn add_impact(&mut self, position: Vec3, shield_transform: &GlobalTransform, time: f32) {
+        let local = shield_transform
+            .affine()
+            .inverse()
+            .transform_point3(position);
+        self.impacts[self.next_impact] = local.extend(time);
+        self.next_impact = (self.next_impact + 1) % MAX_SHIELD_IMPACTS;
+    }
+}
+
+#[derive(Debug, Clone, ShaderType)]
+struct ShieldMaterialUniform {
+    color: LinearRgba,
+    impacts: [Vec4; MAX_SHIELD_IMPACTS],
+    strength: f32,
+}
+
+impl AsBindGroupShaderType<ShieldMaterialUniform> for ShieldMaterial {
+    fn as_bind_group_shader_type(&self, _images: &RenderAssets<GpuImage>) -> ShieldMaterialUniform {
+        ShieldMaterialUniform {
+            color: self.color,
+            impacts: self.impacts,
+            strength: self.strength,
+        }
+    }
+}
+
+/// Sent when something hits a [`Shield`], which starts a ripple at `position`
+#[derive(Event)]
+pub struct ShieldImpactEvent {
+    pub shield: Entity,
+    pub position: Vec3,
 }
 
 #[derive(Bundle, Default)]
@@ -35,13 +100,15 @@ pub struct ShieldBundle {
 
 impl Default for ShieldMaterial {
     fn default() -> Self {
-        Self {
-            color: Srgba::hex("6fc1fc").unwrap().into(),
-        }
+        Self::new(emissive(Srgba::hex("6fc1fc").unwrap(), SHIELD_INTENSITY))
     }
 }
 
 impl Material for ShieldMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/shield.wgsl".into()
+    }
+
     fn fragment_shader() -> ShaderRef {
         "shaders/shield.wgsl".into()
     }
@@ -51,9 +118,45 @@ impl Material for ShieldMaterial {
     }
 }
 
+fn shield_impacts(
+    mut events: EventReader<ShieldImpactEvent>,
+    shields: Query<(&Handle<ShieldMaterial>, &GlobalTransform)>,
+    mut materials: ResMut<Assets<ShieldMaterial>>,
+    time: Res<Time>,
+) {
+    for event in events.read() {
+        let Ok((handle, transform)) = shields.get(event.shield) else {
+            continue;
+        };
+        let Some(material) = materials.get_mut(handle) else {
+            continue;
+        };
+        material.add_impact(event.position, transform, time.elapsed_seconds_wrapped());
+    }
+}
+
+fn update_shield_materials(
+    shields: Query<(&Handle<ShieldMaterial>, &Health), Changed<Health>>,
+    mut materials: ResMut<Assets<ShieldMaterial>>,
+) {
+    for (handle, health) in &shields {
+        let Some(material) = materials.get_mut(handle) else {
+            continue;
+        };
+        let health_fraction = health.health / health.max_health;
+        material.strength = MIN_SHIELD_STRENGTH + (1.0 - MIN_SHIELD_STRENGTH) * health_fraction;
+    }
+}
+
 pub struct ShieldMaterialPlugin;
 impl Plugin for ShieldMaterialPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(MaterialPlugin::<ShieldMaterial>::default());
+        app.add_plugins(MaterialPlugin::<ShieldMaterial>::default())
+            .add_event::<ShieldImpactEvent>()
+            .add_systems(
+                PostUpdate,
+                (shield_impacts, update_shield_materials)
+                    .after(TransformSystem::TransformPropagate),
+            );
     }
 }
@@ -55,6 +55,10 @@ pub struct ToonMaterial {
 
     pub disable_outline: bool,
 
+    /// Between 0 and 1, covers the surface in scorch marks, see
+    /// [`crate::components::hull_damage`]
+    pub damage: f32,
+
     pub filter_scale: f32,
     pub depth_threshold: f32,
     pub normal_threshold: f32,
@@ -74,6 +78,7 @@ struct ToonMaterialUniform {
     depth_normal_threshold: f32,
     use_texture: u32,
     color: LinearRgba,
+    damage: f32,
 }
 
 impl AsBindGroupShaderType<ToonMaterialUniform> for ToonMaterial {
@@ -86,6 +91,7 @@ impl AsBindGroupShaderType<ToonMaterialUniform> for ToonMaterial {
             depth_normal_threshold: self.depth_normal_threshold,
             use_texture: if self.texture.is_some() { 1 } else { 0 },
             color: self.color.into(),
+            damage: self.damage,
         }
     }
 }
@@ -109,6 +115,7 @@ impl Default for ToonMaterial {
             depth_normal_threshold: DEFAULT_DEPTH_NORMAL_THRESHOLD,
             cull_mode: None,
             disable_outline: false,
+            damage: 0.0,
         }
     }
 }
@@ -123,6 +130,7 @@ impl Default for ToonMaterialUniform {
             depth_normal_threshold: DEFAULT_DEPTH_NORMAL_THRESHOLD,
             use_texture: 0,
             color: DEFAULT_COLOR.into(),
+            damage: 0.0,
         }
     }
 }
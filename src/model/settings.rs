@@ -5,6 +5,7 @@ use std::io;
 #[cfg(not(target_family = "wasm"))]
 use std::fs;
 
+use bevy::pbr::DirectionalLightShadowMap;
 use bevy::prelude::*;
 use bevy::window::{PresentMode, PrimaryWindow};
 use cfg_if::cfg_if;
@@ -21,6 +22,19 @@ pub struct Settings {
     pub lang: String,
     pub antialiasing: AntialiasingSetting,
     pub vsync: VSyncSetting,
+    // Settings saved before this was added don't contain it
+    #[serde(default)]
+    pub post_processing: PostProcessingSetting,
+    #[serde(default)]
+    pub outline: OutlineSetting,
+    #[serde(default)]
+    pub bloom: BloomSetting,
+    #[serde(default)]
+    pub graphics: GraphicsPreset,
+    #[serde(default)]
+    pub dynamic_resolution: DynamicResolutionSetting,
+    #[serde(default)]
+    pub screen_shake: ScreenShakeSetting,
     pub profile: Option<Profile>,
 }
 
@@ -31,6 +45,54 @@ pub enum AntialiasingSetting {
     On,
 }
 
+/// Which post effects are enabled, see [`crate::postprocessing::effects`]
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum PostProcessingSetting {
+    Off,
+    // WebGL is slower, so the browser build only uses the cheap effects by default
+    #[cfg_attr(target_family = "wasm", default)]
+    Low,
+    #[cfg_attr(not(target_family = "wasm"), default)]
+    High,
+}
+
+/// How the silhouettes of objects are drawn, see [`crate::postprocessing::outline`]
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum OutlineSetting {
+    /// An extra outline mesh per entity
+    #[default]
+    Mesh,
+    /// Edge detection on the prepass textures, which are not available on WebGL
+    ScreenSpace,
+}
+
+/// Strength of the glow around emissive materials
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum BloomSetting {
+    Off,
+    #[default]
+    Low,
+    High,
+}
+
+/// Trades image quality for performance in everything that is not configured separately
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum GraphicsPreset {
+    Low,
+    #[cfg_attr(target_family = "wasm", default)]
+    Medium,
+    #[cfg_attr(not(target_family = "wasm"), default)]
+    High,
+    Ultra,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+pub struct DynamicResolutionSetting(pub bool);
+
+/// Whether explosions shake the camera, see [`crate::entities::camera::director`]
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+pub struct ScreenShakeSetting(pub bool);
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Profile {
     pub name: String,
@@ -73,24 +135,191 @@ impl AntialiasingSetting {
     }
 }
 
-impl From<AntialiasingSetting> for Msaa {
-    fn from(setting: AntialiasingSetting) -> Self {
+impl From<AntialiasingSetting> for String {
+    fn from(setting: AntialiasingSetting) -> String {
         match setting {
-            AntialiasingSetting::Off => Msaa::Off,
-            AntialiasingSetting::On => Msaa::default(),
+            AntialiasingSetting::Off => t!("off").to_string(),
+            AntialiasingSetting::On => t!("on").to_string(),
         }
     }
 }
 
-impl From<AntialiasingSetting> for String {
-    fn from(setting: AntialiasingSetting) -> String {
+impl PostProcessingSetting {
+    pub fn values() -> Vec<Self> {
+        vec![Self::Off, Self::Low, Self::High]
+    }
+}
+
+impl From<PostProcessingSetting> for String {
+    fn from(setting: PostProcessingSetting) -> String {
         match setting {
-            AntialiasingSetting::Off => t!("off").to_string(),
-            AntialiasingSetting::On => t!("on").to_string(),
+            PostProcessingSetting::Off => t!("off").to_string(),
+            PostProcessingSetting::Low => t!("low").to_string(),
+            PostProcessingSetting::High => t!("high").to_string(),
+        }
+    }
+}
+
+impl OutlineSetting {
+    pub fn values() -> Vec<Self> {
+        if cfg!(target_family = "wasm") {
+            vec![Self::Mesh]
+        } else {
+            vec![Self::Mesh, Self::ScreenSpace]
+        }
+    }
+}
+
+impl From<OutlineSetting> for String {
+    fn from(setting: OutlineSetting) -> String {
+        match setting {
+            OutlineSetting::Mesh => t!("outline_mesh").to_string(),
+            OutlineSetting::ScreenSpace => t!("outline_screen_space").to_string(),
+        }
+    }
+}
+
+impl BloomSetting {
+    pub fn values() -> Vec<Self> {
+        vec![Self::Off, Self::Low, Self::High]
+    }
+}
+
+impl From<BloomSetting> for String {
+    fn from(setting: BloomSetting) -> String {
+        match setting {
+            BloomSetting::Off => t!("off").to_string(),
+            BloomSetting::Low => t!("low").to_string(),
+            BloomSetting::High => t!("high").to_string(),
         }
     }
 }
 
+impl GraphicsPreset {
+    pub fn values() -> Vec<Self> {
+        vec![Self::Low, Self::Medium, Self::High, Self::Ultra]
+    }
+
+    pub fn shadow_map_size(self) -> usize {
+        match self {
+            Self::Low => 1024,
+            Self::Medium => 2048,
+            // Larger textures exceed the limits of many WebGL implementations
+            Self::Ultra if cfg!(not(target_family = "wasm")) => 8192,
+            Self::High | Self::Ultra => 4096,
+        }
+    }
+
+    /// Sample count used when antialiasing is enabled
+    pub fn msaa(self) -> Msaa {
+        match self {
+            Self::Low => Msaa::Off,
+            // WebGL only supports 4 samples
+            Self::Ultra if cfg!(not(target_family = "wasm")) => Msaa::Sample8,
+            _ => Msaa::Sample4,
+        }
+    }
+
+    /// Whether the depth and normal prepasses are rendered, which the screen-space outline needs.
+    /// On WebGL, they are currently broken, see https://github.com/bevyengine/bevy/issues/9710
+    pub fn prepasses(self) -> bool {
+        cfg!(not(target_family = "wasm")) && matches!(self, Self::High | Self::Ultra)
+    }
+
+    /// Multiplier for the emission rate of all particle emitters
+    pub fn particle_density(self) -> f32 {
+        match self {
+            Self::Low => 0.25,
+            Self::Medium => 0.5,
+            Self::High => 1.0,
+            Self::Ultra => 1.5,
+        }
+    }
+
+    /// Multiplier for the width of the black outlines
+    pub fn outline_scale(self) -> f32 {
+        match self {
+            Self::Low => 0.5,
+            Self::Medium => 0.75,
+            Self::High => 1.0,
+            Self::Ultra => 1.25,
+        }
+    }
+
+    /// Factor the resolution of the skybox is divided by
+    pub fn skybox_downscale(self) -> u32 {
+        match self {
+            Self::Low => 4,
+            Self::Medium => 2,
+            Self::High | Self::Ultra => 1,
+        }
+    }
+}
+
+impl From<GraphicsPreset> for String {
+    fn from(setting: GraphicsPreset) -> String {
+        match setting {
+            GraphicsPreset::Low => t!("low").to_string(),
+            GraphicsPreset::Medium => t!("medium").to_string(),
+            GraphicsPreset::High => t!("high").to_string(),
+            GraphicsPreset::Ultra => t!("ultra").to_string(),
+        }
+    }
+}
+
+impl Default for DynamicResolutionSetting {
+    fn default() -> Self {
+        // The browser build is the one most likely to miss its frame time
+        Self(cfg!(target_family = "wasm"))
+    }
+}
+
+impl From<DynamicResolutionSetting> for String {
+    fn from(setting: DynamicResolutionSetting) -> String {
+        if setting.0 {
+            t!("on").to_string()
+        } else {
+            t!("off").to_string()
+        }
+    }
+}
+
+impl Default for ScreenShakeSetting {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+impl From<ScreenShakeSetting> for String {
+    fn from(setting: ScreenShakeSetting) -> String {
+        if setting.0 {
+            t!("on").to_string()
+        } else {
+            t!("off").to_string()
+        }
+    }
+}
+
+impl Settings {
+    pub fn msaa(&self) -> Msaa {
+        match self.antialiasing {
+            AntialiasingSetting::Off => Msaa::Off,
+            AntialiasingSetting::On => self.graphics.msaa(),
+        }
+    }
+
+    pub fn shadow_map(&self) -> DirectionalLightShadowMap {
+        DirectionalLightShadowMap {
+            size: self.graphics.shadow_map_size(),
+        }
+    }
+
+    /// The screen-space outline falls back to the mesh outlines if the prepasses are disabled
+    pub fn screen_space_outline(&self) -> bool {
+        self.outline == OutlineSetting::ScreenSpace && self.graphics.prepasses()
+    }
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
@@ -98,6 +327,12 @@ impl Default for Settings {
             lang: "en".to_string(),
             antialiasing: default(),
             vsync: default(),
+            post_processing: default(),
+            outline: default(),
+            bloom: default(),
+            graphics: default(),
+            dynamic_resolution: default(),
+            screen_shake: default(),
             profile: None,
         }
     }
@@ -190,8 +425,8 @@ fn setup_settings(
     mut window: Query<&mut Window, With<PrimaryWindow>>,
 ) {
     rust_i18n::set_locale(settings.lang.as_str());
-    let msaa: Msaa = settings.antialiasing.into();
-    commands.insert_resource(msaa);
+    commands.insert_resource(settings.msaa());
+    commands.insert_resource(settings.shadow_map());
     for mut window in window.iter_mut() {
         window.present_mode = settings.vsync.into();
     }
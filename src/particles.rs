@@ -1,5 +1,3 @@
-use std::f32::consts::FRAC_PI_2;
-
 use bevy::{
     color::palettes::css,
     prelude::*,
@@ -8,7 +6,14 @@ use bevy::{
 
 use crate::states::AppState;
 
+use self::{
+    fire_particles::FireParticleRes,
+    simulation::{ParticleCurve, ParticleCurves, ParticleEmitter},
+};
+
 pub mod fire_particles;
+pub mod instancing;
+pub mod simulation;
 
 #[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
 pub struct ParticleMaterial {
@@ -40,21 +45,18 @@ impl Material for ParticleMaterial {
     }
 }
 
-fn particle_test_scene_setup(
-    mut commands: Commands,
-    mut materials: ResMut<Assets<ParticleMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-) {
-    let mesh = meshes.add(Rectangle::new(5.0, 5.0));
-    let material = materials.add(ParticleMaterial {
-        color: css::GREEN.into(),
-    });
-    commands.spawn(MaterialMeshBundle {
-        material,
-        mesh,
-        transform: Transform::from_rotation(Quat::from_rotation_x(-FRAC_PI_2)),
-        ..default()
-    });
+fn particle_test_scene_setup(mut commands: Commands, fire_res: Res<FireParticleRes>) {
+    commands.spawn((
+        SpatialBundle::default(),
+        ParticleEmitter::new(fire_res.materials.to_vec(), 200.0)
+            .with_velocity(Vec3::Y * 2.0, 1.0)
+            .with_size(0.2..0.6)
+            .with_lifetime(0.5..1.5)
+            .with_curves(ParticleCurves {
+                scale: ParticleCurve::new([(0.0, 0.0), (0.2, 1.0), (1.0, 0.0)]),
+                color: ParticleCurve::new([(0.0, LinearRgba::WHITE), (1.0, css::GREEN.into())]),
+            }),
+    ));
 }
 
 fn init_camera(mut commands: Commands) {
@@ -90,6 +92,8 @@ impl Plugin for ParticlesPlugin {
         app.add_plugins((
             MaterialPlugin::<ParticleMaterial>::default(),
             fire_particles::FireParticlesPlugin,
+            simulation::ParticleSimulationPlugin,
+            instancing::ParticleInstancingPlugin,
         ))
         .add_systems(
             OnEnter(AppState::ParticleTestScene),
@@ -12,17 +12,10 @@ impl Plugin for FireParticlesPlugin {
 
 #[derive(Resource)]
 pub struct FireParticleRes {
-    pub mesh: Handle<Mesh>,
     pub materials: [Handle<ParticleMaterial>; 4],
 }
 
-fn setup_fire_particles(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ParticleMaterial>>,
-) {
-    let mesh = meshes.add(Rectangle::new(0.4, 0.4));
-
+fn setup_fire_particles(mut commands: Commands, mut materials: ResMut<Assets<ParticleMaterial>>) {
     let colors = [
         Srgba::hex("ef8904").unwrap().into(),
         Srgba::hex("f2600c").unwrap().into(),
@@ -37,5 +30,5 @@ fn setup_fire_particles(
         .try_into()
         .unwrap();
 
-    commands.insert_resource(FireParticleRes { mesh, materials })
+    commands.insert_resource(FireParticleRes { materials })
 }
@@ -0,0 +1,353 @@
+use bevy::{
+    core_pipeline::{
+        core_3d::Transparent3d,
+        prepass::{DeferredPrepass, DepthPrepass, MotionVectorPrepass, NormalPrepass},
+    },
+    ecs::{
+        query::ROQueryItem,
+        system::{lifetimeless::*, SystemParamItem},
+    },
+    pbr::{MeshPipeline, MeshPipelineKey, SetMeshViewBindGroup},
+    prelude::*,
+    render::{
+        mesh::{GpuBufferInfo, GpuMesh, MeshVertexBufferLayoutRef},
+        render_asset::RenderAssets,
+        render_phase::{
+            AddRenderCommand, DrawFunctions, PhaseItem, PhaseItemExtraIndex, RenderCommand,
+            RenderCommandResult, SetItemPipeline, TrackedRenderPass, ViewSortedRenderPhases,
+        },
+        render_resource::*,
+        renderer::{RenderDevice, RenderQueue},
+        view::ExtractedView,
+        Extract, Render, RenderApp, RenderSet,
+    },
+    utils::HashMap,
+};
+use bytemuck::{Pod, Zeroable};
+
+use super::{simulation::Particles, ParticleMaterial};
+
+/// A unit quad that every particle is drawn with.
+#[derive(Resource)]
+struct ParticleMesh(Handle<Mesh>);
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct ParticleInstance {
+    position_scale: [f32; 4],
+    color: [f32; 4],
+}
+
+/// The particles of one material, extracted to the render world.
+#[derive(Component)]
+struct ExtractedParticles {
+    material: AssetId<ParticleMaterial>,
+    mesh: AssetId<Mesh>,
+    center: Vec3,
+    instances: Vec<ParticleInstance>,
+}
+
+#[derive(Component)]
+struct ParticleInstanceBuffer {
+    buffer: Buffer,
+    length: u32,
+}
+
+/// The instance buffer of every material, kept across frames so that it only has to be
+/// reallocated when there are more particles than it can hold.
+#[derive(Resource, Default)]
+struct ParticleInstanceBuffers(HashMap<AssetId<ParticleMaterial>, Buffer>);
+
+fn setup_particle_mesh(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    commands.insert_resource(ParticleMesh(meshes.add(Rectangle::new(1.0, 1.0))));
+}
+
+fn extract_particles(
+    mut commands: Commands,
+    particles: Extract<Res<Particles>>,
+    materials: Extract<Res<Assets<ParticleMaterial>>>,
+    mesh: Extract<Option<Res<ParticleMesh>>>,
+) {
+    let Some(mesh) = &*mesh else {
+        return;
+    };
+    for (material_id, buffer) in &particles.buffers {
+        if buffer.particles.is_empty() {
+            continue;
+        }
+        let Some(material) = materials.get(*material_id) else {
+            continue;
+        };
+
+        let mut center = Vec3::ZERO;
+        let instances = buffer
+            .particles
+            .iter()
+            .map(|particle| {
+                center += particle.position;
+                let color = particle.color(material.color);
+                ParticleInstance {
+                    position_scale: particle.position.extend(particle.scale()).to_array(),
+                    color: [color.red, color.green, color.blue, color.alpha],
+                }
+            })
+            .collect::<Vec<_>>();
+
+        commands.spawn(ExtractedParticles {
+            material: *material_id,
+            mesh: mesh.0.id(),
+            center: center / instances.len() as f32,
+            instances,
+        });
+    }
+}
+
+fn prepare_particle_buffers(
+    mut commands: Commands,
+    query: Query<(Entity, &ExtractedParticles)>,
+    mut buffers: ResMut<ParticleInstanceBuffers>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    for (entity, particles) in &query {
+        let contents: &[u8] = bytemuck::cast_slice(particles.instances.as_slice());
+        let buffer = buffers
+            .0
+            .entry(particles.material)
+            .or_insert_with(|| create_instance_buffer(&render_device, contents.len()));
+        if buffer.size() < contents.len() as u64 {
+            *buffer = create_instance_buffer(&render_device, contents.len());
+        }
+        render_queue.write_buffer(buffer, 0, contents);
+
+        commands.entity(entity).insert(ParticleInstanceBuffer {
+            buffer: buffer.clone(),
+            length: particles.instances.len() as u32,
+        });
+    }
+}
+
+/// Rounds the size up to the next power of two, so a growing effect does not reallocate its
+/// buffer every frame.
+fn create_instance_buffer(render_device: &RenderDevice, size: usize) -> Buffer {
+    render_device.create_buffer(&BufferDescriptor {
+        label: Some("particle instance buffer"),
+        size: size.next_power_of_two() as u64,
+        usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn queue_particles(
+    draw_functions: Res<DrawFunctions<Transparent3d>>,
+    particle_pipeline: Res<ParticlePipeline>,
+    msaa: Res<Msaa>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<ParticlePipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    meshes: Res<RenderAssets<GpuMesh>>,
+    particles: Query<(Entity, &ExtractedParticles)>,
+    mut render_phases: ResMut<ViewSortedRenderPhases<Transparent3d>>,
+    views: Query<(
+        Entity,
+        &ExtractedView,
+        (
+            Has<DepthPrepass>,
+            Has<NormalPrepass>,
+            Has<MotionVectorPrepass>,
+            Has<DeferredPrepass>,
+        ),
+    )>,
+) {
+    let draw_particles = draw_functions.read().id::<DrawParticles>();
+
+    for (
+        view_entity,
+        view,
+        (depth_prepass, normal_prepass, motion_vector_prepass, deferred_prepass),
+    ) in &views
+    {
+        let Some(phase) = render_phases.get_mut(&view_entity) else {
+            continue;
+        };
+
+        // The view bind group layout depends on the prepasses of the view
+        let mut view_key = MeshPipelineKey::from_msaa_samples(msaa.samples())
+            | MeshPipelineKey::from_hdr(view.hdr)
+            | MeshPipelineKey::BLEND_ALPHA;
+        if depth_prepass {
+            view_key |= MeshPipelineKey::DEPTH_PREPASS;
+        }
+        if normal_prepass {
+            view_key |= MeshPipelineKey::NORMAL_PREPASS;
+        }
+        if motion_vector_prepass {
+            view_key |= MeshPipelineKey::MOTION_VECTOR_PREPASS;
+        }
+        if deferred_prepass {
+            view_key |= MeshPipelineKey::DEFERRED_PREPASS;
+        }
+
+        let rangefinder = view.rangefinder3d();
+        for (entity, extracted) in &particles {
+            let Some(mesh) = meshes.get(extracted.mesh) else {
+                continue;
+            };
+            let key =
+                view_key | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology());
+            let pipeline = match pipelines.specialize(
+                &pipeline_cache,
+                &particle_pipeline,
+                key,
+                &mesh.layout,
+            ) {
+                Ok(pipeline) => pipeline,
+                Err(err) => {
+                    error!("Failed to specialize particle pipeline: {err}");
+                    continue;
+                }
+            };
+            phase.add(Transparent3d {
+                entity,
+                pipeline,
+                draw_function: draw_particles,
+                distance: rangefinder.distance_translation(&extracted.center),
+                batch_range: 0..1,
+                extra_index: PhaseItemExtraIndex::NONE,
+            });
+        }
+    }
+}
+
+#[derive(Resource)]
+struct ParticlePipeline {
+    shader: Handle<Shader>,
+    mesh_pipeline: MeshPipeline,
+}
+
+impl FromWorld for ParticlePipeline {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            shader: world
+                .resource::<AssetServer>()
+                .load("shaders/particle.wgsl"),
+            mesh_pipeline: world.resource::<MeshPipeline>().clone(),
+        }
+    }
+}
+
+impl SpecializedMeshPipeline for ParticlePipeline {
+    type Key = MeshPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayoutRef,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut descriptor = self.mesh_pipeline.specialize(key, layout)?;
+        // Particles are positioned in world space, so only the view bind group is needed
+        descriptor.layout.truncate(1);
+        descriptor.label = Some("particle_pipeline".into());
+        descriptor.vertex.shader = self.shader.clone();
+        descriptor.vertex.buffers.push(VertexBufferLayout {
+            array_stride: std::mem::size_of::<ParticleInstance>() as u64,
+            step_mode: VertexStepMode::Instance,
+            attributes: vec![
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 0,
+                    shader_location: 3,
+                },
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: VertexFormat::Float32x4.size(),
+                    shader_location: 4,
+                },
+            ],
+        });
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader = self.shader.clone();
+        }
+        Ok(descriptor)
+    }
+}
+
+type DrawParticles = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    DrawParticlesInstanced,
+);
+
+struct DrawParticlesInstanced;
+
+impl<P: PhaseItem> RenderCommand<P> for DrawParticlesInstanced {
+    type Param = SRes<RenderAssets<GpuMesh>>;
+    type ViewQuery = ();
+    type ItemQuery = (Read<ExtractedParticles>, Read<ParticleInstanceBuffer>);
+
+    #[inline]
+    fn render<'w>(
+        _item: &P,
+        _view: (),
+        item: Option<ROQueryItem<'w, Self::ItemQuery>>,
+        meshes: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some((particles, instance_buffer)) = item else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(gpu_mesh) = meshes.into_inner().get(particles.mesh) else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, instance_buffer.buffer.slice(..));
+
+        match &gpu_mesh.buffer_info {
+            GpuBufferInfo::Indexed {
+                buffer,
+                index_format,
+                count,
+            } => {
+                pass.set_index_buffer(buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(0..*count, 0, 0..instance_buffer.length);
+            }
+            GpuBufferInfo::NonIndexed => {
+                pass.draw(0..gpu_mesh.vertex_count, 0..instance_buffer.length);
+            }
+        }
+        RenderCommandResult::Success
+    }
+}
+
+/// Draws every [`super::simulation::ParticleBuffer`] with one instanced draw call.
+pub struct ParticleInstancingPlugin;
+
+impl Plugin for ParticleInstancingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_particle_mesh);
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_render_command::<Transparent3d, DrawParticles>()
+            .init_resource::<SpecializedMeshPipelines<ParticlePipeline>>()
+            .init_resource::<ParticleInstanceBuffers>()
+            .add_systems(ExtractSchedule, extract_particles)
+            .add_systems(
+                Render,
+                (
+                    queue_particles.in_set(RenderSet::QueueMeshes),
+                    prepare_particle_buffers.in_set(RenderSet::PrepareResources),
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<ParticlePipeline>();
+    }
+}
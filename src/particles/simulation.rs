@@ -0,0 +1,312 @@
+use std::{ops::Range, sync::Arc};
+
+use bevy::{color::Mix, prelude::*, utils::HashMap};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    model::settings::Settings,
+    states::{game_paused, ON_GAME_STARTED},
+};
+
+use super::ParticleMaterial;
+
+/// Values that can be interpolated by a [`ParticleCurve`].
+pub trait CurveValue: Copy {
+    fn interpolate(self, other: Self, t: f32) -> Self;
+}
+
+impl CurveValue for f32 {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl CurveValue for LinearRgba {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self.mix(&other, t)
+    }
+}
+
+/// A piecewise linear curve over the normalized lifetime (0.0 to 1.0) of a particle.
+#[derive(Clone, Debug)]
+pub struct ParticleCurve<T: CurveValue> {
+    keys: Vec<(f32, T)>,
+}
+
+impl<T: CurveValue> ParticleCurve<T> {
+    /// Creates a curve from `(time, value)` keyframes. The keyframes have to be sorted by time.
+    pub fn new(keys: impl Into<Vec<(f32, T)>>) -> Self {
+        let keys = keys.into();
+        assert!(
+            !keys.is_empty(),
+            "A particle curve needs at least one keyframe"
+        );
+        Self { keys }
+    }
+
+    pub fn constant(value: T) -> Self {
+        Self::new([(0.0, value)])
+    }
+
+    pub fn sample(&self, t: f32) -> T {
+        let index = self.keys.partition_point(|(time, _)| *time <= t);
+        if index == 0 {
+            return self.keys[0].1;
+        }
+        if index == self.keys.len() {
+            return self.keys[index - 1].1;
+        }
+        let (start_time, start) = self.keys[index - 1];
+        let (end_time, end) = self.keys[index];
+        start.interpolate(end, (t - start_time) / (end_time - start_time))
+    }
+}
+
+/// Describes how particles change over their lifetime.
+#[derive(Clone, Debug)]
+pub struct ParticleCurves {
+    /// Multiplied with the size of the particle.
+    pub scale: ParticleCurve<f32>,
+    /// Multiplied with the color of the particle material.
+    pub color: ParticleCurve<LinearRgba>,
+}
+
+impl Default for ParticleCurves {
+    fn default() -> Self {
+        Self {
+            scale: ParticleCurve::constant(1.0),
+            color: ParticleCurve::constant(LinearRgba::WHITE),
+        }
+    }
+}
+
+pub struct Particle {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub size: f32,
+    /// Lifetime in seconds
+    pub lifetime: f32,
+    pub curves: Arc<ParticleCurves>,
+    age: f32,
+}
+
+impl Particle {
+    pub fn new(
+        position: Vec3,
+        velocity: Vec3,
+        size: f32,
+        lifetime: f32,
+        curves: Arc<ParticleCurves>,
+    ) -> Self {
+        Self {
+            position,
+            velocity,
+            size,
+            lifetime,
+            curves,
+            age: 0.0,
+        }
+    }
+
+    fn life_fraction(&self) -> f32 {
+        (self.age / self.lifetime).min(1.0)
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.size * self.curves.scale.sample(self.life_fraction())
+    }
+
+    pub fn color(&self, base: LinearRgba) -> LinearRgba {
+        let factor = self.curves.color.sample(self.life_fraction());
+        LinearRgba::new(
+            base.red * factor.red,
+            base.green * factor.green,
+            base.blue * factor.blue,
+            base.alpha * factor.alpha,
+        )
+    }
+}
+
+/// The state of all particles sharing one material. Each buffer is drawn with a single
+/// instanced draw call.
+#[derive(Default)]
+pub struct ParticleBuffer {
+    pub particles: Vec<Particle>,
+}
+
+/// Holds every live particle, grouped by material.
+#[derive(Resource, Default)]
+pub struct Particles {
+    pub buffers: HashMap<AssetId<ParticleMaterial>, ParticleBuffer>,
+}
+
+impl Particles {
+    pub fn spawn(&mut self, material: &Handle<ParticleMaterial>, particle: Particle) {
+        self.buffers
+            .entry(material.id())
+            .or_default()
+            .particles
+            .push(particle);
+    }
+}
+
+/// Continuously emits particles at the position of its entity.
+#[derive(Component, Clone)]
+pub struct ParticleEmitter {
+    pub enabled: bool,
+    /// Every particle gets one of these materials at random
+    pub materials: Vec<Handle<ParticleMaterial>>,
+    /// Particles per second
+    pub rate: f32,
+    /// Velocity in the local space of the emitter
+    pub velocity: Vec3,
+    /// Maximum length of the random velocity added to every particle
+    pub spread: f32,
+    pub size: Range<f32>,
+    pub lifetime: Range<f32>,
+    pub curves: Arc<ParticleCurves>,
+    accumulator: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(materials: Vec<Handle<ParticleMaterial>>, rate: f32) -> Self {
+        Self {
+            enabled: true,
+            materials,
+            rate,
+            velocity: Vec3::ZERO,
+            spread: 1.0,
+            size: 1.0..1.0,
+            lifetime: 1.0..1.0,
+            curves: default(),
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn with_velocity(mut self, velocity: Vec3, spread: f32) -> Self {
+        self.velocity = velocity;
+        self.spread = spread;
+        self
+    }
+
+    pub fn with_size(mut self, size: Range<f32>) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_lifetime(mut self, lifetime: Range<f32>) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    pub fn with_curves(mut self, curves: ParticleCurves) -> Self {
+        self.curves = Arc::new(curves);
+        self
+    }
+}
+
+/// Samples a value from `range`, also allowing empty ranges.
+pub fn sample_range(rng: &mut impl Rng, range: &Range<f32>) -> f32 {
+    if range.is_empty() {
+        range.start
+    } else {
+        rng.gen_range(range.clone())
+    }
+}
+
+/// Returns a random vector with a length of at most `max_length`.
+pub fn random_spread(rng: &mut impl Rng, max_length: f32) -> Vec3 {
+    Vec3::new(
+        rng.gen_range(-1.0..1.0),
+        rng.gen_range(-1.0..1.0),
+        rng.gen_range(-1.0..1.0),
+    )
+    .normalize_or_zero()
+        * rng.gen_range(0.0..=max_length)
+}
+
+fn emit_particles(
+    mut emitters: Query<(&mut ParticleEmitter, &GlobalTransform)>,
+    mut particles: ResMut<Particles>,
+    time: Res<Time>,
+    settings: Res<Settings>,
+) {
+    let mut rng = rand::thread_rng();
+    let density = settings.graphics.particle_density();
+    for (mut emitter, transform) in &mut emitters {
+        if !emitter.enabled {
+            emitter.accumulator = 0.0;
+            continue;
+        }
+        emitter.accumulator += emitter.rate * density * time.delta_seconds();
+
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+        while emitter.accumulator >= 1.0 {
+            emitter.accumulator -= 1.0;
+            let Some(material) = emitter.materials.choose(&mut rng) else {
+                continue;
+            };
+            particles.spawn(
+                material,
+                Particle::new(
+                    translation,
+                    rotation * emitter.velocity + random_spread(&mut rng, emitter.spread),
+                    sample_range(&mut rng, &emitter.size),
+                    sample_range(&mut rng, &emitter.lifetime),
+                    emitter.curves.clone(),
+                ),
+            );
+        }
+    }
+}
+
+fn simulate_particles(mut particles: ResMut<Particles>, time: Res<Time>) {
+    let delta = time.delta_seconds();
+    for buffer in particles.buffers.values_mut() {
+        buffer.particles.retain_mut(|particle| {
+            particle.age += delta;
+            particle.position += particle.velocity * delta;
+            particle.age < particle.lifetime
+        });
+    }
+}
+
+fn clear_particles(mut particles: ResMut<Particles>) {
+    particles.buffers.clear();
+}
+
+pub struct ParticleSimulationPlugin;
+
+impl Plugin for ParticleSimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Particles>()
+            .add_systems(ON_GAME_STARTED, clear_particles)
+            .add_systems(
+                PostUpdate,
+                (emit_particles, simulate_particles)
+                    .chain()
+                    .after(TransformSystem::TransformPropagate)
+                    .run_if(not(game_paused())),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curve_interpolates_between_keys() {
+        let curve = ParticleCurve::new([(0.0, 0.0), (0.5, 1.0), (1.0, 0.0)]);
+        assert_eq!(curve.sample(0.25), 0.5);
+        assert_eq!(curve.sample(0.5), 1.0);
+        assert_eq!(curve.sample(0.75), 0.5);
+    }
+
+    #[test]
+    fn curve_clamps_outside_keys() {
+        let curve = ParticleCurve::new([(0.2, 1.0), (0.8, 2.0)]);
+        assert_eq!(curve.sample(0.0), 1.0);
+        assert_eq!(curve.sample(1.0), 2.0);
+    }
+}
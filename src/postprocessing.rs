@@ -1,13 +1,17 @@
 use bevy::app::{App, Plugin};
 
-// pub mod outline;
+pub mod effects;
+pub mod lensing;
+pub mod outline;
 
 pub struct PostprocessingPlugin;
 
 impl Plugin for PostprocessingPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
-            // outline::OutlinePlugin, 
+            lensing::LensingPlugin,
+            effects::PostEffectsPlugin,
+            outline::OutlinePlugin,
         ));
     }
 }
@@ -0,0 +1,395 @@
+use bevy::{
+    core_pipeline::{
+        core_3d::graph::{Core3d, Node3d},
+        fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    },
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        globals::{GlobalsBuffer, GlobalsUniform},
+        render_graph::{
+            NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            binding_types::{sampler, texture_2d, uniform_buffer},
+            *,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        view::ViewTarget,
+        RenderApp,
+    },
+};
+
+use crate::{
+    components::health::Health,
+    entities::{camera::MainCamera, spaceship::IsPlayer},
+    model::settings::{PostProcessingSetting, Settings},
+};
+
+use super::lensing::LensingLabel;
+
+/// Aberration added at full [`DamageFeedback`]
+const DAMAGE_ABERRATION: f32 = 0.03;
+/// Vignette added at full [`DamageFeedback`]
+const DAMAGE_VIGNETTE: f32 = 0.6;
+/// Feedback per lost fraction of the maximum health
+const DAMAGE_FEEDBACK_SCALE: f32 = 4.0;
+/// How fast the feedback fades, per second
+const DAMAGE_FEEDBACK_DECAY: f32 = 3.0;
+
+/// Shifts the color channels apart towards the edges of the screen.
+#[derive(Component, Clone, Copy)]
+pub struct ChromaticAberration {
+    pub intensity: f32,
+}
+
+impl Default for ChromaticAberration {
+    fn default() -> Self {
+        Self { intensity: 0.002 }
+    }
+}
+
+/// Darkens the corners of the screen.
+#[derive(Component, Clone, Copy)]
+pub struct Vignette {
+    pub intensity: f32,
+    /// Distance from the center, relative to the half diagonal, where the darkening starts
+    pub radius: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            intensity: 0.3,
+            radius: 0.8,
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct Scanlines {
+    pub intensity: f32,
+    /// Lines per screen height
+    pub count: f32,
+}
+
+impl Default for Scanlines {
+    fn default() -> Self {
+        Self {
+            intensity: 0.05,
+            count: 360.0,
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct FilmGrain {
+    pub intensity: f32,
+}
+
+impl Default for FilmGrain {
+    fn default() -> Self {
+        Self { intensity: 0.04 }
+    }
+}
+
+/// Strengthens the aberration and the vignette of a camera for a moment after the player was hit.
+#[derive(Component, Default)]
+pub struct DamageFeedback {
+    /// Between 0 and 1, fades back to 0 over time
+    pub amount: f32,
+}
+
+/// All post effects of a camera, combined into one uniform for the shader.
+#[derive(Component, Clone, Copy, Default, ShaderType)]
+pub struct PostEffectsSettings {
+    aberration: f32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    scanline_intensity: f32,
+    scanline_count: f32,
+    grain_intensity: f32,
+    // WebGL2 requires uniform buffers to be a multiple of 16 bytes
+    _padding: Vec2,
+}
+
+impl ExtractComponent for PostEffectsSettings {
+    type QueryData = (
+        Option<&'static ChromaticAberration>,
+        Option<&'static Vignette>,
+        Option<&'static Scanlines>,
+        Option<&'static FilmGrain>,
+        Option<&'static DamageFeedback>,
+    );
+    type QueryFilter = Or<(
+        With<ChromaticAberration>,
+        With<Vignette>,
+        With<Scanlines>,
+        With<FilmGrain>,
+    )>;
+    type Out = Self;
+
+    fn extract_component(
+        (aberration, vignette, scanlines, grain, damage): QueryItem<'_, Self::QueryData>,
+    ) -> Option<Self> {
+        let damage = damage.map_or(0.0, |damage| damage.amount);
+        Some(Self {
+            aberration: aberration.map_or(0.0, |a| a.intensity + damage * DAMAGE_ABERRATION),
+            vignette_intensity: vignette.map_or(0.0, |v| v.intensity + damage * DAMAGE_VIGNETTE),
+            vignette_radius: vignette.map_or(1.0, |v| v.radius),
+            scanline_intensity: scanlines.map_or(0.0, |s| s.intensity),
+            scanline_count: scanlines.map_or(0.0, |s| s.count),
+            grain_intensity: grain.map_or(0.0, |g| g.intensity),
+            _padding: Vec2::ZERO,
+        })
+    }
+}
+
+/// Adds the effects enabled in the settings to the main camera.
+fn apply_post_processing_setting(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    cameras: Query<Entity, With<MainCamera>>,
+    new_cameras: Query<(), Added<MainCamera>>,
+) {
+    if !settings.is_changed() && new_cameras.is_empty() {
+        return;
+    }
+
+    for camera in &cameras {
+        let mut camera = commands.entity(camera);
+        camera.remove::<(
+            ChromaticAberration,
+            Vignette,
+            Scanlines,
+            FilmGrain,
+            DamageFeedback,
+        )>();
+        match settings.post_processing {
+            PostProcessingSetting::Off => {}
+            PostProcessingSetting::Low => {
+                camera.insert((
+                    ChromaticAberration::default(),
+                    Vignette::default(),
+                    DamageFeedback::default(),
+                ));
+            }
+            PostProcessingSetting::High => {
+                camera.insert((
+                    ChromaticAberration::default(),
+                    Vignette::default(),
+                    Scanlines::default(),
+                    FilmGrain::default(),
+                    DamageFeedback::default(),
+                ));
+            }
+        }
+    }
+}
+
+fn damage_feedback(
+    players: Query<&Health, IsPlayer>,
+    mut cameras: Query<&mut DamageFeedback>,
+    mut last_health: Local<Option<f32>>,
+    time: Res<Time>,
+) {
+    let player = players.get_single().ok();
+    let hit = match (*last_health, player) {
+        (Some(last), Some(health)) if health.health < last => {
+            (last - health.health) / health.max_health * DAMAGE_FEEDBACK_SCALE
+        }
+        _ => 0.0,
+    };
+    *last_health = player.map(|health| health.health);
+
+    let decay = (-DAMAGE_FEEDBACK_DECAY * time.delta_seconds()).exp();
+    for mut feedback in &mut cameras {
+        feedback.amount = ((feedback.amount + hit) * decay).min(1.0);
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct PostEffectsLabel;
+
+#[derive(Default)]
+struct PostEffectsNode;
+
+impl ViewNode for PostEffectsNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static DynamicUniformIndex<PostEffectsSettings>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, settings_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let post_effects_pipeline = world.resource::<PostEffectsPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let pipeline_id = if view_target.is_hdr() {
+            post_effects_pipeline.hdr_pipeline_id
+        } else {
+            post_effects_pipeline.pipeline_id
+        };
+        // The shader may fail to compile on some platforms, the image is left untouched then
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
+            return Ok(());
+        };
+
+        let settings_uniforms = world.resource::<ComponentUniforms<PostEffectsSettings>>();
+        let Some(settings_binding) = settings_uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+        let Some(globals_binding) = world.resource::<GlobalsBuffer>().buffer.binding() else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+
+        let bind_group = render_context.render_device().create_bind_group(
+            "post_effects_bind_group",
+            &post_effects_pipeline.layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &post_effects_pipeline.sampler,
+                settings_binding.clone(),
+                globals_binding.clone(),
+            )),
+        );
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("post_effects_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+struct PostEffectsPipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    pipeline_id: CachedRenderPipelineId,
+    hdr_pipeline_id: CachedRenderPipelineId,
+}
+
+impl FromWorld for PostEffectsPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let layout = render_device.create_bind_group_layout(
+            "post_effects_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    uniform_buffer::<PostEffectsSettings>(true),
+                    uniform_buffer::<GlobalsUniform>(false),
+                ),
+            ),
+        );
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
+
+        let shader = world
+            .resource::<AssetServer>()
+            .load("shaders/post_effects.wgsl");
+
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let queue_pipeline = |format: TextureFormat| {
+            pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+                label: Some("post_effects_pipeline".into()),
+                layout: vec![layout.clone()],
+                vertex: fullscreen_shader_vertex_state(),
+                fragment: Some(FragmentState {
+                    shader: shader.clone(),
+                    shader_defs: vec![],
+                    entry_point: "fragment".into(),
+                    targets: vec![Some(ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                push_constant_ranges: vec![],
+            })
+        };
+        let pipeline_id = queue_pipeline(TextureFormat::bevy_default());
+        let hdr_pipeline_id = queue_pipeline(ViewTarget::TEXTURE_FORMAT_HDR);
+
+        Self {
+            layout,
+            sampler,
+            pipeline_id,
+            hdr_pipeline_id,
+        }
+    }
+}
+
+/// Chromatic aberration, vignette, scanlines and film grain in a single pass after the
+/// gravitational lensing. Which of them are enabled is configured per camera.
+pub struct PostEffectsPlugin;
+
+impl Plugin for PostEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<PostEffectsSettings>::default(),
+            UniformComponentPlugin::<PostEffectsSettings>::default(),
+        ))
+        .add_systems(Update, (apply_post_processing_setting, damage_feedback));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .add_render_graph_node::<ViewNodeRunner<PostEffectsNode>>(Core3d, PostEffectsLabel)
+            .add_render_graph_edges(
+                Core3d,
+                // The lensing node already runs after tonemapping
+                (
+                    LensingLabel,
+                    PostEffectsLabel,
+                    Node3d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.init_resource::<PostEffectsPipeline>();
+    }
+}
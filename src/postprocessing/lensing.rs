@@ -0,0 +1,301 @@
+use bevy::{
+    core_pipeline::{
+        core_3d::graph::{Core3d, Node3d},
+        fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    },
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        camera::CameraUpdateSystem,
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        render_graph::{
+            NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            binding_types::{sampler, texture_2d, uniform_buffer},
+            *,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        view::ViewTarget,
+        RenderApp,
+    },
+};
+
+use crate::entities::camera::MainCamera;
+
+const MAX_LENSES: usize = 8;
+/// How far the light is bent around a lens, relative to its radius
+const LENS_REACH: f32 = 6.0;
+
+/// Bends the light around an entity, like the gravity of a black hole does.
+#[derive(Component, Clone, Copy)]
+pub struct GravitationalLens {
+    /// Radius in world units that the light bends around. The lens only distorts the image, the
+    /// dark core has to be drawn by the entity itself.
+    pub radius: f32,
+    /// 1.0 for a black hole, negative values push the light outwards
+    pub strength: f32,
+}
+
+/// The visible lenses in the screen space of a camera.
+#[derive(Component, Clone, Copy, Default, ExtractComponent, ShaderType)]
+pub struct LensingSettings {
+    /// xy: center in uv coordinates, z: radius relative to the screen height, w: strength
+    lenses: [Vec4; MAX_LENSES],
+    lens_count: u32,
+    aspect_ratio: f32,
+    /// See [`LENS_REACH`]
+    reach: f32,
+}
+
+fn update_lensing_settings(
+    mut commands: Commands,
+    mut cameras: Query<
+        (
+            Entity,
+            &Camera,
+            &GlobalTransform,
+            Option<&mut LensingSettings>,
+        ),
+        With<MainCamera>,
+    >,
+    lenses: Query<(&GlobalTransform, &GravitationalLens)>,
+) {
+    for (entity, camera, camera_transform, settings) in &mut cameras {
+        let Some(viewport_size) = camera.logical_viewport_size() else {
+            continue;
+        };
+        let aspect_ratio = viewport_size.x / viewport_size.y;
+        let screen_scale = Vec2::new(aspect_ratio, 1.0) * 0.5;
+
+        let mut new_settings = LensingSettings {
+            aspect_ratio,
+            reach: LENS_REACH,
+            ..default()
+        };
+
+        for (transform, lens) in &lenses {
+            if new_settings.lens_count as usize >= MAX_LENSES {
+                break;
+            }
+            let position = transform.translation();
+            let Some(center) = camera.world_to_ndc(camera_transform, position) else {
+                continue;
+            };
+            let Some(edge) = camera.world_to_ndc(
+                camera_transform,
+                position + camera_transform.right() * lens.radius,
+            ) else {
+                continue;
+            };
+            // Behind the camera or beyond the far plane
+            if !(0.0..=1.0).contains(&center.z) {
+                continue;
+            }
+
+            let radius = ((edge.xy() - center.xy()) * screen_scale).length();
+            let reach = radius * LENS_REACH;
+            let center = center.xy() * screen_scale;
+            if center.x.abs() > screen_scale.x + reach || center.y.abs() > screen_scale.y + reach {
+                continue;
+            }
+
+            let uv = Vec2::new(center.x / aspect_ratio + 0.5, 0.5 - center.y);
+            new_settings.lenses[new_settings.lens_count as usize] =
+                Vec4::new(uv.x, uv.y, radius, lens.strength);
+            new_settings.lens_count += 1;
+        }
+
+        match settings {
+            Some(mut settings) => *settings = new_settings,
+            None => {
+                commands.entity(entity).insert(new_settings);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub(super) struct LensingLabel;
+
+#[derive(Default)]
+struct LensingNode;
+
+impl ViewNode for LensingNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static LensingSettings,
+        &'static DynamicUniformIndex<LensingSettings>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, settings, settings_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        if settings.lens_count == 0 {
+            return Ok(());
+        }
+
+        let lensing_pipeline = world.resource::<LensingPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let pipeline_id = if view_target.is_hdr() {
+            lensing_pipeline.hdr_pipeline_id
+        } else {
+            lensing_pipeline.pipeline_id
+        };
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
+            return Ok(());
+        };
+
+        let settings_uniforms = world.resource::<ComponentUniforms<LensingSettings>>();
+        let Some(settings_binding) = settings_uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+
+        let bind_group = render_context.render_device().create_bind_group(
+            "lensing_bind_group",
+            &lensing_pipeline.layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &lensing_pipeline.sampler,
+                settings_binding.clone(),
+            )),
+        );
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("lensing_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+struct LensingPipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    pipeline_id: CachedRenderPipelineId,
+    hdr_pipeline_id: CachedRenderPipelineId,
+}
+
+impl FromWorld for LensingPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let layout = render_device.create_bind_group_layout(
+            "lensing_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    uniform_buffer::<LensingSettings>(true),
+                ),
+            ),
+        );
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
+
+        let shader = world.resource::<AssetServer>().load("shaders/lensing.wgsl");
+
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let queue_pipeline = |format: TextureFormat| {
+            pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+                label: Some("lensing_pipeline".into()),
+                layout: vec![layout.clone()],
+                vertex: fullscreen_shader_vertex_state(),
+                fragment: Some(FragmentState {
+                    shader: shader.clone(),
+                    shader_defs: vec![],
+                    entry_point: "fragment".into(),
+                    targets: vec![Some(ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                push_constant_ranges: vec![],
+            })
+        };
+        let pipeline_id = queue_pipeline(TextureFormat::bevy_default());
+        let hdr_pipeline_id = queue_pipeline(ViewTarget::TEXTURE_FORMAT_HDR);
+
+        Self {
+            layout,
+            sampler,
+            pipeline_id,
+            hdr_pipeline_id,
+        }
+    }
+}
+
+/// Distorts the screen around [`GravitationalLens`]es.
+pub struct LensingPlugin;
+
+impl Plugin for LensingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<LensingSettings>::default(),
+            UniformComponentPlugin::<LensingSettings>::default(),
+        ))
+        .add_systems(
+            PostUpdate,
+            update_lensing_settings
+                .after(TransformSystem::TransformPropagate)
+                .after(CameraUpdateSystem),
+        );
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .add_render_graph_node::<ViewNodeRunner<LensingNode>>(Core3d, LensingLabel)
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::Tonemapping,
+                    LensingLabel,
+                    Node3d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.init_resource::<LensingPipeline>();
+    }
+}
@@ -1,227 +1,415 @@
 use bevy::{
-    core_pipeline::{core_3d, fullscreen_vertex_shader::fullscreen_shader_vertex_state}, ecs::query::QueryItem, prelude::*, render::{
-        extract_component::ComponentUniforms, render_graph::{
-            NodeRunError, RenderGraphApp, RenderGraphContext, ViewNode, ViewNodeRunner
-        }, render_resource::{
-            BindGroupEntries, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, CachedRenderPipelineId, ColorTargetState, ColorWrites, FragmentState, MultisampleState, Operations, PipelineCache, PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages, TextureFormat, TextureSampleType, TextureViewDimension
-        }, renderer::{RenderContext, RenderDevice}, texture::BevyDefault, view::{ViewDepthTexture, ViewTarget}, RenderApp
-    }
+    core_pipeline::{
+        core_3d::graph::{Core3d, Node3d},
+        fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+        prepass::ViewPrepassTextures,
+    },
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        render_graph::{
+            NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            binding_types::{
+                sampler, texture_2d, texture_2d_multisampled, texture_depth_2d,
+                texture_depth_2d_multisampled, uniform_buffer,
+            },
+            *,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        view::{Msaa, ViewTarget},
+        RenderApp,
+    },
 };
+use bevy_mod_outline::OutlineVolume;
 
-pub struct OutlinePlugin;
+use crate::{entities::camera::MainCamera, model::settings::Settings};
 
-impl Plugin for OutlinePlugin {
-    fn build(&self, app: &mut App) {
+use super::lensing::LensingLabel;
 
-        // We need to get the render app from the main app
-        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
-            return;
-        };
+/// Width of the outline of highlighted entities
+const HIGHLIGHT_WIDTH: f32 = 4.0;
 
-        render_app
-            // Bevy's renderer uses a render graph which is a collection of nodes in a directed acyclic graph.
-            // It currently runs on each view/camera and executes each node in the specified order.
-            // It will make sure that any node that needs a dependency from another node
-            // only runs when that dependency is done.
-            //
-            // Each node can execute arbitrary work, but it generally runs at least one render pass.
-            // A node only has access to the render world, so if you need data from the main world
-            // you need to extract it manually or with the plugin like above.
-            // Add a [`Node`] to the [`RenderGraph`]
-            // The Node needs to impl FromWorld
-            //
-            // The [`ViewNodeRunner`] is a special [`Node`] that will automatically run the node for each view
-            // matching the [`ViewQuery`]
-            .add_render_graph_node::<ViewNodeRunner<PostProcessNode>>(
-                // Specify the name of the graph, in this case we want the graph for 3d
-                core_3d::graph::NAME,
-                // It also needs the name of the node
-                PostProcessNode::NAME,
-            )
-            .add_render_graph_edges(
-                core_3d::graph::NAME,
-                // Specify the node ordering.
-                // This will automatically create all required node edges to enforce the given ordering.
-                &[
-                    core_3d::graph::node::TONEMAPPING,
-                    PostProcessNode::NAME,
-                    core_3d::graph::node::END_MAIN_PASS_POST_PROCESSING,
-                ],
-            );
+/// Draws the silhouettes of everything a camera sees in a single pass, by finding edges in the
+/// depth and normal prepass. Replaces the black [`OutlineVolume`]s, which need an extra draw
+/// call per mesh.
+#[derive(Component, Clone, Copy)]
+pub struct ScreenSpaceOutline {
+    pub color: Color,
+    /// Width of the lines in pixels
+    pub thickness: f32,
+    /// Relative depth difference between neighbouring pixels that counts as an edge
+    pub depth_threshold: f32,
+    /// Difference between the normals of neighbouring pixels that counts as an edge
+    pub normal_threshold: f32,
+}
+
+impl Default for ScreenSpaceOutline {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+            thickness: 2.0,
+            depth_threshold: 0.1,
+            normal_threshold: 0.4,
+        }
     }
+}
 
-    fn finish(&self, app: &mut App) {
-        // We need to get the render app from the main app
-        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
-            return;
+#[derive(Component, Clone, Copy, ShaderType)]
+pub struct ScreenSpaceOutlineUniform {
+    color: Vec4,
+    thickness: f32,
+    depth_threshold: f32,
+    normal_threshold: f32,
+    // WebGL2 requires uniform buffers to be a multiple of 16 bytes
+    _padding: f32,
+}
+
+impl ExtractComponent for ScreenSpaceOutline {
+    type QueryData = &'static Self;
+    type QueryFilter = ();
+    type Out = ScreenSpaceOutlineUniform;
+
+    fn extract_component(outline: QueryItem<'_, Self::QueryData>) -> Option<Self::Out> {
+        Some(ScreenSpaceOutlineUniform {
+            color: outline.color.to_linear().to_vec4(),
+            thickness: outline.thickness,
+            depth_threshold: outline.depth_threshold,
+            normal_threshold: outline.normal_threshold,
+            _padding: 0.0,
+        })
+    }
+}
+
+/// Outlines an entity in a color. Colored outlines are drawn per mesh in both outline modes, so
+/// they work as a mask that makes targets stand out.
+#[derive(Component, Clone, Copy)]
+pub struct OutlineHighlight {
+    pub color: Color,
+}
+
+/// The outline a highlighted entity had before, so it can be restored
+#[derive(Component)]
+struct OriginalOutline(OutlineVolume);
+
+/// Width of a black outline before it was scaled by the graphics preset
+#[derive(Component)]
+struct BaseOutlineWidth(f32);
+
+fn apply_outline_setting(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    cameras: Query<Entity, With<MainCamera>>,
+    new_cameras: Query<(), Added<MainCamera>>,
+) {
+    if !settings.is_changed() && new_cameras.is_empty() {
+        return;
+    }
+
+    for camera in &cameras {
+        if settings.screen_space_outline() {
+            let default = ScreenSpaceOutline::default();
+            commands.entity(camera).insert(ScreenSpaceOutline {
+                thickness: default.thickness * settings.graphics.outline_scale(),
+                ..default
+            });
+        } else {
+            commands.entity(camera).remove::<ScreenSpaceOutline>();
+        }
+    }
+}
+
+fn outline_highlight(
+    mut commands: Commands,
+    mut highlighted: Query<
+        (
+            Entity,
+            &mut OutlineVolume,
+            &OutlineHighlight,
+            Has<OriginalOutline>,
+        ),
+        Changed<OutlineHighlight>,
+    >,
+    mut restored: Query<(&mut OutlineVolume, &OriginalOutline), Without<OutlineHighlight>>,
+    mut removed: RemovedComponents<OutlineHighlight>,
+) {
+    for (entity, mut volume, highlight, has_original) in &mut highlighted {
+        if !has_original {
+            commands
+                .entity(entity)
+                .insert(OriginalOutline(volume.clone()));
+        }
+        *volume = OutlineVolume {
+            visible: true,
+            colour: highlight.color,
+            width: HIGHLIGHT_WIDTH,
         };
+    }
 
-        render_app
-            // Initialize the pipeline
-            .init_resource::<PostProcessPipeline>();
+    for entity in removed.read() {
+        let Ok((mut volume, original)) = restored.get_mut(entity) else {
+            continue;
+        };
+        *volume = original.0.clone();
+        commands.entity(entity).remove::<OriginalOutline>();
     }
 }
 
-struct PostProcessNode;
+/// Scales the black outlines by the graphics preset. Colored outlines are left as they are, so
+/// they stay visible.
+fn outline_width(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut new_volumes: Query<
+        (Entity, &mut OutlineVolume),
+        (Added<OutlineVolume>, Without<BaseOutlineWidth>),
+    >,
+    mut volumes: Query<(&mut OutlineVolume, &BaseOutlineWidth), Without<OutlineHighlight>>,
+) {
+    let scale = settings.graphics.outline_scale();
+    for (entity, mut volume) in &mut new_volumes {
+        if volume.colour != Color::BLACK {
+            continue;
+        }
+        commands
+            .entity(entity)
+            .insert(BaseOutlineWidth(volume.width));
+        volume.width *= scale;
+    }
 
-impl PostProcessNode {
-    const NAME: &'static str = "outline";
+    if !settings.is_changed() {
+        return;
+    }
+    for (mut volume, base) in &mut volumes {
+        volume.width = base.0 * scale;
+    }
 }
 
-impl FromWorld for PostProcessNode {
-    fn from_world(_world: &mut World) -> Self {
-        Self
+/// Hides the black per-mesh outlines while the screen-space outline draws them instead.
+fn outline_volume_visibility(settings: Res<Settings>, mut volumes: Query<&mut OutlineVolume>) {
+    let mesh_outlines = !settings.screen_space_outline();
+    for mut volume in &mut volumes {
+        if !settings.is_changed() && !volume.is_changed() {
+            continue;
+        }
+        let visible = mesh_outlines || volume.colour != Color::BLACK;
+        if volume.visible != visible {
+            volume.visible = visible;
+        }
     }
 }
 
-impl ViewNode for PostProcessNode {
-    type ViewQuery = &'static ViewDepthTexture;
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct OutlineLabel;
+
+#[derive(Default)]
+struct OutlineNode;
+
+impl ViewNode for OutlineNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static ViewPrepassTextures,
+        &'static DynamicUniformIndex<ScreenSpaceOutlineUniform>,
+    );
 
     fn run(
         &self,
         _graph: &mut RenderGraphContext,
         render_context: &mut RenderContext,
-        view_target: QueryItem<Self::ViewQuery>,
+        (view_target, prepass_textures, settings_index): QueryItem<Self::ViewQuery>,
         world: &World,
     ) -> Result<(), NodeRunError> {
-        // Get the pipeline resource that contains the global data we need
-        // to create the render pipeline
-        let post_process_pipeline = world.resource::<PostProcessPipeline>();
+        // The prepasses are disabled on WebGL
+        let (Some(depth), Some(normal)) = (
+            prepass_textures.depth_view(),
+            prepass_textures.normal_view(),
+        ) else {
+            return Ok(());
+        };
 
-        // The pipeline cache is a cache of all previously created pipelines.
-        // It is required to avoid creating a new pipeline each frame,
-        // which is expensive due to shader compilation.
+        let outline_pipeline = world.resource::<OutlinePipeline>();
+        let multisampled = world.resource::<Msaa>().samples() > 1;
+        let variant = &outline_pipeline.variants[usize::from(multisampled)];
+        let pipeline_id = if view_target.is_hdr() {
+            variant.hdr_pipeline_id
+        } else {
+            variant.pipeline_id
+        };
         let pipeline_cache = world.resource::<PipelineCache>();
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
+            return Ok(());
+        };
 
-        // Get the pipeline from the cache
-        let Some(pipeline) = pipeline_cache.get_render_pipeline(post_process_pipeline.pipeline_id)
-        else {
+        let settings_uniforms = world.resource::<ComponentUniforms<ScreenSpaceOutlineUniform>>();
+        let Some(settings_binding) = settings_uniforms.uniforms().binding() else {
             return Ok(());
         };
 
-        // This will start a new "post process write", obtaining two texture
-        // views from the view target - a `source` and a `destination`.
-        // `source` is the "current" main texture and you _must_ write into
-        // `destination` because calling `post_process_write()` on the
-        // [`ViewTarget`] will internally flip the [`ViewTarget`]'s main
-        // texture to the `destination` texture. Failing to do so will cause
-        // the current main texture information to be lost.
         let post_process = view_target.post_process_write();
 
-        // The bind_group gets created each frame.
-        //
-        // Normally, you would create a bind_group in the Queue set,
-        // but this doesn't work with the post_process_write().
-        // The reason it doesn't work is because each post_process_write will alternate the source/destination.
-        // The only way to have the correct source/destination for the bind_group
-        // is to make sure you get it during the node execution.
         let bind_group = render_context.render_device().create_bind_group(
-            "post_process_bind_group",
-            &post_process_pipeline.layout,
-            // It's important for this to match the BindGroupLayout defined in the PostProcessPipeline
+            "outline_bind_group",
+            &variant.layout,
             &BindGroupEntries::sequential((
-                // Make sure to use the source view
                 post_process.source,
-                // Use the sampler created for the pipeline
-                &post_process_pipeline.sampler,
+                &outline_pipeline.sampler,
+                depth,
+                normal,
+                settings_binding.clone(),
             )),
         );
 
-        // Begin the render pass
         let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
-            label: Some("post_process_pass"),
+            label: Some("outline_pass"),
             color_attachments: &[Some(RenderPassColorAttachment {
-                // We need to specify the post process destination view here
-                // to make sure we write to the appropriate texture.
                 view: post_process.destination,
                 resolve_target: None,
                 ops: Operations::default(),
             })],
             depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
         });
 
-        // This is mostly just wgpu boilerplate for drawing a fullscreen triangle,
-        // using the pipeline/bind_group created above
         render_pass.set_render_pipeline(pipeline);
-        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
         render_pass.draw(0..3, 0..1);
 
         Ok(())
     }
 }
 
-#[derive(Resource)]
-struct PostProcessPipeline {
+/// The prepass textures are multisampled when MSAA is on, which needs a different layout
+struct OutlinePipelineVariant {
     layout: BindGroupLayout,
-    sampler: Sampler,
     pipeline_id: CachedRenderPipelineId,
+    hdr_pipeline_id: CachedRenderPipelineId,
 }
 
-impl FromWorld for PostProcessPipeline {
+#[derive(Resource)]
+struct OutlinePipeline {
+    sampler: Sampler,
+    /// Without and with MSAA
+    variants: [OutlinePipelineVariant; 2],
+}
+
+impl FromWorld for OutlinePipeline {
     fn from_world(world: &mut World) -> Self {
         let render_device = world.resource::<RenderDevice>();
 
-        let layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("outline_bind_group_layout"), 
-            entries: &[
-                BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Texture {
-                        sample_type: TextureSampleType::Float { filterable: true },
-                        view_dimension: TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                // The sampler that will be used to sample the screen texture
-                BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ]
-        });
+        let create_layout = |multisampled: bool| {
+            if multisampled {
+                render_device.create_bind_group_layout(
+                    "outline_multisampled_bind_group_layout",
+                    &BindGroupLayoutEntries::sequential(
+                        ShaderStages::FRAGMENT,
+                        (
+                            texture_2d(TextureSampleType::Float { filterable: true }),
+                            sampler(SamplerBindingType::Filtering),
+                            texture_depth_2d_multisampled(),
+                            texture_2d_multisampled(TextureSampleType::Float { filterable: false }),
+                            uniform_buffer::<ScreenSpaceOutlineUniform>(true),
+                        ),
+                    ),
+                )
+            } else {
+                render_device.create_bind_group_layout(
+                    "outline_bind_group_layout",
+                    &BindGroupLayoutEntries::sequential(
+                        ShaderStages::FRAGMENT,
+                        (
+                            texture_2d(TextureSampleType::Float { filterable: true }),
+                            sampler(SamplerBindingType::Filtering),
+                            texture_depth_2d(),
+                            texture_2d(TextureSampleType::Float { filterable: false }),
+                            uniform_buffer::<ScreenSpaceOutlineUniform>(true),
+                        ),
+                    ),
+                )
+            }
+        };
 
         let sampler = render_device.create_sampler(&SamplerDescriptor::default());
 
-        let shader: Handle<Shader> = world
-            .resource::<AssetServer>()
-            .load("shaders/outline.wgsl");
-
-        let pipeline_id = world
-            .resource_mut::<PipelineCache>()
-            // This will add the pipeline to the cache and queue it's creation
-            .queue_render_pipeline(RenderPipelineDescriptor {
-                label: Some("outline_pipeline2".into()),
-                layout: vec![layout.clone()],
-                // This will setup a fullscreen triangle for the vertex state
-                vertex: fullscreen_shader_vertex_state(),
-                fragment: Some(FragmentState {
-                    shader,
-                    shader_defs: vec![],
-                    // Make sure this matches the entry point of your shader.
-                    // It can be anything as long as it matches here and in the shader.
-                    entry_point: "fragment".into(),
-                    targets: vec![Some(ColorTargetState {
-                        format: TextureFormat::bevy_default(),
-                        blend: None,
-                        write_mask: ColorWrites::ALL,
-                    })],
-                }),
-                // All of the following properties are not important for this effect so just use the default values.
-                // This struct doesn't have the Default trait implemented because not all field can have a default value.
-                primitive: PrimitiveState::default(),
-                depth_stencil: None,
-                multisample: MultisampleState::default(),
-                push_constant_ranges: vec![],
-            });
+        let shader = world.resource::<AssetServer>().load("shaders/outline.wgsl");
 
-        Self {
-            pipeline_id, 
-            sampler, 
-            layout, 
-        }
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let variants = [false, true].map(|multisampled| {
+            let layout = create_layout(multisampled);
+            let queue_pipeline = |format: TextureFormat| {
+                pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some("outline_pipeline".into()),
+                    layout: vec![layout.clone()],
+                    vertex: fullscreen_shader_vertex_state(),
+                    fragment: Some(FragmentState {
+                        shader: shader.clone(),
+                        shader_defs: if multisampled {
+                            vec!["MULTISAMPLED".into()]
+                        } else {
+                            vec![]
+                        },
+                        entry_point: "fragment".into(),
+                        targets: vec![Some(ColorTargetState {
+                            format,
+                            blend: None,
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                    push_constant_ranges: vec![],
+                })
+            };
+            OutlinePipelineVariant {
+                pipeline_id: queue_pipeline(TextureFormat::bevy_default()),
+                hdr_pipeline_id: queue_pipeline(ViewTarget::TEXTURE_FORMAT_HDR),
+                layout,
+            }
+        });
+
+        Self { sampler, variants }
     }
-}
\ No newline at end of file
+}
+
+/// Switches between the per-mesh outlines of `bevy_mod_outline` and a [`ScreenSpaceOutline`],
+/// depending on the settings.
+pub struct OutlinePlugin;
+
+impl Plugin for OutlinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<ScreenSpaceOutline>::default(),
+            UniformComponentPlugin::<ScreenSpaceOutlineUniform>::default(),
+        ))
+        .add_systems(
+            Update,
+            (
+                apply_outline_setting,
+                (outline_highlight, outline_width, outline_volume_visibility).chain(),
+            ),
+        );
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .add_render_graph_node::<ViewNodeRunner<OutlineNode>>(Core3d, OutlineLabel)
+            .add_render_graph_edges(Core3d, (Node3d::Tonemapping, OutlineLabel, LensingLabel));
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.init_resource::<OutlinePipeline>();
+    }
+}
@@ -2,6 +2,7 @@ pub mod exhaust_test;
 pub mod loading_screen;
 pub mod main_scene;
 pub mod pause;
+pub mod photo_mode;
 pub mod start_screen;
 
 use bevy::app::{App, Plugin};
@@ -32,6 +33,8 @@ pub enum PausedState {
     Paused,
     #[default]
     Running,
+    /// Frozen like [`PausedState::Paused`], with a free camera instead of the pause menu
+    PhotoMode,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash, Default, States, Copy)]
@@ -140,6 +143,7 @@ impl Plugin for StatesPlugin {
             .init_state::<StartScreenState>()
             .add_plugins((
                 pause::PausePlugin,
+                photo_mode::PhotoModePlugin,
                 loading_screen::LoadingScreenPlugin,
                 start_screen::StartScreenPlugin,
                 main_scene::MainScenePlugin,
@@ -34,9 +34,7 @@ fn setup_shield(
     mut materials: ResMut<Assets<ShieldMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
-    let material = materials.add(ShieldMaterial {
-        color: Srgba::hex("2ae0ed0f").unwrap().into(),
-    });
+    let material = materials.add(ShieldMaterial::new(Srgba::hex("2ae0ed0f").unwrap().into()));
     commands.spawn(MaterialMeshBundle {
         mesh: meshes.add(Sphere { radius: 2. }),
         material,
@@ -7,7 +7,7 @@ use crate::{
 use bevy::prelude::*;
 use bevy_rapier3d::plugin::RapierConfiguration;
 
-use super::{game_paused, pause_physics, resume_physics, PausedState};
+use super::{game_paused, pause_physics, photo_mode::PhotoModeButton, resume_physics, PausedState};
 
 #[derive(Component)]
 pub struct PauseScreen;
@@ -72,6 +72,11 @@ fn on_pause(
                 ControlsButton,
             ));
 
+            c.spawn((
+                TextButtonBundle::from_section(t!("photo_mode"), text_style.clone()),
+                PhotoModeButton,
+            ));
+
             c.spawn((
                 TextButtonBundle::from_section(t!("quit"), text_style.clone()),
                 QuitButton,
@@ -0,0 +1,432 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    core_pipeline::dof::{DepthOfFieldMode, DepthOfFieldSettings},
+    input::mouse::{MouseMotion, MouseWheel},
+    prelude::*,
+    render::view::{
+        screenshot::ScreenshotManager, ColorGrading, ColorGradingGlobal, ColorGradingSection,
+    },
+    window::PrimaryWindow,
+};
+use bevy_rapier3d::plugin::RapierConfiguration;
+use cfg_if::cfg_if;
+
+use crate::{
+    entities::camera::MainCamera,
+    ui::{fonts::FontsResource, theme::text_body_style},
+};
+
+use super::{game_paused, pause_physics, DespawnOnCleanup, PausedState};
+
+#[cfg(not(target_family = "wasm"))]
+const SCREENSHOT_DIR: &str = "screenshots";
+
+const MOVE_SPEED: f32 = 30.0;
+/// Speed multiplier while shift is held
+const FAST_MOVE_FACTOR: f32 = 4.0;
+/// Radians per second when looking around with the keyboard
+const LOOK_SPEED: f32 = 1.5;
+/// Radians per pixel when looking around with the mouse
+const MOUSE_SENSITIVITY: f32 = 0.003;
+const ROLL_SPEED: f32 = 1.0;
+const MIN_FOV: f32 = 10.0_f32.to_radians();
+const MAX_FOV: f32 = 120.0_f32.to_radians();
+/// Radians per line scrolled
+const FOV_STEP: f32 = 2.0_f32.to_radians();
+/// Factor the focal distance changes by per second
+const FOCUS_SPEED: f32 = 2.0;
+const MIN_FOCAL_DISTANCE: f32 = 1.0;
+const MAX_FOCAL_DISTANCE: f32 = 1000.0;
+
+/// Hidden while photo mode is active, like the hud and the minimap
+#[derive(Component)]
+pub struct HideInPhotoMode;
+
+#[derive(Component)]
+pub struct PhotoModeButton;
+
+#[derive(Component)]
+struct PhotoModeHelp;
+
+/// The visibility an entity had before it was hidden by photo mode
+#[derive(Component)]
+struct HiddenByPhotoMode(Visibility);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum PhotoFilter {
+    #[default]
+    None,
+    Warm,
+    Cool,
+    Noir,
+    Vivid,
+}
+
+impl PhotoFilter {
+    fn next(self) -> Self {
+        match self {
+            Self::None => Self::Warm,
+            Self::Warm => Self::Cool,
+            Self::Cool => Self::Noir,
+            Self::Noir => Self::Vivid,
+            Self::Vivid => Self::None,
+        }
+    }
+
+    /// `None` keeps the color grading of the game
+    fn color_grading(self) -> Option<ColorGrading> {
+        let (global, section) = match self {
+            Self::None => return None,
+            Self::Warm => (
+                ColorGradingGlobal {
+                    temperature: 0.3,
+                    post_saturation: 1.1,
+                    ..default()
+                },
+                ColorGradingSection::default(),
+            ),
+            Self::Cool => (
+                ColorGradingGlobal {
+                    temperature: -0.3,
+                    tint: 0.05,
+                    ..default()
+                },
+                ColorGradingSection::default(),
+            ),
+            Self::Noir => (
+                ColorGradingGlobal {
+                    post_saturation: 0.0,
+                    ..default()
+                },
+                ColorGradingSection {
+                    contrast: 1.3,
+                    ..default()
+                },
+            ),
+            Self::Vivid => (
+                ColorGradingGlobal {
+                    post_saturation: 1.4,
+                    ..default()
+                },
+                ColorGradingSection {
+                    contrast: 1.1,
+                    ..default()
+                },
+            ),
+        };
+        Some(ColorGrading::with_identical_sections(global, section))
+    }
+}
+
+/// Exists while photo mode is active. Keeps what has to be restored once it is left.
+#[derive(Resource)]
+struct PhotoMode {
+    camera_transform: Transform,
+    fov: f32,
+    color_grading: ColorGrading,
+    /// Whether the virtual time was already paused, so leaving photo mode must not resume it
+    time_was_paused: bool,
+    yaw: f32,
+    pitch: f32,
+    roll: f32,
+    filter: PhotoFilter,
+    focal_distance: f32,
+    depth_of_field: bool,
+}
+
+fn photo_mode_button(
+    mut next_state: ResMut<NextState<PausedState>>,
+    query: Query<&Interaction, (Changed<Interaction>, With<PhotoModeButton>)>,
+) {
+    for interaction in &query {
+        if *interaction == Interaction::Pressed {
+            next_state.set(PausedState::PhotoMode);
+        }
+    }
+}
+
+fn enter_photo_mode(
+    mut commands: Commands,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut time: ResMut<Time<Virtual>>,
+    mut hidden: Query<(Entity, &mut Visibility), With<HideInPhotoMode>>,
+    cameras: Query<(&Transform, &Projection, &ColorGrading), With<MainCamera>>,
+    font_res: Res<FontsResource>,
+) {
+    // Leaving the pause menu resumes the physics
+    pause_physics(&mut rapier_config);
+    let time_was_paused = time.is_paused();
+    time.pause();
+
+    for (entity, mut visibility) in &mut hidden {
+        commands
+            .entity(entity)
+            .insert(HiddenByPhotoMode(*visibility));
+        *visibility = Visibility::Hidden;
+    }
+
+    let Ok((transform, projection, color_grading)) = cameras.get_single() else {
+        return;
+    };
+    let Projection::Perspective(perspective) = projection else {
+        return;
+    };
+    let (yaw, pitch, roll) = transform.rotation.to_euler(EulerRot::YXZ);
+    commands.insert_resource(PhotoMode {
+        camera_transform: *transform,
+        fov: perspective.fov,
+        color_grading: color_grading.clone(),
+        time_was_paused,
+        yaw,
+        pitch,
+        roll,
+        filter: PhotoFilter::None,
+        focal_distance: transform.translation.y.max(MIN_FOCAL_DISTANCE),
+        depth_of_field: false,
+    });
+
+    commands.spawn((
+        PhotoModeHelp,
+        DespawnOnCleanup,
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(20.),
+                bottom: Val::Px(20.),
+                max_width: Val::Percent(60.),
+                ..default()
+            },
+            background_color: Color::srgba(0., 0., 0., 0.5).into(),
+            ..TextBundle::from_section(
+                t!("photo_mode_help"),
+                TextStyle {
+                    font_size: 20.,
+                    ..text_body_style(&font_res)
+                },
+            )
+        },
+    ));
+}
+
+fn exit_photo_mode(
+    mut commands: Commands,
+    photo_mode: Option<Res<PhotoMode>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut hidden: Query<(Entity, &mut Visibility, &HiddenByPhotoMode)>,
+    mut cameras: Query<
+        (Entity, &mut Transform, &mut Projection, &mut ColorGrading),
+        With<MainCamera>,
+    >,
+    help: Query<Entity, With<PhotoModeHelp>>,
+) {
+    for (entity, mut visibility, previous) in &mut hidden {
+        *visibility = previous.0;
+        commands.entity(entity).remove::<HiddenByPhotoMode>();
+    }
+    for entity in &help {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Some(photo_mode) = photo_mode else {
+        time.unpause();
+        return;
+    };
+    if !photo_mode.time_was_paused {
+        time.unpause();
+    }
+    for (camera, mut transform, mut projection, mut color_grading) in &mut cameras {
+        *transform = photo_mode.camera_transform;
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov = photo_mode.fov;
+        }
+        *color_grading = photo_mode.color_grading.clone();
+        commands.entity(camera).remove::<DepthOfFieldSettings>();
+    }
+    commands.remove_resource::<PhotoMode>();
+}
+
+fn fly_camera(
+    mut photo_mode: ResMut<PhotoMode>,
+    mut cameras: Query<&mut Transform, With<MainCamera>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    time: Res<Time<Real>>,
+) {
+    let Ok(mut transform) = cameras.get_single_mut() else {
+        return;
+    };
+    let delta = time.delta_seconds();
+    let axis = |positive: KeyCode, negative: KeyCode| {
+        keyboard_input.pressed(positive) as i32 as f32
+            - keyboard_input.pressed(negative) as i32 as f32
+    };
+
+    let mut look = Vec2::new(
+        axis(KeyCode::ArrowLeft, KeyCode::ArrowRight),
+        axis(KeyCode::ArrowUp, KeyCode::ArrowDown),
+    ) * LOOK_SPEED
+        * delta;
+    // Holding the right mouse button looks around with the mouse
+    let mouse_delta = mouse_motion.read().map(|motion| motion.delta).sum::<Vec2>();
+    if mouse_input.pressed(MouseButton::Right) {
+        look -= mouse_delta * MOUSE_SENSITIVITY;
+    }
+
+    let photo_mode = photo_mode.as_mut();
+    photo_mode.yaw += look.x;
+    photo_mode.pitch = (photo_mode.pitch + look.y).clamp(-FRAC_PI_2, FRAC_PI_2);
+    photo_mode.roll += axis(KeyCode::KeyQ, KeyCode::KeyE) * ROLL_SPEED * delta;
+    transform.rotation = Quat::from_euler(
+        EulerRot::YXZ,
+        photo_mode.yaw,
+        photo_mode.pitch,
+        photo_mode.roll,
+    );
+
+    let speed = if keyboard_input.pressed(KeyCode::ShiftLeft) {
+        MOVE_SPEED * FAST_MOVE_FACTOR
+    } else {
+        MOVE_SPEED
+    };
+    let movement = *transform.forward() * axis(KeyCode::KeyW, KeyCode::KeyS)
+        + *transform.right() * axis(KeyCode::KeyD, KeyCode::KeyA)
+        + Vec3::Y * axis(KeyCode::Space, KeyCode::ControlLeft);
+    transform.translation += movement.normalize_or_zero() * speed * delta;
+}
+
+fn photo_controls(
+    mut commands: Commands,
+    mut photo_mode: ResMut<PhotoMode>,
+    mut cameras: Query<(Entity, &mut Projection, &mut ColorGrading), With<MainCamera>>,
+    mut help: Query<&mut Visibility, With<PhotoModeHelp>>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time<Real>>,
+) {
+    let Ok((camera, mut projection, mut color_grading)) = cameras.get_single_mut() else {
+        return;
+    };
+
+    let scroll = mouse_wheel.read().map(|event| event.y).sum::<f32>();
+    if let Projection::Perspective(perspective) = projection.as_mut()
+        && scroll != 0.0
+    {
+        perspective.fov = (perspective.fov - scroll * FOV_STEP).clamp(MIN_FOV, MAX_FOV);
+    }
+
+    if keyboard_input.just_pressed(KeyCode::KeyC) {
+        photo_mode.filter = photo_mode.filter.next();
+        *color_grading = photo_mode
+            .filter
+            .color_grading()
+            .unwrap_or_else(|| photo_mode.color_grading.clone());
+    }
+
+    if keyboard_input.just_pressed(KeyCode::KeyH) {
+        for mut visibility in &mut help {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+
+    let toggled_depth_of_field = keyboard_input.just_pressed(KeyCode::KeyF);
+    if toggled_depth_of_field {
+        photo_mode.depth_of_field = !photo_mode.depth_of_field;
+        if !photo_mode.depth_of_field {
+            commands.entity(camera).remove::<DepthOfFieldSettings>();
+        }
+    }
+    let mut focal_distance = photo_mode.focal_distance;
+    if keyboard_input.pressed(KeyCode::BracketRight) {
+        focal_distance *= FOCUS_SPEED.powf(time.delta_seconds());
+    }
+    if keyboard_input.pressed(KeyCode::BracketLeft) {
+        focal_distance /= FOCUS_SPEED.powf(time.delta_seconds());
+    }
+    let focal_distance = focal_distance.clamp(MIN_FOCAL_DISTANCE, MAX_FOCAL_DISTANCE);
+    let focus_changed = focal_distance != photo_mode.focal_distance;
+    photo_mode.focal_distance = focal_distance;
+
+    if photo_mode.depth_of_field && (toggled_depth_of_field || focus_changed) {
+        commands.entity(camera).insert(DepthOfFieldSettings {
+            // WebGL does not support the bokeh blur
+            mode: if cfg!(target_family = "wasm") {
+                DepthOfFieldMode::Gaussian
+            } else {
+                DepthOfFieldMode::Bokeh
+            },
+            focal_distance: photo_mode.focal_distance,
+            ..default()
+        });
+    }
+}
+
+fn leave_photo_mode(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<PausedState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(PausedState::Paused);
+    }
+}
+
+/// Where a new screenshot is saved. In the browser, the file is downloaded under this name.
+fn screenshot_path() -> String {
+    cfg_if! {
+        if #[cfg(target_family = "wasm")] {
+            "screenshot.png".to_string()
+        } else {
+            if let Err(e) = std::fs::create_dir_all(SCREENSHOT_DIR) {
+                error!("Failed to create the screenshot directory: {:?}", e);
+            }
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis());
+            format!("{SCREENSHOT_DIR}/screenshot-{timestamp}.png")
+        }
+    }
+}
+
+fn capture_screenshot(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    windows: Query<Entity, With<PrimaryWindow>>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyP) {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    let path = screenshot_path();
+    match screenshot_manager.save_screenshot_to_disk(window, &path) {
+        Ok(()) => info!("Saving screenshot to {}", path),
+        Err(e) => error!("Failed to take a screenshot: {:?}", e),
+    }
+}
+
+/// A free camera for taking screenshots, reachable from the pause menu. The simulation is frozen
+/// and the hud is hidden while it is active.
+pub struct PhotoModePlugin;
+
+impl Plugin for PhotoModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                photo_mode_button.run_if(game_paused()),
+                leave_photo_mode.run_if(in_state(PausedState::PhotoMode)),
+                (fly_camera, photo_controls, capture_screenshot)
+                    .run_if(in_state(PausedState::PhotoMode))
+                    .run_if(resource_exists::<PhotoMode>),
+            ),
+        )
+        .add_systems(OnEnter(PausedState::PhotoMode), enter_photo_mode)
+        .add_systems(OnExit(PausedState::PhotoMode), exit_photo_mode);
+    }
+}
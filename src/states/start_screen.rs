@@ -96,16 +96,22 @@ fn setup_start_screen(
             color: Color::WHITE,
             size: 30.0,
             pos: Vec3::new(-60.0, -10.0, 100.0),
+            orbit: None,
+            moons: Vec::new(),
         },
         PlanetSpawnConfig {
             color: Srgba::hex("11ff22").unwrap().into(),
             size: 25.0,
             pos: Vec3::new(90.0, -20.0, 30.0),
+            orbit: None,
+            moons: Vec::new(),
         },
         PlanetSpawnConfig {
             color: Srgba::hex("3300ff").unwrap().into(),
             size: 50.0,
             pos: Vec3::new(0.0, -20.0, 0.0),
+            orbit: None,
+            moons: Vec::new(),
         },
     ] {
         commands.add(spawn_planet.to_command(config));
@@ -27,6 +27,7 @@ impl Command for ShowControls {
             (t!("shoot"), "Space"),
             (t!("place_turret"), "T"),
             (t!("place_bomb"), "G"),
+            (t!("open_build_mode"), "B"),
             (t!("toggle_auxiliary_drive"), "Shift"), 
         ];
 
@@ -2,7 +2,12 @@ use bevy::{
     color::palettes::css,
     ecs::world::Command,
     prelude::*,
-    render::{mesh::PrimitiveTopology, render_asset::RenderAssetUsages, view::RenderLayers},
+    render::{
+        mesh::PrimitiveTopology,
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        view::RenderLayers,
+    },
     sprite::{Anchor, MaterialMesh2dBundle},
 };
 use bevy_asset_loader::{
@@ -14,36 +19,58 @@ use space_game_common as common;
 use crate::{
     components::health::Health,
     entities::{
-        camera::RENDER_LAYER_2D,
+        camera::{viewport_to_window, RENDER_LAYER_2D},
+        defense::{BuildMode, DefenseType},
+        nebula::InNebula,
+        space_station::{Docked, REPAIR_COST, TURRET_COST, UPGRADE_COST},
         spaceship::{
             bot::Bot,
-            player::{Player, PlayerInventory, PlayerRespawnTimer},
+            player::{
+                Player, PlayerInventory, PlayerRespawnTimer, PlayerTurret, MAX_ACTIVE_TURRETS,
+            },
             IsPlayer, Spaceship,
         },
     },
-    states::{game_running, main_scene::GameTime, AppState, DespawnOnCleanup, ON_GAME_STARTED},
+    states::{
+        game_running, main_scene::GameTime, photo_mode::HideInPhotoMode, AppState,
+        DespawnOnCleanup, ON_GAME_STARTED,
+    },
     utils::{misc::cleanup_system, sets::Set},
 };
 
-use super::{fonts::FontsResource, theme::text_body_style};
+use super::{
+    fonts::FontsResource,
+    theme::{text_body_style, text_title_style_small},
+    ui_card,
+};
 
 #[derive(Component)]
 pub struct HudRootNode;
 
+#[derive(Component, Clone, Copy)]
+pub enum InventoryCounter {
+    Bombs,
+    Turrets,
+    Ore,
+}
+
+#[derive(Component)]
+struct StationMenu;
+
 #[derive(Component)]
-pub struct BombCounter;
+struct BuildMenu;
 
 #[derive(Component)]
-pub struct TurretCounter;
+struct BuildMenuItem(DefenseType);
 
 #[derive(Component)]
 struct HealthBarContent;
 
-fn spawn_inventory_item<C: Component>(
+fn spawn_inventory_item(
     commands: &mut Commands,
     icon: Handle<Image>,
     font_res: &FontsResource,
-    marker: C,
+    counter: InventoryCounter,
 ) -> Entity {
     commands
         .spawn((NodeBundle {
@@ -73,7 +100,7 @@ fn spawn_inventory_item<C: Component>(
 
             c.spawn((
                 TextBundle::from_section("", text_body_style(font_res)),
-                marker,
+                counter,
             ));
         })
         .id()
@@ -83,14 +110,53 @@ const PANEL_WIDTH: f32 = 400.;
 const PANEL_HEIGHT: f32 = 40.;
 const PADDING: f32 = 5.;
 
+/// Draws the icon for the ore counter, a nugget with a lit and a shaded side.
+fn ore_icon_image() -> Image {
+    const SIZE: u32 = 32;
+    const RADIUS: f32 = 14.0;
+    const OUTLINE: f32 = 2.0;
+
+    let data = (0..SIZE * SIZE)
+        .flat_map(|i| {
+            let x = (i % SIZE) as f32 - SIZE as f32 / 2.0 + 0.5;
+            let y = (i / SIZE) as f32 - SIZE as f32 / 2.0 + 0.5;
+            // Distance in a hexagonal metric, so the nugget gets straight edges
+            let distance = (x.abs() * 0.866 + y.abs() * 0.5).max(y.abs());
+            if distance > RADIUS {
+                [0, 0, 0, 0]
+            } else if distance > RADIUS - OUTLINE {
+                [0x3A, 0x2A, 0x12, 255]
+            } else if x + y < 0.0 {
+                [0xF5, 0xC0, 0x4A, 255]
+            } else {
+                [0xC9, 0x8A, 0x2B, 255]
+            }
+        })
+        .collect();
+
+    Image::new(
+        Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
 fn main_hud_setup(
     mut commands: Commands,
     font_resource: Res<FontsResource>,
     ui_assets: Res<UiAssets>,
+    hud_res: Res<HudRes>,
 ) {
     let root = commands
         .spawn((
             HudRootNode,
+            HideInPhotoMode,
             NodeBundle {
                 style: Style {
                     width: Val::Percent(100.),
@@ -178,19 +244,101 @@ fn main_hud_setup(
         &mut commands,
         ui_assets.bomb_icon.clone(),
         &font_resource,
-        BombCounter,
+        InventoryCounter::Bombs,
     );
     let turret_counter = spawn_inventory_item(
         &mut commands,
         ui_assets.turret_icon.clone(),
         &font_resource,
-        TurretCounter,
+        InventoryCounter::Turrets,
+    );
+    let ore_counter = spawn_inventory_item(
+        &mut commands,
+        hud_res.ore_icon.clone(),
+        &font_resource,
+        InventoryCounter::Ore,
     );
 
     commands
         .entity(inventory)
         .add_child(bomb_counter)
-        .add_child(turret_counter);
+        .add_child(turret_counter)
+        .add_child(ore_counter);
+
+    let station_menu = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(15.)),
+                    margin: UiRect::bottom(Val::Px(10.)),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..ui_card()
+            },
+            StationMenu,
+        ))
+        .with_children(|c| {
+            c.spawn(TextBundle::from_section(
+                t!("space_station"),
+                text_title_style_small(&font_resource),
+            ));
+            for item in [
+                t!("repair_station", cost = REPAIR_COST),
+                t!("buy_turret", cost = TURRET_COST),
+                t!("upgrade_station", cost = UPGRADE_COST),
+                t!("undock"),
+            ] {
+                c.spawn(TextBundle::from_section(
+                    item,
+                    text_body_style(&font_resource),
+                ));
+            }
+        })
+        .id();
+
+    let build_menu = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(15.)),
+                    margin: UiRect::bottom(Val::Px(10.)),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..ui_card()
+            },
+            BuildMenu,
+        ))
+        .with_children(|c| {
+            c.spawn(TextBundle::from_section(
+                t!("build_mode"),
+                text_title_style_small(&font_resource),
+            ));
+            for (i, kind) in DefenseType::ALL.into_iter().enumerate() {
+                c.spawn((
+                    TextBundle::from_section(
+                        t!(
+                            "build_item",
+                            key = i + 1,
+                            name = kind.name(),
+                            cost = kind.cost()
+                        ),
+                        text_body_style(&font_resource),
+                    ),
+                    BuildMenuItem(kind),
+                ));
+            }
+            for item in [t!("build_hint"), t!("close_build_mode")] {
+                c.spawn(TextBundle::from_section(
+                    item,
+                    text_body_style(&font_resource),
+                ));
+            }
+        })
+        .id();
 
     let health_bar = commands
         .spawn(NodeBundle {
@@ -233,6 +381,8 @@ fn main_hud_setup(
 
     commands
         .entity(bottom_left)
+        .add_child(station_menu)
+        .add_child(build_menu)
         .add_child(inventory)
         .add_child(health_bar);
 
@@ -256,15 +406,76 @@ fn health_bar_update(
 
 fn inventory_update(
     player_inventory: Res<PlayerInventory>,
-    mut bomb_counter: Query<&mut Text, (With<BombCounter>, Without<TurretCounter>)>,
-    mut turret_counter: Query<&mut Text, (With<TurretCounter>, Without<BombCounter>)>,
+    mut counters: Query<(&mut Text, &InventoryCounter)>,
+    turrets: Query<(), With<PlayerTurret>>,
+    new_turrets: Query<(), Added<PlayerTurret>>,
+    mut removed_turrets: RemovedComponents<PlayerTurret>,
+) {
+    // Turrets are spawned after the inventory changed, so they are detected separately
+    let turrets_changed = removed_turrets.read().count() > 0 || !new_turrets.is_empty();
+    if !player_inventory.is_changed() && !turrets_changed {
+        return;
+    }
+
+    for (mut text, counter) in &mut counters {
+        let count = match counter {
+            InventoryCounter::Bombs => player_inventory.bombs,
+            InventoryCounter::Turrets => {
+                // Show how many turrets are placed, so it is clear why no more can be placed
+                let active = turrets.iter().count();
+                text.sections[0].style.color = if active >= MAX_ACTIVE_TURRETS {
+                    css::RED.into()
+                } else {
+                    Color::WHITE
+                };
+                text.sections[0].value = format!(
+                    "x{} ({}/{})",
+                    player_inventory.turrets, active, MAX_ACTIVE_TURRETS
+                );
+                continue;
+            }
+            InventoryCounter::Ore => player_inventory.ore,
+        };
+        text.sections[0].value = format!("x{}", count);
+    }
+}
+
+fn station_menu_update(
+    player: Query<Has<Docked>, IsPlayer>,
+    mut menus: Query<&mut Visibility, With<StationMenu>>,
+) {
+    let docked = player.get_single().unwrap_or(false);
+    for mut visibility in &mut menus {
+        *visibility = if docked {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn build_menu_update(
+    build_mode: Option<Res<BuildMode>>,
+    mut menus: Query<&mut Visibility, With<BuildMenu>>,
+    mut items: Query<(&mut Text, &BuildMenuItem)>,
 ) {
-    // TODO: simply this
-    for mut text in &mut bomb_counter {
-        text.sections[0].value = format!("x{}", player_inventory.bombs);
+    for mut visibility in &mut menus {
+        *visibility = if build_mode.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
     }
-    for mut text in &mut turret_counter {
-        text.sections[0].value = format!("x{}", player_inventory.turrets);
+
+    let Some(build_mode) = build_mode else {
+        return;
+    };
+    for (mut text, item) in &mut items {
+        text.sections[0].style.color = if item.0 == build_mode.selected {
+            css::GOLD.into()
+        } else {
+            Color::WHITE
+        };
     }
 }
 
@@ -287,6 +498,7 @@ impl Command for SpawnEnemyIndicator {
         world.spawn((
             DespawnOnCleanup,
             EnemyIndicator { enemy: self.enemy },
+            HideInPhotoMode,
             MaterialMesh2dBundle {
                 mesh: res.mesh.clone().into(),
                 material: res.material.clone(),
@@ -306,25 +518,32 @@ pub struct EnemyIndicatorBundle {
 }
 
 fn update_enemy_indicator(
-    transform_query: Query<&Transform, (Without<Player>, Without<EnemyIndicator>)>,
-    player: Query<&Transform, IsPlayer>,
+    transform_query: Query<(&Transform, Has<InNebula>), (Without<Player>, Without<EnemyIndicator>)>,
+    player: Query<(&Transform, Has<InNebula>), IsPlayer>,
     mut indicators: Query<
-        (&mut Transform, &EnemyIndicator, Entity),
+        (&mut Transform, &mut Visibility, &EnemyIndicator, Entity),
         (Without<Player>, Without<Bot>),
     >,
     mut commands: Commands,
 ) {
     const MAX_SCALE: f32 = 20.0;
 
-    let Ok(player_transform) = player.get_single() else {
+    let Ok((player_transform, player_in_nebula)) = player.get_single() else {
         return;
     };
-    for (mut indicator_transform, indicator, entity) in &mut indicators {
-        let Ok(transform) = transform_query.get(indicator.enemy) else {
+    for (mut indicator_transform, mut visibility, indicator, entity) in &mut indicators {
+        let Ok((transform, enemy_in_nebula)) = transform_query.get(indicator.enemy) else {
             commands.entity(entity).despawn_recursive();
             continue;
         };
 
+        // Sensors don't work inside nebulae
+        if player_in_nebula || enemy_in_nebula {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Inherited;
+
         let mut dir = player_transform.translation.xz() - transform.translation.xz();
         dir.x *= -1.;
 
@@ -352,6 +571,17 @@ fn setup_enemy_indicator(
     commands.insert_resource(EnemyIndicatorRes { mesh, material });
 }
 
+fn hud_setup(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    commands.insert_resource(HudRes {
+        ore_icon: images.add(ore_icon_image()),
+    });
+}
+
+#[derive(Resource)]
+struct HudRes {
+    ore_icon: Handle<Image>,
+}
+
 #[derive(Resource)]
 pub struct EnemyIndicatorRes {
     mesh: Handle<Mesh>,
@@ -384,6 +614,8 @@ fn auxiliary_drive_update(
 pub struct Score {
     pub value: u32,
     pub events: Vec<common::ScoreEvent>,
+    /// Ore collected during the match, including ore that was spent or lost
+    pub ore_collected: u32,
 }
 
 impl Score {
@@ -391,6 +623,7 @@ impl Score {
         Self {
             value: 0,
             events: Vec::new(),
+            ore_collected: 0,
         }
     }
 }
@@ -442,6 +675,7 @@ fn score_events(
             warn!("Could not get viewport position for node");
             continue;
         };
+        let screen_pos = viewport_to_window(camera, window, screen_pos);
 
         let pos = Vec2::new(
             screen_pos.x - screen_size.x / 2.0,
@@ -467,6 +701,7 @@ fn score_events(
                 ..default()
             },
             ScoreElement { event: score_event },
+            HideInPhotoMode,
             RenderLayers::layer(RENDER_LAYER_2D),
         ));
     }
@@ -586,7 +821,7 @@ impl Plugin for GameHudPlugin {
                     cleanup_system::<ScoreElement>,
                 ),
             )
-            .add_systems(Startup, setup_enemy_indicator)
+            .add_systems(Startup, (setup_enemy_indicator, hud_setup))
             .add_systems(ON_GAME_STARTED, (main_hud_setup,))
             .add_loading_state(
                 LoadingState::new(AppState::MainSceneLoading).load_collection::<UiAssets>(),
@@ -601,7 +836,9 @@ impl Plugin for GameHudPlugin {
                     score_events.in_set(Set::ScoreEvents),
                     score_element_update,
                     score_update,
-                    inventory_update.run_if(resource_changed::<PlayerInventory>),
+                    inventory_update,
+                    station_menu_update,
+                    build_menu_update,
                     respawn_ui_setup.run_if(resource_added::<PlayerRespawnTimer>),
                     cleanup_system::<RespawnTimerUIParent>
                         .run_if(resource_removed::<PlayerRespawnTimer>()),
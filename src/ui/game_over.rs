@@ -9,7 +9,9 @@ use crate::states::{
     game_over, game_running, reset_physics_speed, slow_down_physics, AppState, DespawnOnCleanup,
 };
 use crate::ui::fonts::FontsResource;
-use crate::ui::theme::{fullscreen_center_style, text_button_style, text_title_style};
+use crate::ui::theme::{
+    fullscreen_center_style, text_body_style, text_button_style, text_title_style,
+};
 use crate::ui::widgets::TextButtonBundle;
 use crate::utils::api::ApiManager;
 use crate::utils::tasks::TaskComponent;
@@ -21,7 +23,10 @@ use super::ui_card;
 use super::widgets::FocusTextInputOnInteraction;
 
 #[derive(Event)]
-pub struct GameOverEvent;
+pub struct GameOverEvent {
+    /// Where the last space station was destroyed
+    pub position: Vec3,
+}
 
 #[derive(Component)]
 struct GameOverScreen;
@@ -96,14 +101,19 @@ fn game_over_screen_setup(
                 text_title_style(&font_res),
             ));
 
+            c.spawn(TextBundle::from_section(
+                t!("score", score = score.value),
+                text_button_style(&font_res),
+            ));
+
             c.spawn(TextBundle {
                 style: Style {
                     margin: UiRect::bottom(Val::Px(20.0)),
                     ..default()
                 },
                 ..TextBundle::from_section(
-                    t!("score", score = score.value),
-                    text_button_style(&font_res),
+                    t!("ore_collected", ore = score.ore_collected),
+                    text_body_style(&font_res),
                 )
             });
 
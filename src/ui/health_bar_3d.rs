@@ -6,7 +6,7 @@ use bevy::{
 use crate::{
     components::health::Health,
     entities::camera::RENDER_LAYER_2D,
-    states::{game_running, DespawnOnCleanup},
+    states::{game_running, photo_mode::HideInPhotoMode, DespawnOnCleanup},
 };
 
 use super::sprite_3d_renderer::Sprite3DObject;
@@ -47,6 +47,7 @@ impl Command for SpawnHealthBar {
         world
             .spawn((
                 DespawnOnCleanup,
+                HideInPhotoMode,
                 HealthBar3dBackground,
                 Sprite3DObject {
                     parent: self.entity,
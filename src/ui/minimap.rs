@@ -1,21 +1,34 @@
 use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::ImageSampler;
 use bevy::render::view::RenderLayers;
 use bevy::sprite::Anchor;
 use bevy::window::WindowResized;
 use bevy_asset_loader::prelude::AssetCollection;
+use rand::Rng;
 use std::f32::consts::PI;
 
 use crate::entities::camera::RENDER_LAYER_2D;
+use crate::entities::nebula::InNebula;
+use crate::entities::spaceship::IsPlayer;
+use crate::states::photo_mode::HideInPhotoMode;
 use crate::states::{game_running, AppState, DespawnOnCleanup, ON_GAME_STARTED};
 use crate::utils::asset_loading::AppExtension;
 
 pub const MINIMAP_RANGE: f32 = 400.;
 pub const MINIMAP_SIZE: f32 = 300.;
 const MINIMAP_PADDING: f32 = 10.;
+/// Size of the static noise in pixels, the texture is twice as large so it can be scrolled
+const STATIC_RESOLUTION: u32 = 96;
 
 #[derive(Component)]
 struct Minimap;
 
+/// Noise covering the minimap while the player is inside a nebula
+#[derive(Component)]
+struct MinimapStatic;
+
 #[derive(Component)]
 struct MinimapObject {
     entity: Entity,
@@ -25,6 +38,8 @@ struct MinimapObject {
 pub struct ShowOnMinimap {
     pub sprite: Handle<Image>,
     pub size: MinimapSize,
+    /// Tint of the sprite
+    pub color: Color,
 }
 
 pub enum MinimapSize {
@@ -53,28 +68,79 @@ fn get_minimap_pos(window_width: f32, window_height: f32) -> Vec3 {
     )
 }
 
-fn setup_minimap(mut commands: Commands, window_query: Query<&Window>) {
+fn static_noise_image() -> Image {
+    let mut rng = rand::thread_rng();
+    let size = STATIC_RESOLUTION * 2;
+    let data = (0..size * size)
+        .flat_map(|_| {
+            let value = rng.gen_range(0..=255);
+            [value, value, value, 255]
+        })
+        .collect();
+
+    let mut image = Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+fn setup_minimap(
+    mut commands: Commands,
+    window_query: Query<&Window>,
+    mut images: ResMut<Assets<Image>>,
+) {
     let Ok(window) = window_query.get_single() else {
         warn!("Could not find a window");
         return;
     };
 
-    commands.spawn((
-        Minimap,
-        DespawnOnCleanup,
-        SpriteBundle {
-            sprite: Sprite {
-                color: Color::BLACK,
-                custom_size: Some(Vec2::splat(MINIMAP_SIZE)),
+    let minimap_static = commands
+        .spawn((
+            MinimapStatic,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::srgba(1.0, 1.0, 1.0, 0.9),
+                    custom_size: Some(Vec2::splat(MINIMAP_SIZE)),
+                    ..default()
+                },
+                texture: images.add(static_noise_image()),
+                // In front of the minimap objects
+                transform: Transform::from_xyz(0., 0., 1.),
+                visibility: Visibility::Hidden,
                 ..default()
             },
-            transform: Transform::from_translation(get_minimap_pos(
-                window.width(),
-                window.height(),
-            )),
-            ..default()
-        },
-    ));
+            RenderLayers::layer(RENDER_LAYER_2D),
+        ))
+        .id();
+
+    commands
+        .spawn((
+            Minimap,
+            DespawnOnCleanup,
+            HideInPhotoMode,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::BLACK,
+                    custom_size: Some(Vec2::splat(MINIMAP_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(get_minimap_pos(
+                    window.width(),
+                    window.height(),
+                )),
+                ..default()
+            },
+        ))
+        .add_child(minimap_static);
 }
 
 fn spawn_minimap_objects(
@@ -104,6 +170,7 @@ fn spawn_minimap_objects(
                             None
                         },
                         anchor: Anchor::Center,
+                        color: show_on_minimap.color,
                         ..default()
                     },
                     transform: Transform::from_scale(Vec3::new(scale, scale, 1.)),
@@ -130,8 +197,33 @@ fn window_resize(
     }
 }
 
+fn update_minimap_static(
+    players: Query<Has<InNebula>, IsPlayer>,
+    mut minimap_static: Query<(&mut Sprite, &mut Visibility), With<MinimapStatic>>,
+) {
+    let in_nebula = players.iter().any(|in_nebula| in_nebula);
+    for (mut sprite, mut visibility) in &mut minimap_static {
+        if !in_nebula {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Inherited;
+
+        // Show a different part of the noise every frame to make it flicker
+        let mut rng = rand::thread_rng();
+        let offset = Vec2::new(
+            rng.gen_range(0..STATIC_RESOLUTION) as f32,
+            rng.gen_range(0..STATIC_RESOLUTION) as f32,
+        );
+        sprite.rect = Some(Rect::from_corners(
+            offset,
+            offset + Vec2::splat(STATIC_RESOLUTION as f32),
+        ));
+    }
+}
+
 fn update_minimap(
-    show_on_minimap_query: Query<&Transform, With<ShowOnMinimap>>,
+    show_on_minimap_query: Query<(&Transform, Has<InNebula>), With<ShowOnMinimap>>,
     mut minimap_objects: Query<
         (&MinimapObject, &mut Transform, &mut Visibility, Entity),
         Without<ShowOnMinimap>,
@@ -139,18 +231,20 @@ fn update_minimap(
     mut commands: Commands,
 ) {
     for (minimap_obj, mut transform, mut visibility, entity) in &mut minimap_objects {
-        let Ok(object_transform) = show_on_minimap_query.get(minimap_obj.entity) else {
+        let Ok((object_transform, in_nebula)) = show_on_minimap_query.get(minimap_obj.entity)
+        else {
             commands.entity(entity).despawn_recursive();
             continue;
         };
 
         let minimap_pos = object_transform.translation / MINIMAP_RANGE * MINIMAP_SIZE;
 
-        if minimap_pos.length() > MINIMAP_SIZE / 2. {
+        // Sensors can't see into nebulae
+        if minimap_pos.length() > MINIMAP_SIZE / 2. || in_nebula {
             *visibility = Visibility::Hidden;
             continue;
         } else if *visibility == Visibility::Hidden {
-            *visibility = Visibility::Visible;
+            *visibility = Visibility::Inherited;
         }
 
         transform.translation = Vec3::new(minimap_pos.x, -minimap_pos.z, 0.);
@@ -186,7 +280,13 @@ impl Plugin for MinimapPlugin {
         .add_systems(ON_GAME_STARTED, setup_minimap)
         .add_systems(
             Update,
-            (update_minimap, spawn_minimap_objects, window_resize).run_if(game_running()),
+            (
+                update_minimap,
+                update_minimap_static,
+                spawn_minimap_objects,
+                window_resize,
+            )
+                .run_if(game_running()),
         );
     }
 }
@@ -1,6 +1,9 @@
 use bevy::{ecs::world::Command, prelude::*, window::PrimaryWindow};
 
-use crate::model::settings::{AntialiasingSetting, Settings, VSyncSetting};
+use crate::model::settings::{
+    AntialiasingSetting, BloomSetting, DynamicResolutionSetting, GraphicsPreset, OutlineSetting,
+    PostProcessingSetting, ScreenShakeSetting, Settings, VSyncSetting,
+};
 
 use super::{
     fonts::FontsResource,
@@ -47,6 +50,24 @@ struct LanguageSetting;
 #[derive(Component)]
 struct AntialiasSetting;
 
+#[derive(Component)]
+struct PostProcessingSettingsItem;
+
+#[derive(Component)]
+struct OutlineSettingsItem;
+
+#[derive(Component)]
+struct BloomSettingsItem;
+
+#[derive(Component)]
+struct GraphicsPresetSettingsItem;
+
+#[derive(Component)]
+struct DynamicResolutionSettingsItem;
+
+#[derive(Component)]
+struct ScreenShakeSettingsItem;
+
 pub struct OpenSettings;
 
 impl Command for OpenSettings {
@@ -69,7 +90,7 @@ impl Command for OpenSettings {
             .with_children(|c| {
                 c.spawn(NodeBundle {
                     style: Style {
-                        height: Val::Px(330.),
+                        height: Val::Px(630.),
                         padding: UiRect::all(Val::Px(15.)),
                         position_type: PositionType::Relative,
                         flex_direction: FlexDirection::Column,
@@ -79,6 +100,23 @@ impl Command for OpenSettings {
                     ..ui_card()
                 })
                 .with_children(|c| {
+                    c.settings_item(false, |c| {
+                        c.spawn(TextBundle::from_section(t!("graphics"), style.clone()));
+
+                        let initial: String = settings.graphics.into();
+                        c.spawn((
+                            TextButtonBundle::from_section(initial, style.clone()),
+                            RotateSetting {
+                                current_index: GraphicsPreset::values()
+                                    .iter()
+                                    .position(|s| s == &settings.graphics)
+                                    .unwrap_or(0),
+                                values: GraphicsPreset::values(),
+                            },
+                            GraphicsPresetSettingsItem,
+                        ));
+                    });
+
                     c.settings_item(false, |c| {
                         c.spawn(TextBundle::from_section(t!("shadows"), style.clone()));
 
@@ -135,6 +173,94 @@ impl Command for OpenSettings {
                         ));
                     });
 
+                    c.settings_item(false, |c| {
+                        c.spawn(TextBundle::from_section(
+                            t!("post_processing"),
+                            style.clone(),
+                        ));
+
+                        let initial: String = settings.post_processing.into();
+                        c.spawn((
+                            TextButtonBundle::from_section(initial, style.clone()),
+                            RotateSetting {
+                                current_index: PostProcessingSetting::values()
+                                    .iter()
+                                    .position(|s| s == &settings.post_processing)
+                                    .unwrap_or(0),
+                                values: PostProcessingSetting::values(),
+                            },
+                            PostProcessingSettingsItem,
+                        ));
+                    });
+
+                    c.settings_item(false, |c| {
+                        c.spawn(TextBundle::from_section(t!("outline"), style.clone()));
+
+                        let initial: String = settings.outline.into();
+                        c.spawn((
+                            TextButtonBundle::from_section(initial, style.clone()),
+                            RotateSetting {
+                                current_index: OutlineSetting::values()
+                                    .iter()
+                                    .position(|s| s == &settings.outline)
+                                    .unwrap_or(0),
+                                values: OutlineSetting::values(),
+                            },
+                            OutlineSettingsItem,
+                        ));
+                    });
+
+                    c.settings_item(false, |c| {
+                        c.spawn(TextBundle::from_section(t!("bloom"), style.clone()));
+
+                        let initial: String = settings.bloom.into();
+                        c.spawn((
+                            TextButtonBundle::from_section(initial, style.clone()),
+                            RotateSetting {
+                                current_index: BloomSetting::values()
+                                    .iter()
+                                    .position(|s| s == &settings.bloom)
+                                    .unwrap_or(0),
+                                values: BloomSetting::values(),
+                            },
+                            BloomSettingsItem,
+                        ));
+                    });
+
+                    c.settings_item(false, |c| {
+                        c.spawn(TextBundle::from_section(
+                            t!("dynamic_resolution"),
+                            style.clone(),
+                        ));
+
+                        let initial: String = settings.dynamic_resolution.into();
+                        c.spawn((
+                            TextButtonBundle::from_section(initial, style.clone()),
+                            RotateSetting {
+                                current_index: if settings.dynamic_resolution.0 { 0 } else { 1 },
+                                values: vec![
+                                    DynamicResolutionSetting(true),
+                                    DynamicResolutionSetting(false),
+                                ],
+                            },
+                            DynamicResolutionSettingsItem,
+                        ));
+                    });
+
+                    c.settings_item(false, |c| {
+                        c.spawn(TextBundle::from_section(t!("screen_shake"), style.clone()));
+
+                        let initial: String = settings.screen_shake.into();
+                        c.spawn((
+                            TextButtonBundle::from_section(initial, style.clone()),
+                            RotateSetting {
+                                current_index: if settings.screen_shake.0 { 0 } else { 1 },
+                                values: vec![ScreenShakeSetting(true), ScreenShakeSetting(false)],
+                            },
+                            ScreenShakeSettingsItem,
+                        ));
+                    });
+
                     c.spawn(TextBundle {
                         style: Style {
                             margin: UiRect::top(Val::Percent(10.)),
@@ -301,11 +427,113 @@ fn update_msaa(
 ) {
     for rotate_setting in &query {
         settings.antialiasing = *rotate_setting.value();
-        let res: Msaa = (*rotate_setting.value()).into();
-        commands.insert_resource(res);
+        commands.insert_resource(settings.msaa());
         debug!("Antialiasing set to: {:?}", settings.antialiasing);
     }
 }
+
+fn update_post_processing(
+    query: Query<
+        &RotateSetting<PostProcessingSetting>,
+        (
+            Changed<RotateSetting<PostProcessingSetting>>,
+            With<PostProcessingSettingsItem>,
+        ),
+    >,
+    mut settings: ResMut<Settings>,
+) {
+    for rotate_setting in &query {
+        settings.post_processing = *rotate_setting.value();
+        debug!("Post processing set to: {:?}", settings.post_processing);
+    }
+}
+
+fn update_outline(
+    query: Query<
+        &RotateSetting<OutlineSetting>,
+        (
+            Changed<RotateSetting<OutlineSetting>>,
+            With<OutlineSettingsItem>,
+        ),
+    >,
+    mut settings: ResMut<Settings>,
+) {
+    for rotate_setting in &query {
+        settings.outline = *rotate_setting.value();
+        debug!("Outline set to: {:?}", settings.outline);
+    }
+}
+
+fn update_bloom(
+    query: Query<
+        &RotateSetting<BloomSetting>,
+        (
+            Changed<RotateSetting<BloomSetting>>,
+            With<BloomSettingsItem>,
+        ),
+    >,
+    mut settings: ResMut<Settings>,
+) {
+    for rotate_setting in &query {
+        settings.bloom = *rotate_setting.value();
+        debug!("Bloom set to: {:?}", settings.bloom);
+    }
+}
+
+fn update_graphics_preset(
+    query: Query<
+        &RotateSetting<GraphicsPreset>,
+        (
+            Changed<RotateSetting<GraphicsPreset>>,
+            With<GraphicsPresetSettingsItem>,
+        ),
+    >,
+    mut settings: ResMut<Settings>,
+    mut commands: Commands,
+) {
+    for rotate_setting in &query {
+        settings.graphics = *rotate_setting.value();
+        commands.insert_resource(settings.msaa());
+        commands.insert_resource(settings.shadow_map());
+        debug!("Graphics preset set to: {:?}", settings.graphics);
+    }
+}
+
+fn update_dynamic_resolution(
+    query: Query<
+        &RotateSetting<DynamicResolutionSetting>,
+        (
+            Changed<RotateSetting<DynamicResolutionSetting>>,
+            With<DynamicResolutionSettingsItem>,
+        ),
+    >,
+    mut settings: ResMut<Settings>,
+) {
+    for rotate_setting in &query {
+        settings.dynamic_resolution = *rotate_setting.value();
+        debug!(
+            "Dynamic resolution set to: {:?}",
+            settings.dynamic_resolution
+        );
+    }
+}
+
+fn update_screen_shake(
+    query: Query<
+        &RotateSetting<ScreenShakeSetting>,
+        (
+            Changed<RotateSetting<ScreenShakeSetting>>,
+            With<ScreenShakeSettingsItem>,
+        ),
+    >,
+    mut settings: ResMut<Settings>,
+) {
+    for rotate_setting in &query {
+        settings.screen_shake = *rotate_setting.value();
+        debug!("Screen shake set to: {:?}", settings.screen_shake);
+    }
+}
+
 fn update_lang(
     query: Query<&RotateSetting<String>, (Changed<RotateSetting<String>>, With<LanguageSetting>)>,
     mut settings: ResMut<Settings>,
@@ -329,9 +557,26 @@ impl Plugin for SettingsPlugin {
                 settings_button,
                 update_msaa,
                 update_vsync,
+                update_post_processing,
+                update_outline,
+                update_bloom,
+                update_graphics_preset,
+                update_dynamic_resolution,
+                update_screen_shake,
+            ),
+        )
+        .add_systems(
+            Update,
+            (
                 rotate_settings_item::<String>,
                 rotate_settings_item::<AntialiasingSetting>,
                 rotate_settings_item::<VSyncSetting>,
+                rotate_settings_item::<PostProcessingSetting>,
+                rotate_settings_item::<OutlineSetting>,
+                rotate_settings_item::<BloomSetting>,
+                rotate_settings_item::<GraphicsPreset>,
+                rotate_settings_item::<DynamicResolutionSetting>,
+                rotate_settings_item::<ScreenShakeSetting>,
             ),
         );
     }
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::utils::sets::Set;
+use crate::{entities::camera::viewport_to_window, utils::sets::Set};
 
 #[derive(Component)]
 pub struct Sprite3DObject {
@@ -35,6 +35,7 @@ fn node_3d_renderer_update(
             warn!("Could not get viewport position for node");
             continue;
         };
+        let screen_pos = viewport_to_window(camera, window, screen_pos);
 
         transform.translation = (Vec2::new(
             screen_pos.x - window.width() / 2.0,
@@ -3,7 +3,7 @@ use std::future::Future;
 use bevy::{
     ecs::world::CommandQueue,
     prelude::*,
-    tasks::{block_on, futures_lite::future, IoTaskPool, Task},
+    tasks::{block_on, futures_lite::future, IoTaskPool, Task, TaskPool},
 };
 use cfg_if::cfg_if;
 
@@ -25,7 +25,20 @@ impl TaskComponent {
     where
         T: Send + 'static,
     {
-        let task_pool = IoTaskPool::get();
+        Self::new_in(IoTaskPool::get(), future, on_complete)
+    }
+
+    /// Like [`TaskComponent::new`], but runs the future on `task_pool`, e.g. the
+    /// `AsyncComputeTaskPool` for work that is not waiting on IO.
+    pub fn new_in<T>(
+        task_pool: &TaskPool,
+        #[cfg(not(target_family = "wasm"))] future: impl Future<Output = T> + 'static + Send,
+        #[cfg(target_family = "wasm")] future: impl Future<Output = T> + 'static,
+        on_complete: impl FnOnce(T, &mut World) + Send + 'static,
+    ) -> TaskComponent
+    where
+        T: Send + 'static,
+    {
         let (tx, rx) = crossbeam_channel::bounded(1);
         let task = task_pool.spawn(async move {
             cfg_if! {